@@ -2,10 +2,7 @@ extern crate storytree_native;
 
 use std::fs;
 
-use storytree_native::{
-    modal::{Dialog, DialogAction},
-    prelude::{WindowBuilder, WindowContext},
-};
+use storytree_native::modal::{Dialog, DialogAction};
 
 fn rgb(color: u32) -> (u8, u8, u8) {
     let color: [u8; 4] = color.to_be_bytes();
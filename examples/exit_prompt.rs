@@ -1,6 +1,6 @@
 extern crate storytree_native;
 
-use storytree_native::{prelude::*, Window};
+use storytree_native::{prelude::*, Window, WindowIcon};
 use storytree_native::event::{App, close, Event, keyboard::{KeyCode, KeyEvent}, quit};
 use storytree_native::modal::{Button, Buttons, Dialog};
 use storytree_native::style::{Background, Theme};
@@ -10,27 +10,21 @@ fn main() {
     .title("Rust Window")
         .theme(Theme::Auto)
         .background(Background::new(0xA35FC1, 0x0B0B0B))
-        .icon("../../assets/images/NativeUI.ico")
+        .icon(WindowIcon::from_path("examples/images/NativeUI.ico").unwrap())
         .show()
         .unwrap();
 
     App::run(|id, event, _| match event {
-        Event::Keyboard(KeyEvent::KeyDown(key)) => match key {
-            KeyCode::Escape => {
-                close(id);
-            }
-            _ => {}
-        },
-        Event::Close => {
+        Event::Keyboard(KeyEvent::KeyDown(KeyCode::Escape)) => close(id),
+        Event::Close
             if Dialog::prompt()
                 .title("Exit Application")
                 .message("Are you sure?")
                 .buttons(Buttons::OkCancel)
                 .show()
-                == Button::Ok
-            {
-                quit(0)
-            }
+                == Button::Ok =>
+        {
+            quit(0)
         }
         _ => {}
     })
@@ -1,10 +1,6 @@
 extern crate storytree_native;
 
-use storytree_native::{
-    modal::Dialog,
-    prelude::WindowBuilder
-    ,
-};
+use storytree_native::modal::Dialog;
 
 fn main() {
     match Dialog::file()
@@ -1,6 +1,6 @@
 extern crate storytree_native;
 
-use storytree_native::{modal::Dialog, prelude::*};
+use storytree_native::modal::Dialog;
 
 fn main() {
     match Dialog::file()
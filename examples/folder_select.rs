@@ -1,15 +1,6 @@
 extern crate storytree_native;
 
-use storytree_native::{
-    event::{
-        Event
-        , keyboard::{KeyCode, KeyEvent},
-    },
-    modal::Dialog,
-    prelude::{WindowBuilder, WindowContext},
-    Window,
-};
-use storytree_native::event::App;
+use storytree_native::modal::Dialog;
 
 fn main() {
     match Dialog::file()
@@ -1,15 +1,6 @@
 extern crate storytree_native;
 
-use storytree_native::{
-    event::{
-        Event
-        , keyboard::{KeyCode, KeyEvent},
-    },
-    modal::{Dialog, FontWeight},
-    prelude::{WindowBuilder, WindowContext},
-    Window,
-};
-use storytree_native::event::App;
+use storytree_native::modal::{Dialog, FontWeight};
 
 fn main() {
     match Dialog::font()
@@ -0,0 +1,31 @@
+extern crate storytree_native;
+
+use storytree_native::event::{App, Event};
+use storytree_native::prelude::*;
+use storytree_native::surface::Surface;
+use storytree_native::Window;
+
+/// Draw a gradient that follows the window size without a GPU or an extra crate
+fn main() {
+    let id = Window::builder()
+        .title("Software Rendering")
+        .size(640, 480)
+        .show()
+        .unwrap();
+
+    let surface = Surface::new(id).unwrap();
+    App::run_with(surface, |_, event, state| {
+        if let Event::Repaint = event {
+            let mut surface = state.as_mut();
+            surface.fit().unwrap();
+            let (width, height) = surface.size();
+            for (index, pixel) in surface.iter_mut().enumerate() {
+                let (x, y) = (index as u32 % width, index as u32 / width);
+                let red = x * 255 / width.max(1);
+                let green = y * 255 / height.max(1);
+                *pixel = 0xFF00_0000 | (red << 16) | (green << 8) | 0x80;
+            }
+            surface.present().unwrap();
+        }
+    });
+}
@@ -4,15 +4,13 @@ use std::fmt::Debug;
 
 use storytree_native::event::App;
 use storytree_native::style::{Background, Theme};
-use storytree_native::toggle_fullscreen;
 use storytree_native::{
     event::{
-        close,
         keyboard::{KeyCode, KeyEvent},
         Event,
     },
     prelude::*,
-    Window,
+    Window, WindowIcon,
 };
 
 /// Controls the state of the modifier keys
@@ -30,7 +28,7 @@ fn main() {
         // Try changing this to `Light` and `Dark` and see what happens
         .theme(Theme::Auto)
         .background(Background::new(0xA35FC1, 0x0B0B0B))
-        .icon("examples/images/NativeUI")
+        .icon(WindowIcon::from_path("examples/images/NativeUI.ico").unwrap())
         .show()
         .unwrap();
 
@@ -42,8 +40,8 @@ fn main() {
             KeyCode::Capital => state.as_mut().caps = true,
 
             // Window controls
-            KeyCode::F11 => toggle_fullscreen(id),
-            KeyCode::Escape => close(id),
+            KeyCode::F11 => id.toggle_fullscreen(),
+            KeyCode::Escape => id.request_close(),
 
             key => {
                 // Print key with current modifiers
@@ -0,0 +1,414 @@
+//! Describing a window's startup appearance in a TOML or JSON file.
+//!
+//! Needs the `config` feature. Every key is optional, missing ones keep the defaults of
+//! [`WindowOptions`]. Sizes are logical pixels and colors are `#RRGGBB` or `#AARRGGBB` strings.
+//! Icon paths are relative to the config file.
+//!
+//! ```toml
+//! title = "Editor"
+//! size = [1024, 768]
+//! fixed = false
+//! theme = "dark"
+//! icon = "assets/icon.png"
+//!
+//! [background]
+//! light = "#FFFFFF"
+//! dark = "#1E1E1E"
+//!
+//! [constraints]
+//! min_size = [480, 320]
+//! aspect_ratio = [16, 10]
+//! ```
+//!
+//! Errors in the file come back as [`Error::invalid_parameter`] naming the line and the key:
+//! ``Line 3, `theme`: unknown variant `dim`, expected one of `auto`, `light`, `dark` ``.
+//!
+//! ```no_run
+//! use storytree_native::prelude::*;
+//! use storytree_native::{Window, WindowOptions};
+//!
+//! let mut options = WindowOptions::from_config("window.toml").unwrap();
+//! options.show = true;
+//! let id = Window::create(options).unwrap();
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::dpi::{LogicalSize, Size};
+use crate::error::Error;
+use crate::icon::WindowIcon;
+use crate::style::{Background, Theme};
+use crate::window::{SizeConstraints, WindowOptions};
+
+/// Syntax of a config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Pick the format from a `.toml` or `.json` extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A non zero `[width, height]` pair
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "[u32; 2]")]
+struct Pair(u32, u32);
+
+impl TryFrom<[u32; 2]> for Pair {
+    type Error = String;
+
+    fn try_from([width, height]: [u32; 2]) -> Result<Self, String> {
+        match width > 0 && height > 0 {
+            true => Ok(Pair(width, height)),
+            false => Err(format!("[{}, {}] has a zero side", width, height)),
+        }
+    }
+}
+
+/// A `#RRGGBB` or `#AARRGGBB` color
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct Color(u32);
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        text.strip_prefix('#')
+            .filter(|hex| hex.len() == 6 || hex.len() == 8)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .map(Color)
+            .ok_or_else(|| format!("`{}` is not a #RRGGBB or #AARRGGBB color", text))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ThemeConfig {
+    Auto,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackgroundConfig {
+    light: Option<Color>,
+    dark: Option<Color>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawConstraints")]
+struct ConstraintsConfig(SizeConstraints);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConstraints {
+    min_size: Option<Pair>,
+    max_size: Option<Pair>,
+    aspect_ratio: Option<Pair>,
+    resize_increments: Option<Pair>,
+}
+
+impl TryFrom<RawConstraints> for ConstraintsConfig {
+    type Error = String;
+
+    fn try_from(raw: RawConstraints) -> Result<Self, String> {
+        let pair = |pair: Option<Pair>| pair.map(|Pair(width, height)| (width, height));
+        let constraints = SizeConstraints {
+            min_size: pair(raw.min_size),
+            max_size: pair(raw.max_size),
+            aspect_ratio: pair(raw.aspect_ratio),
+            resize_increments: pair(raw.resize_increments),
+        };
+        if let (Some(min), Some(max)) = (constraints.min_size, constraints.max_size) {
+            if min.0 > max.0 || min.1 > max.1 {
+                return Err(format!(
+                    "min_size {:?} is larger than max_size {:?}",
+                    min, max
+                ));
+            }
+        }
+        Ok(ConstraintsConfig(constraints))
+    }
+}
+
+/// Everything a config file can set
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WindowConfig {
+    title: Option<String>,
+    size: Option<Pair>,
+    fixed: Option<bool>,
+    theme: Option<ThemeConfig>,
+    background: Option<BackgroundConfig>,
+    icon: Option<PathBuf>,
+    constraints: Option<ConstraintsConfig>,
+}
+
+/// Get the 1 based line of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Turn a parse error into an `Error` naming the line and the key it happened at
+fn config_error(line: Option<usize>, path: &serde_path_to_error::Path, message: &str) -> Error {
+    let field = path.to_string();
+    // Syntax errors end the path with `?` where the key couldn't be read
+    let field = match field.trim_end_matches('?').trim_end_matches('.') {
+        "" => ".",
+        field => field,
+    };
+    // TOML syntax errors put what was expected on a second line
+    let message = message.trim().replace('\n', ", ");
+    let message = match (line, field) {
+        (Some(line), ".") => format!("Line {}: {}", line, message),
+        (Some(line), _) => format!("Line {}, `{}`: {}", line, field, message),
+        (None, ".") => message,
+        (None, _) => format!("`{}`: {}", field, message),
+    };
+    Error::invalid_parameter(message)
+}
+
+fn parse(text: &str, format: ConfigFormat) -> Result<WindowConfig, Error> {
+    match format {
+        ConfigFormat::Toml => serde_path_to_error::deserialize(toml::Deserializer::new(text))
+            .map_err(|err| {
+                let line = err.inner().span().map(|span| line_of(text, span.start));
+                config_error(line, err.path(), err.inner().message())
+            }),
+        ConfigFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_str(text);
+            serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+                let inner = err.inner();
+                // The message ends with the location, which is reported separately
+                let message = inner.to_string();
+                let message = message
+                    .rsplit_once(" at line ")
+                    .map_or(message.as_str(), |(message, _)| message);
+                let line = (inner.line() > 0).then_some(inner.line());
+                config_error(line, err.path(), message)
+            })
+        }
+    }
+}
+
+impl WindowOptions {
+    /// Read window options from a `.toml` or `.json` file, icon paths are relative to the file
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path).ok_or_else(|| {
+            Error::invalid_parameter(format!("{} is not a .toml or .json file", path.display()))
+        })?;
+        let text = fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        WindowOptions::from_config_in(&text, format, base)
+    }
+
+    /// Read window options from config text, icon paths are relative to the working directory
+    pub fn from_config_str(text: &str, format: ConfigFormat) -> Result<Self, Error> {
+        WindowOptions::from_config_in(text, format, Path::new(""))
+    }
+
+    fn from_config_in(text: &str, format: ConfigFormat, base: &Path) -> Result<Self, Error> {
+        let config = parse(text, format)?;
+        let mut options = WindowOptions::default();
+        if let Some(title) = config.title {
+            options.title = title;
+        }
+        if let Some(Pair(width, height)) = config.size {
+            options.size = Some(Size::Logical(LogicalSize::new(width as f64, height as f64)));
+        }
+        if let Some(fixed) = config.fixed {
+            options.fixed_size = fixed;
+        }
+        if let Some(theme) = config.theme {
+            options.theme = match theme {
+                ThemeConfig::Auto => Theme::Auto,
+                ThemeConfig::Light => Theme::Light,
+                ThemeConfig::Dark => Theme::Dark,
+            };
+        }
+        if let Some(background) = config.background {
+            let default = Background::default();
+            options.background = Background::new(
+                background
+                    .light
+                    .map_or(default.light(), |Color(color)| color),
+                background.dark.map_or(default.dark(), |Color(color)| color),
+            );
+        }
+        if let Some(icon) = config.icon {
+            let icon = WindowIcon::from_path(base.join(&icon)).map_err(|err| {
+                Error::invalid_parameter(format!(
+                    "`icon`: {} can't be loaded: {}",
+                    icon.display(),
+                    err.message
+                ))
+            })?;
+            options.icon = Some(icon);
+        }
+        if let Some(ConstraintsConfig(constraints)) = config.constraints {
+            options.constraints = constraints;
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the errors of the same config written as TOML and as JSON
+    fn errors(toml: &str, json: &str) -> (String, String) {
+        let error = |text, format| {
+            WindowOptions::from_config_str(text, format)
+                .unwrap_err()
+                .message
+        };
+        (
+            error(toml, ConfigFormat::Toml),
+            error(json, ConfigFormat::Json),
+        )
+    }
+
+    #[test]
+    fn reads_every_key() {
+        let toml = "title = \"Editor\"\nsize = [1024, 768]\nfixed = true\ntheme = \"dark\"\n\n\
+                    [background]\ndark = \"#801E1E1E\"\n\n[constraints]\nmin_size = [480, 320]\n";
+        let json = r##"{
+            "title": "Editor",
+            "size": [1024, 768],
+            "fixed": true,
+            "theme": "dark",
+            "background": { "dark": "#801E1E1E" },
+            "constraints": { "min_size": [480, 320] }
+        }"##;
+        for (text, format) in [(toml, ConfigFormat::Toml), (json, ConfigFormat::Json)] {
+            let options = WindowOptions::from_config_str(text, format).unwrap();
+            assert_eq!(options.title, "Editor");
+            assert_eq!(
+                options.size,
+                Some(Size::Logical(LogicalSize::new(1024.0, 768.0)))
+            );
+            assert!(options.fixed_size);
+            assert_eq!(options.theme, Theme::Dark);
+            assert_eq!(options.background.dark(), 0x801E1E1E);
+            assert_eq!(options.background.light(), Background::default().light());
+            assert_eq!(options.constraints.min_size, Some((480, 320)));
+        }
+    }
+
+    #[test]
+    fn rejects_zero_sizes() {
+        let (toml, json) = errors(
+            "title = \"Editor\"\nsize = [0, 768]\n",
+            "{\n  \"size\": [0, 768],\n  \"title\": \"Editor\"\n}",
+        );
+        assert_eq!(toml, "Line 2, `size`: [0, 768] has a zero side");
+        assert_eq!(json, "Line 2, `size`: [0, 768] has a zero side");
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        let (toml, json) = errors(
+            "[background]\nlight = \"#FFF\"\n",
+            "{\n  \"background\": {\n    \"light\": \"#FFF\",\n    \"dark\": \"#000000\"\n  }\n}",
+        );
+        let message = "`background.light`: `#FFF` is not a #RRGGBB or #AARRGGBB color";
+        assert_eq!(toml, format!("Line 2, {}", message));
+        assert_eq!(json, format!("Line 3, {}", message));
+    }
+
+    #[test]
+    fn rejects_unknown_themes() {
+        let (toml, json) = errors(
+            "title = \"Editor\"\ntheme = \"dim\"\n",
+            "{\n  \"title\": \"Editor\",\n  \"theme\": \"dim\"\n}",
+        );
+        let message = "`theme`: unknown variant `dim`, expected one of `auto`, `light`, `dark`";
+        assert_eq!(toml, format!("Line 2, {}", message));
+        assert_eq!(json, format!("Line 3, {}", message));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let (toml, json) = errors(
+            "title = \"Editor\"\ncolour = \"#FFFFFF\"\n",
+            "{\n  \"colour\": \"#FFFFFF\"\n}",
+        );
+        let message =
+            "`colour`: unknown field `colour`, expected one of `title`, `size`, `fixed`, \
+                       `theme`, `background`, `icon`, `constraints`";
+        assert_eq!(toml, format!("Line 2, {}", message));
+        assert_eq!(json, format!("Line 2, {}", message));
+
+        let (toml, json) = errors(
+            "[background]\nlight = \"#FFFFFF\"\ndim = \"#000000\"\n",
+            "{\n  \"background\": {\n    \"dim\": \"#000000\"\n  }\n}",
+        );
+        let message = "`background.dim`: unknown field `dim`, expected `light` or `dark`";
+        assert_eq!(toml, format!("Line 3, {}", message));
+        assert_eq!(json, format!("Line 3, {}", message));
+    }
+
+    #[test]
+    fn rejects_min_size_larger_than_max_size() {
+        let (toml, json) = errors(
+            "[constraints]\nmin_size = [800, 600]\nmax_size = [640, 480]\n",
+            "{\n  \"constraints\": { \"min_size\": [800, 600], \"max_size\": [640, 480] },\n  \
+             \"title\": \"Editor\"\n}",
+        );
+        let message = "`constraints`: min_size (800, 600) is larger than max_size (640, 480)";
+        // TOML points at the table header
+        assert_eq!(toml, format!("Line 1, {}", message));
+        assert_eq!(json, format!("Line 2, {}", message));
+    }
+
+    #[test]
+    fn rejects_wrong_types() {
+        let (toml, json) = errors(
+            "title = \"Editor\"\nfixed = \"yes\"\n",
+            "{\n  \"title\": \"Editor\",\n  \"fixed\": \"yes\",\n  \"theme\": \"dark\"\n}",
+        );
+        let message = "`fixed`: invalid type: string \"yes\", expected a boolean";
+        assert_eq!(toml, format!("Line 2, {}", message));
+        assert_eq!(json, format!("Line 3, {}", message));
+    }
+
+    #[test]
+    fn rejects_syntax_errors() {
+        let (toml, json) = errors(
+            "title = \"Editor\"\nsize = [1024 768]\n",
+            "{\n  \"title\": \"Editor\"\n  \"size\": [1024, 768]\n}",
+        );
+        assert_eq!(toml, "Line 2: invalid array, expected `]`");
+        assert_eq!(json, "Line 3: expected `,` or `}`");
+    }
+
+    #[test]
+    fn rejects_missing_icons() {
+        let (toml, json) = errors(
+            "icon = \"missing.png\"\n",
+            "{\n  \"icon\": \"missing.png\"\n}",
+        );
+        assert!(
+            toml.starts_with("`icon`: missing.png can't be loaded: "),
+            "{}",
+            toml
+        );
+        assert_eq!(toml, json);
+    }
+}
@@ -0,0 +1,181 @@
+//! Mouse cursor shapes and custom cursor images.
+//!
+//! Each window has its own cursor that is shown while the mouse is over its client area. System
+//! cursors are picked with a [`CursorIcon`], named after the CSS `cursor` values, and images can
+//! be turned into a [`CustomCursor`]. A focused window can also keep the pointer inside its
+//! client area or lock it in place with a [`CursorGrab`], relative movement is still reported
+//! by [`Event::MouseMotion`](crate::event::Event::MouseMotion).
+//!
+//! ```no_run
+//! use storytree_native::prelude::*;
+//! use storytree_native::cursor::CustomCursor;
+//! use storytree_native::{CursorGrab, CursorIcon, Window};
+//!
+//! let id = Window::builder().cursor(CursorIcon::Crosshair).show().unwrap();
+//! let dot = CustomCursor::from_rgba(vec![0xFF; 8 * 8 * 4], 8, 8, (4, 4)).unwrap();
+//! id.set_cursor(dot).unwrap();
+//! id.set_cursor_visible(false).unwrap();
+//! id.set_cursor_grab(CursorGrab::Locked).unwrap();
+//! ```
+
+use crate::error::Error;
+use crate::icon::IconImage;
+
+/// System cursor shapes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    ContextMenu,
+    Help,
+    /// Hand used for links
+    Pointer,
+    /// Busy but still accepting input
+    Progress,
+    /// Busy and not accepting input
+    Wait,
+    Cell,
+    Crosshair,
+    Text,
+    VerticalText,
+    Alias,
+    Copy,
+    Move,
+    NoDrop,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+    EResize,
+    NResize,
+    NeResize,
+    NwResize,
+    SResize,
+    SeResize,
+    SwResize,
+    WResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+}
+
+impl CursorIcon {
+    /// Get the names of the cursor in freedesktop cursor themes, the CSS name first and then
+    /// the older X11 names themes that predate it use
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(crate) fn names(self) -> &'static [&'static str] {
+        match self {
+            CursorIcon::Default => &["default", "left_ptr"],
+            CursorIcon::ContextMenu => &["context-menu", "left_ptr"],
+            CursorIcon::Help => &["help", "question_arrow", "left_ptr"],
+            CursorIcon::Pointer => &["pointer", "hand2", "hand1"],
+            CursorIcon::Progress => &["progress", "left_ptr_watch", "watch"],
+            CursorIcon::Wait => &["wait", "watch"],
+            CursorIcon::Cell => &["cell", "plus"],
+            CursorIcon::Crosshair => &["crosshair", "cross"],
+            CursorIcon::Text => &["text", "xterm"],
+            CursorIcon::VerticalText => &["vertical-text", "xterm"],
+            CursorIcon::Alias => &["alias", "link", "left_ptr"],
+            CursorIcon::Copy => &["copy", "left_ptr"],
+            CursorIcon::Move => &["move", "fleur"],
+            CursorIcon::NoDrop => &["no-drop", "circle"],
+            CursorIcon::NotAllowed => &["not-allowed", "crossed_circle", "circle"],
+            CursorIcon::Grab => &["grab", "openhand", "hand1"],
+            CursorIcon::Grabbing => &["grabbing", "closedhand", "fleur"],
+            CursorIcon::AllScroll => &["all-scroll", "fleur"],
+            CursorIcon::ZoomIn => &["zoom-in", "left_ptr"],
+            CursorIcon::ZoomOut => &["zoom-out", "left_ptr"],
+            CursorIcon::EResize => &["e-resize", "right_side"],
+            CursorIcon::NResize => &["n-resize", "top_side"],
+            CursorIcon::NeResize => &["ne-resize", "top_right_corner"],
+            CursorIcon::NwResize => &["nw-resize", "top_left_corner"],
+            CursorIcon::SResize => &["s-resize", "bottom_side"],
+            CursorIcon::SeResize => &["se-resize", "bottom_right_corner"],
+            CursorIcon::SwResize => &["sw-resize", "bottom_left_corner"],
+            CursorIcon::WResize => &["w-resize", "left_side"],
+            CursorIcon::EwResize => &["ew-resize", "sb_h_double_arrow"],
+            CursorIcon::NsResize => &["ns-resize", "sb_v_double_arrow"],
+            CursorIcon::NeswResize => &["nesw-resize", "fd_double_arrow", "size_bdiag"],
+            CursorIcon::NwseResize => &["nwse-resize", "bd_double_arrow", "size_fdiag"],
+            CursorIcon::ColResize => &["col-resize", "sb_h_double_arrow"],
+            CursorIcon::RowResize => &["row-resize", "sb_v_double_arrow"],
+        }
+    }
+}
+
+/// Cursor made from an image, the hotspot is the pixel that points at the mouse position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCursor {
+    image: IconImage,
+    hotspot: (u32, u32),
+}
+
+impl CustomCursor {
+    /// Create a cursor from `width * height` RGBA pixels, extra bytes are ignored
+    pub fn from_rgba(
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        hotspot: (u32, u32),
+    ) -> Result<Self, Error> {
+        if hotspot.0 >= width || hotspot.1 >= height {
+            return Err(Error::invalid_parameter(format!(
+                "Hotspot {:?} is outside of the {}x{} cursor",
+                hotspot, width, height
+            )));
+        }
+        Ok(Self {
+            image: IconImage::new(rgba, width, height)?,
+            hotspot,
+        })
+    }
+
+    pub fn image(&self) -> &IconImage {
+        &self.image
+    }
+
+    pub fn hotspot(&self) -> (u32, u32) {
+        self.hotspot
+    }
+}
+
+/// How a window holds on to the pointer while it has focus
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorGrab {
+    #[default]
+    None,
+    /// The pointer can't leave the client area
+    Confined,
+    /// The pointer stays in the middle of the client area, only raw motion reports movement
+    Locked,
+}
+
+/// Cursor shown over the client area of a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cursor {
+    Icon(CursorIcon),
+    Custom(CustomCursor),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Cursor::Icon(CursorIcon::Default)
+    }
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self {
+        Cursor::Icon(icon)
+    }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(cursor: CustomCursor) -> Self {
+        Cursor::Custom(cursor)
+    }
+}
@@ -0,0 +1,48 @@
+//! Typed values attached to a window, see [`WindowId::insert_data`].
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::window::WindowId;
+
+/// One value of each type per window
+type TypeMap = HashMap<TypeId, Rc<dyn Any>>;
+
+thread_local! {
+    /// Windows live on the thread that created them, so does their data
+    static DATA: RefCell<HashMap<WindowId, TypeMap>> = RefCell::new(HashMap::new());
+}
+
+/// Attach `value` to the window, returns the value of the same type it replaces
+pub(crate) fn insert<T: 'static>(id: WindowId, value: T) -> Option<Rc<T>> {
+    let old = DATA.with_borrow_mut(|data| {
+        data.entry(id)
+            .or_default()
+            .insert(TypeId::of::<T>(), Rc::new(value))
+    });
+    old.and_then(|old| old.downcast().ok())
+}
+
+pub(crate) fn get<T: 'static>(id: WindowId) -> Option<Rc<T>> {
+    let value = DATA.with_borrow(|data| data.get(&id)?.get(&TypeId::of::<T>()).cloned());
+    value.and_then(|value| value.downcast().ok())
+}
+
+pub(crate) fn remove<T: 'static>(id: WindowId) -> Option<Rc<T>> {
+    let value = DATA.with_borrow_mut(|data| data.get_mut(&id)?.remove(&TypeId::of::<T>()));
+    value.and_then(|value| value.downcast().ok())
+}
+
+/// Drop everything attached to a destroyed window
+pub(crate) fn forget(id: WindowId) {
+    // Taken out first, the values can touch the data of other windows when they are dropped
+    let values = DATA.with_borrow_mut(|data| data.remove(&id));
+    drop(values);
+}
+
+pub(crate) fn forget_all() {
+    let values = DATA.take();
+    drop(values);
+}
@@ -0,0 +1,95 @@
+//! Logical and physical pixels.
+//!
+//! Physical pixels are the pixels of the monitor. Logical pixels are physical pixels divided by
+//! the scale factor of the monitor a window is on, a scale factor of `1.0` means 96 DPI.
+//!
+//! Sizes passed to [`crate::prelude::WindowBuilder::size`] are logical so windows look the same
+//! on every monitor. Everything the crate reports back, window sizes,
+//! [`crate::event::Event::Resized`] and mouse positions, is in physical pixels.
+
+/// Size in pixels scaled by the monitor's scale factor
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LogicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl LogicalSize {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self { width, height }
+    }
+
+    pub fn to_physical(&self, scale_factor: f64) -> PhysicalSize {
+        PhysicalSize {
+            width: (self.width * scale_factor).round() as u32,
+            height: (self.height * scale_factor).round() as u32,
+        }
+    }
+}
+
+/// Size in monitor pixels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PhysicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PhysicalSize {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn to_logical(&self, scale_factor: f64) -> LogicalSize {
+        LogicalSize {
+            width: self.width as f64 / scale_factor,
+            height: self.height as f64 / scale_factor,
+        }
+    }
+}
+
+impl From<(u32, u32)> for PhysicalSize {
+    fn from((width, height): (u32, u32)) -> Self {
+        Self { width, height }
+    }
+}
+
+impl From<PhysicalSize> for (u32, u32) {
+    fn from(size: PhysicalSize) -> Self {
+        (size.width, size.height)
+    }
+}
+
+/// A size in either kind of pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    Logical(LogicalSize),
+    Physical(PhysicalSize),
+}
+
+impl Size {
+    pub fn to_physical(&self, scale_factor: f64) -> PhysicalSize {
+        match self {
+            Size::Logical(size) => size.to_physical(scale_factor),
+            Size::Physical(size) => *size,
+        }
+    }
+
+    pub fn to_logical(&self, scale_factor: f64) -> LogicalSize {
+        match self {
+            Size::Logical(size) => *size,
+            Size::Physical(size) => size.to_logical(scale_factor),
+        }
+    }
+}
+
+impl From<LogicalSize> for Size {
+    fn from(size: LogicalSize) -> Self {
+        Size::Logical(size)
+    }
+}
+
+impl From<PhysicalSize> for Size {
+    fn from(size: PhysicalSize) -> Self {
+        Size::Physical(size)
+    }
+}
@@ -1,12 +1,64 @@
 use std::fmt::Display;
 use std::io;
 
+use crate::window::WindowId;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd)]
 pub struct Error {
     pub code: isize,
     pub message: String,
 }
 
+impl Error {
+    /// Error returned when a feature is not implemented for the current platform
+    pub fn unsupported(feature: &str) -> Self {
+        Self {
+            // Same value as win32 `ERROR_NOT_SUPPORTED`
+            code: 50,
+            message: format!("{} is not supported on this platform", feature),
+        }
+    }
+
+    /// Error returned when a window id doesn't belong to an open window
+    pub fn invalid_window(id: WindowId) -> Self {
+        Self {
+            // Same value as win32 `ERROR_INVALID_WINDOW_HANDLE`
+            code: 1400,
+            message: format!("Invalid window handle {}", id.as_raw()),
+        }
+    }
+
+    /// Error returned when an argument is out of range or malformed
+    pub fn invalid_parameter(message: impl Into<String>) -> Self {
+        Self {
+            // Same value as win32 `ERROR_INVALID_PARAMETER`
+            code: 87,
+            message: message.into(),
+        }
+    }
+
+    /// Error returned when something is held by another app, like a pointer grab
+    pub fn busy(message: impl Into<String>) -> Self {
+        Self {
+            // Same value as win32 `ERROR_BUSY`
+            code: 170,
+            message: message.into(),
+        }
+    }
+
+    /// Error returned when a pixel buffer has fewer pixels than its width and height need
+    pub fn invalid_buffer(len: usize, width: u32, height: u32) -> Self {
+        Self {
+            // Same value as win32 `ERROR_INSUFFICIENT_BUFFER`
+            code: 122,
+            message: format!(
+                "Buffer of {} pixels is too small for {}x{}",
+                len, width, height
+            ),
+        }
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[StoryTree] {}", self.message)
@@ -27,6 +79,6 @@ impl From<io::Error> for Error {
 #[macro_export]
 macro_rules! e {
     ($e: expr) => {
-        $e.map_err(|e| Into::<crate::error::Error>::into(e))
+        $e.map_err(|e| Into::<$crate::error::Error>::into(e))
     };
 }
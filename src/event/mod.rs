@@ -1,11 +1,11 @@
-use std::{cell::RefCell, sync::{Arc, RwLockWriteGuard}};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fmt::Debug;
-use std::marker::PhantomData;
-use std::sync::{RwLock, RwLockReadGuard};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+use crate::window::WindowId;
 use keyboard::KeyEvent;
 use mouse::MouseEvent;
-use crate::event::mouse::MouseEventType;
 
 pub mod keyboard;
 pub mod mouse;
@@ -28,7 +28,7 @@ impl IntoEventResult for bool {
 
 #[derive(Debug, Clone)]
 pub struct PaintEvent {
-    pub handle: isize,
+    pub handle: WindowId,
 }
 
 #[derive(Debug, Clone)]
@@ -37,21 +37,172 @@ pub enum Event {
     Repaint,
     Keyboard(KeyEvent),
     Mouse(MouseEvent),
+    /// Raw movement of the mouse without pointer acceleration or screen edges, in device units.
+    /// Sent to the focused window while it grabs the cursor or turned it on with
+    /// [`WindowId::set_raw_mouse_motion`], never sent on Wayland.
+    MouseMotion { dx: f64, dy: f64 },
+    /// The area inside the window frame changed size
+    Resized { width: u32, height: u32 },
+    /// The top left corner of the window frame moved, never sent on Wayland
+    Moved { x: i32, y: i32 },
+    /// The window gained (`true`) or lost (`false`) keyboard focus
+    Focused(bool),
+    Minimized,
+    Maximized,
+    /// The window left the minimized or maximized state
+    Restored,
+    /// The window is gone, this is the last event for it
+    Destroyed,
+    /// The window moved to a monitor with another scale factor or the scale factor of its monitor
+    /// changed. `width` and `height` are the physical client size that keeps the window the same
+    /// logical size, the window is resized to it right after this event.
+    ScaleFactorChanged {
+        scale_factor: f64,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Get the event for a change between minimized, maximized and normal, `was` is the previous
+/// `(minimized, maximized)` state
+pub(crate) fn state_change(was: (bool, bool), minimized: bool, maximized: bool) -> Option<Event> {
+    let (was_minimized, was_maximized) = was;
+    if minimized {
+        (!was_minimized).then_some(Event::Minimized)
+    } else if maximized {
+        // Restoring a window that was minimized while maximized brings it back maximized
+        (was_minimized || !was_maximized).then_some(Event::Maximized)
+    } else {
+        (was_minimized || was_maximized).then_some(Event::Restored)
+    }
+}
+
+/// When [`App::run`] returns by itself
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExitPolicy {
+    /// Stop once every window is closed
+    #[default]
+    LastWindowClosed,
+    /// Stop when this window is closed, even if others are still open
+    MainWindowClosed(WindowId),
+    /// Keep running when every window is closed, only [`quit`] ends the app
+    Never,
+}
+
+thread_local! {
+    static EXIT_POLICY: Cell<ExitPolicy> = const { Cell::new(ExitPolicy::LastWindowClosed) };
+}
+
+/// Check if the event loop should stop now that `closed` is destroyed, `remaining` is how many
+/// windows are still open on this thread
+pub(crate) fn exit_after_close(closed: WindowId, remaining: usize) -> bool {
+    match EXIT_POLICY.get() {
+        ExitPolicy::LastWindowClosed => remaining == 0,
+        ExitPolicy::MainWindowClosed(main) => closed == main,
+        ExitPolicy::Never => false,
+    }
+}
+
+/// Handles the events of one window, set with
+/// [`WindowBuilder::handler`](crate::prelude::WindowBuilder::handler). The callback passed to
+/// [`App::run`] gets the events of windows without a handler.
+pub trait WindowHandler: Send + Sync {
+    /// Handle an event, the result means the same as the one of the app callback
+    fn event(&self, id: WindowId, event: Event) -> bool;
+}
+
+impl<F, R> WindowHandler for F
+where
+    F: Fn(WindowId, Event) -> R + Send + Sync,
+    R: IntoEventResult,
+{
+    fn event(&self, id: WindowId, event: Event) -> bool {
+        self(id, event).into_event_result()
+    }
+}
+
+/// Shared handle to a [`WindowHandler`]
+#[derive(Clone)]
+pub struct EventHandler(Arc<dyn WindowHandler>);
+
+impl EventHandler {
+    pub fn new(handler: impl WindowHandler + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl Debug for EventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
+
+thread_local! {
+    static HANDLERS: RefCell<HashMap<WindowId, EventHandler>> = RefCell::new(HashMap::new());
+}
+
+/// Send the events of `id` to the handler from its options instead of the app callback. Backends
+/// call this when the window enters their registry, before it gets any event.
+pub(crate) fn set_handler(id: WindowId, handler: Option<&EventHandler>) {
+    if let Some(handler) = handler {
+        HANDLERS.with_borrow_mut(|handlers| handlers.insert(id, handler.clone()));
+    }
+}
+
+/// Drop the handler and data of a destroyed window. Backends call this once the app got
+/// `Event::Destroyed` for it, or when it is destroyed without the event loop running.
+pub(crate) fn forget(id: WindowId) {
+    // Taken out first, dropping them can run app code
+    let handler = HANDLERS.with_borrow_mut(|handlers| handlers.remove(&id));
+    drop(handler);
+    crate::data::forget(id);
+}
+
+/// Drop the handlers and data of every window
+pub(crate) fn forget_all() {
+    let handlers = HANDLERS.take();
+    drop(handlers);
+    crate::data::forget_all();
+}
+
+/// Wrap the app callback so windows with a handler get their events there
+fn dispatch<F, R, T>(
+    callback: F,
+) -> impl Fn(WindowId, Event, State<T>) -> bool + 'static + Sync + Send
+where
+    F: Fn(WindowId, Event, State<T>) -> R + 'static + Sync + Send,
+    R: IntoEventResult,
+    T: Clone + Send + Sync + 'static,
+{
+    move |id, event, state| {
+        // Cloned out of the map so the handler can create windows and set handlers itself
+        let handler = HANDLERS.with_borrow(|handlers| handlers.get(&id).cloned());
+        match handler {
+            Some(EventHandler(handler)) => handler.event(id, event),
+            None => callback(id, event, state).into_event_result(),
+        }
+    }
 }
 
 pub trait IntoEvent {
     fn into_event(self) -> Event;
 }
 
-pub fn close(id: isize) {
+pub fn close(id: WindowId) {
     #[cfg(target_os = "windows")]
-    unsafe {
+    if crate::headless::is_active() {
+        crate::headless::event::close(id);
+    } else {
         use crate::windows::event::wnd_proc;
         use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
         use windows::Win32::UI::WindowsAndMessaging::{CallWindowProcW, WM_CLOSE};
 
-        CallWindowProcW(Some(wnd_proc), HWND(id), WM_CLOSE, WPARAM(0), LPARAM(0));
+        unsafe {
+            CallWindowProcW(Some(wnd_proc), HWND::from(id), WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
     }
+    #[cfg(target_os = "linux")]
+    crate::linux::event::close(id);
 }
 
 pub fn quit(code: i32) {
@@ -92,32 +243,48 @@ impl<T: Send + Sync + Clone + Debug> Debug for State<T> {
 pub struct App;
 impl App
 {
+    /// Choose when the event loop on this thread returns by itself, by default it runs until
+    /// the last window is closed
+    pub fn set_exit_policy(policy: ExitPolicy) {
+        EXIT_POLICY.set(policy);
+    }
+
+    pub fn exit_policy() -> ExitPolicy {
+        EXIT_POLICY.get()
+    }
+
     pub fn run<F, R>(callback: F)
     where
-        F: Fn(isize, Event, State<()>) -> R + 'static + Sync + Send,
+        F: Fn(WindowId, Event, State<()>) -> R + 'static + Sync + Send,
         R: IntoEventResult,
     {
         #[cfg(target_os = "windows")]
-        crate::windows::event::run(State::default(), callback);
+        crate::windows::event::run(State::default(), dispatch(callback));
+        #[cfg(target_os = "linux")]
+        crate::linux::event::run(State::default(), dispatch(callback));
     }
 
     pub fn run_with<S, F, R>(state: S, callback: F)
         where
             S: Clone + Send + Sync + 'static,
-            F: Fn(isize, Event, State<S>) -> R + 'static + Sync + Send,
+            F: Fn(WindowId, Event, State<S>) -> R + 'static + Sync + Send,
             R: IntoEventResult,
     {
         #[cfg(target_os = "windows")]
-        crate::windows::event::run(State::new(state), callback);
+        crate::windows::event::run(State::new(state), dispatch(callback));
+        #[cfg(target_os = "linux")]
+        crate::linux::event::run(State::new(state), dispatch(callback));
     }
 }
 
 pub fn run<R, F, T>(state: T, callback: F)
 where
     R: IntoEventResult,
-    F: Fn(isize, Event, State<T>) -> R + 'static + Sync + Send,
+    F: Fn(WindowId, Event, State<T>) -> R + 'static + Sync + Send,
     T: Clone + Send + Sync + 'static
 {
     #[cfg(target_os = "windows")]
-    crate::windows::event::run(State::new(state), callback);
+    crate::windows::event::run(State::new(state), dispatch(callback));
+    #[cfg(target_os = "linux")]
+    crate::linux::event::run(State::new(state), dispatch(callback));
 }
@@ -21,6 +21,7 @@ pub enum MouseEventType {
     HScroll(i16),
 }
 
+/// Mouse input, `x` and `y` are physical pixels from the top left of the area inside the frame
 #[derive(Debug, Clone)]
 pub struct MouseEvent {
     pub x: u16,
@@ -0,0 +1,62 @@
+use crate::event::{Event, IntoEventResult, State};
+use crate::window::WindowId;
+
+use super::window::{with_window, Window};
+use super::{send, EVENTS, QUIT};
+
+/// Ask a window to close, `Event::Close` is delivered by the event loop first
+pub fn close(id: WindowId) {
+    send(id, Event::Close);
+}
+
+/// Deliver queued events until the [`ExitPolicy`] ends the app or the queue is empty.
+///
+/// Nothing can produce events outside of the app so the loop returns instead of waiting once
+/// every queued event, including the ones sent from the callback, has been handled. Events for
+/// windows that don't exist are dropped, except for `Event::Destroyed` which is still delivered
+/// after a window is closed.
+///
+/// [`ExitPolicy`]: crate::event::ExitPolicy
+pub fn run<R, F, T>(state: State<T>, callback: F)
+where
+    R: IntoEventResult,
+    F: (Fn(WindowId, Event, State<T>) -> R) + 'static + Sync + Send,
+    T: Send + Sync + Clone + 'static,
+{
+    QUIT.set(false);
+    loop {
+        let next = EVENTS.with(|events| {
+            let mut events = events.borrow_mut();
+            if QUIT.get() {
+                let index = events
+                    .iter()
+                    .position(|(_, event)| matches!(event, Event::Destroyed))?;
+                events.remove(index)
+            } else {
+                events.pop_front()
+            }
+        });
+        let Some((id, event)) = next else {
+            break;
+        };
+        if !matches!(event, Event::Destroyed) && with_window(id, |_| ()).is_none() {
+            continue;
+        }
+
+        match event {
+            Event::Close => {
+                if callback(id, Event::Close, state.clone()).into_event_result() {
+                    let _ = Window::close(id);
+                }
+            }
+            Event::Destroyed => {
+                callback(id, Event::Destroyed, state.clone());
+                // Kept until now so the handler and data outlive the event
+                crate::event::forget(id);
+            }
+            event => {
+                callback(id, event, state.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! In memory backend for running apps without a display server.
+//!
+//! Windows only exist as state on the current thread. Every [`WindowContext`] call is recorded
+//! as a [`Call`], events can be queued with [`send`] and are delivered by [`App::run`], and dialogs
+//! answer with responses queued through [`respond_prompt`] and [`respond`].
+//!
+//! The backend is used when building with the `headless` feature, when `STORYTREE_BACKEND` is
+//! set to `headless` or when a window is built with [`Backend::Headless`].
+//!
+//! ```no_run
+//! use storytree_native::prelude::*;
+//! use storytree_native::{headless, Backend, Window};
+//! use storytree_native::event::{App, Event};
+//!
+//! let id = Window::builder().backend(Backend::Headless).show().unwrap();
+//! headless::send(id, Event::Close);
+//! App::run(|_, _, _| {});
+//!
+//! assert!(headless::window(id).is_none());
+//! assert!(headless::calls().contains(&headless::Call::Close(id)));
+//! ```
+//!
+//! [`WindowContext`]: crate::window::WindowContext
+//! [`App::run`]: crate::event::App::run
+//! [`Backend::Headless`]: crate::Backend::Headless
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::error::Error;
+use crate::event::Event;
+use crate::icon::WindowIcon;
+use crate::modal::{Button, DialogAction};
+use crate::style::{Background, Theme};
+use crate::window::{backend, Backend, Fullscreen, SizeConstraints, WindowId, WindowLevel};
+
+pub(crate) mod event;
+pub(crate) mod monitor;
+pub(crate) mod window;
+
+pub(crate) use window::Window;
+
+/// Request made to the headless backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    Create(WindowId),
+    SetTheme(WindowId, Theme),
+    Show(WindowId),
+    Hide(WindowId),
+    Minimize(WindowId),
+    Restore(WindowId),
+    Maximize(WindowId),
+    SetFullscreen(WindowId, Option<Fullscreen>),
+    Close(WindowId),
+    SetTitle(WindowId, String),
+    SetIcon(WindowId, Option<WindowIcon>),
+    SetSmallIcon(WindowId, Option<WindowIcon>),
+    SetSize(WindowId, u32, u32),
+    SetPosition(WindowId, i32, i32),
+    CenterOnMonitor(WindowId),
+    SetSizeConstraints(WindowId, SizeConstraints),
+    SetOpacity(WindowId, f32),
+    SetTransparent(WindowId, bool),
+    SetClickThrough(WindowId, bool),
+    SetLevel(WindowId, WindowLevel),
+    SetToolWindow(WindowId, bool),
+    SetSkipTaskbar(WindowId, bool),
+    SetSkipPager(WindowId, bool),
+    SetCursor(WindowId, Cursor),
+    SetCursorVisible(WindowId, bool),
+    SetCursorGrab(WindowId, CursorGrab),
+    SetRawMouseMotion(WindowId, bool),
+    SetCursorPosition(WindowId, i32, i32),
+    /// A `width` by `height` buffer was presented
+    Present(WindowId, u32, u32),
+    /// A dialog was opened, `parent` is the window passed to the `*_with` methods
+    Dialog {
+        kind: DialogKind,
+        parent: Option<WindowId>,
+    },
+}
+
+/// Type of dialog that was opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogKind {
+    Prompt {
+        title: &'static str,
+        message: &'static str,
+    },
+    OpenFile,
+    SaveFile,
+    OpenFolder,
+    Color,
+    Font,
+}
+
+/// Pixels last presented to a headless window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// Snapshot of a headless window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowState {
+    pub id: WindowId,
+    pub title: String,
+    pub icon: Option<WindowIcon>,
+    pub small_icon: Option<WindowIcon>,
+    pub theme: Theme,
+    pub background: Background,
+    pub fixed_size: bool,
+    pub constraints: SizeConstraints,
+    pub size: (u32, u32),
+    pub position: (i32, i32),
+    pub scale_factor: f64,
+    pub decorations: bool,
+    pub opacity: f32,
+    pub transparent: bool,
+    pub click_through: bool,
+    pub level: WindowLevel,
+    pub tool_window: bool,
+    pub skip_taskbar: bool,
+    pub skip_pager: bool,
+    pub owner: Option<WindowId>,
+    pub modal: bool,
+    pub cursor: Cursor,
+    pub cursor_visible: bool,
+    pub cursor_grab: CursorGrab,
+    pub raw_mouse_motion: bool,
+    pub frame: Option<Frame>,
+
+    pub visible: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: Option<Fullscreen>,
+}
+
+thread_local! {
+    static CALLS: RefCell<Vec<Call>> = const { RefCell::new(Vec::new()) };
+    static EVENTS: RefCell<VecDeque<(WindowId, Event)>> = const { RefCell::new(VecDeque::new()) };
+    static BUTTONS: RefCell<VecDeque<Button>> = const { RefCell::new(VecDeque::new()) };
+    static ACTIONS: RefCell<VecDeque<DialogAction>> = const { RefCell::new(VecDeque::new()) };
+    /// Set when closing a window ends the event loop under the app's `ExitPolicy`
+    static QUIT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Check if the headless backend is active on this thread
+pub fn is_active() -> bool {
+    backend() == Backend::Headless
+}
+
+/// Queue an event for a window, it is passed to the app callback by the event loop
+pub fn send(id: WindowId, event: Event) {
+    EVENTS.with(|events| events.borrow_mut().push_back((id, event)));
+}
+
+/// Get every call made to the backend on this thread
+pub fn calls() -> Vec<Call> {
+    CALLS.with(|calls| calls.borrow().clone())
+}
+
+/// Get and clear the calls made to the backend on this thread
+pub fn take_calls() -> Vec<Call> {
+    CALLS.with(|calls| std::mem::take(&mut *calls.borrow_mut()))
+}
+
+/// Get the state of an open window
+pub fn window(id: WindowId) -> Option<WindowState> {
+    window::with_window(id, |window| window.state())
+}
+
+/// Get the state of every open window
+pub fn windows() -> Vec<WindowState> {
+    window::with_windows(|windows| windows.iter().map(|window| window.state()).collect())
+}
+
+/// Act as if the window moved to a monitor with another scale factor. Like the native backends
+/// this queues `Event::ScaleFactorChanged` and resizes the window to keep its logical size.
+pub fn set_scale_factor(id: WindowId, scale_factor: f64) -> Result<(), Error> {
+    window::set_scale_factor(id, scale_factor)
+}
+
+/// Queue the button the next prompt answers with, prompts answer with `Button::Cancel` when
+/// nothing is queued
+pub fn respond_prompt(button: Button) {
+    BUTTONS.with(|buttons| buttons.borrow_mut().push_back(button));
+}
+
+/// Queue the result of the next file, color or font dialog, dialogs are canceled when nothing is
+/// queued
+pub fn respond(action: DialogAction) {
+    ACTIONS.with(|actions| actions.borrow_mut().push_back(action));
+}
+
+/// Close every window, dropping their handlers and data, and clear recorded calls, queued events
+/// and dialog responses
+pub fn reset() {
+    window::with_windows(|windows| windows.clear());
+    crate::event::forget_all();
+    CALLS.with(|calls| calls.borrow_mut().clear());
+    EVENTS.with(|events| events.borrow_mut().clear());
+    BUTTONS.with(|buttons| buttons.borrow_mut().clear());
+    ACTIONS.with(|actions| actions.borrow_mut().clear());
+    QUIT.set(false);
+}
+
+fn record(call: Call) {
+    CALLS.with(|calls| calls.borrow_mut().push(call));
+}
+
+pub(crate) fn prompt(title: &'static str, message: &'static str) -> Button {
+    record(Call::Dialog {
+        kind: DialogKind::Prompt { title, message },
+        parent: None,
+    });
+    BUTTONS
+        .with(|buttons| buttons.borrow_mut().pop_front())
+        .unwrap_or(Button::Cancel)
+}
+
+pub(crate) fn dialog(kind: DialogKind, parent: Option<WindowId>) -> Result<DialogAction, Error> {
+    record(Call::Dialog { kind, parent });
+    Ok(ACTIONS
+        .with(|actions| actions.borrow_mut().pop_front())
+        .unwrap_or(DialogAction::Canceled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::App;
+    use crate::prelude::*;
+    use crate::Window;
+
+    #[test]
+    fn run_delivers_sent_events() {
+        let id = Window::builder()
+            .title("Editor")
+            .size(640, 480)
+            .backend(Backend::Headless)
+            .show()
+            .unwrap();
+        assert_eq!(calls(), vec![Call::Create(id), Call::Show(id)]);
+        let state = window(id).unwrap();
+        assert_eq!(state.title, "Editor");
+        assert_eq!(state.size, (640, 480));
+        assert!(state.visible);
+
+        send(id, Event::Focused(true));
+        send(id, Event::Close);
+        App::run_with(String::from("Focused"), |id, event, title| {
+            if let Event::Focused(true) = event {
+                id.set_title(title.as_ref().clone()).unwrap();
+            }
+        });
+
+        assert_eq!(
+            calls()[2..],
+            [Call::SetTitle(id, "Focused".to_string()), Call::Close(id)]
+        );
+        assert!(window(id).is_none());
+    }
+
+    #[test]
+    fn set_theme_is_recorded() {
+        let id = Window::builder().backend(Backend::Headless).show().unwrap();
+        id.set_theme(Theme::Dark).unwrap();
+        assert_eq!(calls().last(), Some(&Call::SetTheme(id, Theme::Dark)));
+        assert_eq!(window(id).unwrap().theme, Theme::Dark);
+    }
+
+    #[test]
+    fn handlers_get_their_window_events() {
+        use std::sync::{Arc, Mutex};
+
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let log = handled.clone();
+        let with_handler = Window::builder()
+            .backend(Backend::Headless)
+            .handler(move |_, event: Event| log.lock().unwrap().push(format!("{:?}", event)))
+            .show()
+            .unwrap();
+        let without = Window::builder().backend(Backend::Headless).show().unwrap();
+
+        send(with_handler, Event::Focused(true));
+        send(without, Event::Focused(true));
+        crate::event::close(with_handler);
+        let unhandled = Arc::new(Mutex::new(Vec::new()));
+        let log = unhandled.clone();
+        App::run(move |id, event, _| log.lock().unwrap().push(format!("{:?} {:?}", id, event)));
+
+        assert_eq!(
+            *handled.lock().unwrap(),
+            ["Repaint", "Focused(true)", "Close", "Destroyed"]
+        );
+        assert_eq!(
+            *unhandled.lock().unwrap(),
+            [
+                format!("{:?} Repaint", without),
+                format!("{:?} Focused(true)", without)
+            ]
+        );
+        assert!(window(without).is_some());
+    }
+
+    #[test]
+    fn window_data_is_dropped_with_the_window() {
+        use std::rc::Rc;
+
+        let id = Window::builder().backend(Backend::Headless).show().unwrap();
+        let other = Window::builder().backend(Backend::Headless).show().unwrap();
+        let marker = Rc::new(());
+        id.insert_data(marker.clone()).unwrap();
+        other.insert_data(marker.clone()).unwrap();
+        assert!(id.insert_data(String::from("a")).unwrap().is_none());
+        assert_eq!(*id.insert_data(String::from("b")).unwrap().unwrap(), "a");
+        assert_eq!(Rc::strong_count(&marker), 3);
+
+        crate::event::close(id);
+        App::run(move |window, event, _| {
+            if window == id && matches!(event, Event::Destroyed) {
+                // Still there while the app handles the last event
+                assert_eq!(*window.data::<String>().unwrap(), "b");
+            }
+        });
+        assert!(id.data::<String>().is_none());
+        assert!(id.insert_data(0u32).is_err());
+        assert_eq!(Rc::strong_count(&marker), 2);
+
+        reset();
+        assert!(other.data::<Rc<()>>().is_none());
+        assert_eq!(Rc::strong_count(&marker), 1);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::error::Error;
+use crate::monitor::{Monitor, VideoMode};
+use crate::window::WindowId;
+
+use super::window::{with_window, SCREEN_SIZE};
+
+/// The single `SCREEN_SIZE` monitor headless windows are placed on
+fn screen() -> Monitor {
+    Monitor {
+        handle: 1,
+        name: String::from("Headless"),
+        position: (0, 0),
+        size: SCREEN_SIZE,
+        work_area: (0, 0, SCREEN_SIZE.0, SCREEN_SIZE.1),
+        refresh_rate: Some(60_000),
+        scale_factor: 1.0,
+        primary: true,
+    }
+}
+
+pub fn all() -> Result<Vec<Monitor>, Error> {
+    Ok(vec![screen()])
+}
+
+pub fn primary() -> Result<Monitor, Error> {
+    Ok(screen())
+}
+
+pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+    with_window(id, |_| screen()).ok_or_else(|| Error::invalid_window(id))
+}
+
+/// The virtual monitor can switch to its own size and 720p
+pub fn video_modes(monitor: &Monitor) -> Result<Vec<VideoMode>, Error> {
+    Ok([SCREEN_SIZE, (1280, 720)]
+        .into_iter()
+        .map(|size| VideoMode {
+            size,
+            bit_depth: 32,
+            refresh_rate: Some(60_000),
+            monitor: monitor.clone(),
+        })
+        .collect())
+}
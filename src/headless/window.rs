@@ -0,0 +1,471 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use raw_window_handle::{HandleError, RawDisplayHandle, RawWindowHandle};
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::dpi::PhysicalSize;
+use crate::error::Error;
+use crate::event::{exit_after_close, set_handler, state_change, Event};
+use crate::icon::WindowIcon;
+use crate::style::Theme;
+use crate::window::{
+    Bounds, Fullscreen, SizeConstraints, WindowId, WindowLevel, WindowOptions, DEFAULT_SIZE,
+};
+
+use super::{record, send, Call, Frame, WindowState, QUIT};
+
+/// Size of the monitor headless windows are placed on
+pub const SCREEN_SIZE: (u32, u32) = (1920, 1080);
+
+thread_local! {
+    static WINDOWS: Mutex<Vec<Window>> = const { Mutex::new(Vec::new()) };
+    static NEXT_ID: Cell<isize> = const { Cell::new(1) };
+}
+
+pub(crate) fn with_windows<R>(f: impl FnOnce(&mut Vec<Window>) -> R) -> R {
+    WINDOWS.with(|windows| f(&mut windows.lock().unwrap()))
+}
+
+pub(crate) fn with_window<R>(id: WindowId, f: impl FnOnce(&mut Window) -> R) -> Option<R> {
+    with_windows(|windows| windows.iter_mut().find(|window| window.id == id).map(f))
+}
+
+/// Change a window and queue the events a native backend would send for the change
+fn update(id: WindowId, f: impl FnOnce(&mut Window)) -> Option<()> {
+    let events = with_window(id, |window| {
+        let (size, position) = (window.size, window.position);
+        let was = (window.minimized, window.maximized);
+        f(window);
+
+        let mut events = Vec::new();
+        events.extend(state_change(was, window.minimized, window.maximized));
+        if window.size != size {
+            events.push(Event::Resized {
+                width: window.size.0,
+                height: window.size.1,
+            });
+        }
+        if window.position != position {
+            events.push(Event::Moved {
+                x: window.position.0,
+                y: window.position.1,
+            });
+        }
+        events
+    })?;
+    for event in events {
+        send(id, event);
+    }
+    Some(())
+}
+
+pub(crate) fn set_scale_factor(id: WindowId, scale_factor: f64) -> Result<(), Error> {
+    let size = with_window(id, |window| {
+        let size = PhysicalSize::from(window.size)
+            .to_logical(window.scale_factor)
+            .to_physical(scale_factor);
+        window.scale_factor = scale_factor;
+        size
+    })
+    .ok_or_else(|| Error::invalid_window(id))?;
+    send(
+        id,
+        Event::ScaleFactorChanged {
+            scale_factor,
+            width: size.width,
+            height: size.height,
+        },
+    );
+    update(id, |window| window.size = size.into()).ok_or_else(|| Error::invalid_window(id))
+}
+
+pub struct Window {
+    id: WindowId,
+    options: WindowOptions,
+
+    size: (u32, u32),
+    position: (i32, i32),
+    scale_factor: f64,
+    visible: bool,
+    minimized: bool,
+    maximized: bool,
+    fullscreen: Option<Fullscreen>,
+    /// Size and position to go back to when leaving fullscreen
+    windowed: Option<((u32, u32), (i32, i32))>,
+    frame: Option<Frame>,
+}
+
+impl Window {
+    pub fn state(&self) -> WindowState {
+        WindowState {
+            id: self.id,
+            title: self.options.title.clone(),
+            icon: self.options.icon.clone(),
+            small_icon: self.options.small_icon.clone(),
+            theme: self.options.theme,
+            background: self.options.background,
+            fixed_size: self.options.fixed_size,
+            constraints: self.options.constraints,
+            size: self.size,
+            position: self.position,
+            scale_factor: self.scale_factor,
+            decorations: self.options.decorations,
+            opacity: self.options.opacity,
+            transparent: self.options.transparent,
+            click_through: self.options.click_through,
+            level: self.options.level,
+            tool_window: self.options.tool_window,
+            skip_taskbar: self.options.skip_taskbar,
+            skip_pager: self.options.skip_pager,
+            owner: self.options.owner,
+            modal: self.options.modal,
+            cursor: self.options.cursor.clone(),
+            cursor_visible: self.options.cursor_visible,
+            cursor_grab: self.options.cursor_grab,
+            raw_mouse_motion: self.options.raw_mouse_motion,
+            frame: self.frame.clone(),
+            visible: self.visible,
+            minimized: self.minimized,
+            maximized: self.maximized,
+            fullscreen: self.fullscreen.clone(),
+        }
+    }
+
+    pub fn create(options: WindowOptions) -> Result<WindowId, Error> {
+        let id = WindowId(NEXT_ID.get());
+        NEXT_ID.set(id.0 + 1);
+
+        let show = options.show;
+        // Headless windows start on a monitor with a scale factor of 1
+        let mut size = options
+            .size
+            .map_or(DEFAULT_SIZE, |size| size.to_physical(1.0).into());
+        if !options.fixed_size {
+            size = options.constraints.constrain(size, false);
+        }
+        set_handler(id, options.handler.as_ref());
+        with_windows(|windows| {
+            windows.push(Window {
+                id,
+                size,
+                position: (0, 0),
+                scale_factor: 1.0,
+                options,
+                visible: false,
+                minimized: false,
+                maximized: false,
+                fullscreen: None,
+                windowed: None,
+                frame: None,
+            })
+        });
+        record(Call::Create(id));
+
+        if show {
+            Window::show(id);
+        }
+        Ok(id)
+    }
+
+    pub fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error> {
+        record(Call::SetTheme(id, theme));
+        with_window(id, |window| window.options.theme = theme)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Show the window, like the native backends this triggers a repaint
+    pub fn show(id: WindowId) {
+        record(Call::Show(id));
+        if update(id, |window| {
+            window.visible = true;
+            window.minimized = false;
+        })
+        .is_some()
+        {
+            send(id, Event::Repaint);
+        }
+    }
+
+    /// Hide the window
+    pub fn hide(id: WindowId) {
+        record(Call::Hide(id));
+        with_window(id, |window| window.visible = false);
+    }
+
+    /// Minimize the window
+    pub fn minimize(id: WindowId) {
+        record(Call::Minimize(id));
+        update(id, |window| window.minimized = true);
+    }
+
+    /// Restore the window
+    pub fn restore(id: WindowId) {
+        record(Call::Restore(id));
+        update(id, |window| {
+            window.visible = true;
+            window.minimized = false;
+            window.maximized = false;
+        });
+    }
+
+    /// Maximize the window
+    pub fn maximize(id: WindowId) {
+        record(Call::Maximize(id));
+        update(id, |window| {
+            window.visible = true;
+            window.minimized = false;
+            window.maximized = true;
+        });
+    }
+
+    /// Get the window size, headless windows have no frame so this is also the outer size
+    pub fn client_size(id: WindowId) -> Result<(u32, u32), Error> {
+        with_window(id, |window| window.size).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn outer_size(id: WindowId) -> Result<(u32, u32), Error> {
+        Window::client_size(id)
+    }
+
+    pub fn position(id: WindowId) -> Result<(i32, i32), Error> {
+        with_window(id, |window| window.position).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn title(id: WindowId) -> Result<String, Error> {
+        with_window(id, |window| window.options.title.clone())
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn exists(id: WindowId) -> bool {
+        with_window(id, |_| ()).is_some()
+    }
+
+    pub fn is_visible(id: WindowId) -> bool {
+        with_window(id, |window| window.visible && !window.minimized).unwrap_or(false)
+    }
+
+    pub fn is_minimized(id: WindowId) -> bool {
+        with_window(id, |window| window.minimized).unwrap_or(false)
+    }
+
+    pub fn is_maximized(id: WindowId) -> bool {
+        with_window(id, |window| window.maximized).unwrap_or(false)
+    }
+
+    /// Maximizing doesn't resize headless windows, only fullscreen keeps other bounds
+    pub fn normal_bounds(id: WindowId) -> Result<Bounds, Error> {
+        with_window(id, |window| {
+            let (size, position) = window.windowed.unwrap_or((window.size, window.position));
+            (Some(position), size)
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn is_fullscreen(id: WindowId) -> bool {
+        with_window(id, |window| window.fullscreen.is_some()).unwrap_or(false)
+    }
+
+    pub fn fullscreen(id: WindowId) -> Option<Fullscreen> {
+        with_window(id, |window| window.fullscreen.clone()).flatten()
+    }
+
+    /// Cover the virtual `SCREEN_SIZE` monitor, or a mode sized area of it
+    pub fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        record(Call::SetFullscreen(id, fullscreen.clone()));
+        update(id, |window| {
+            match &fullscreen {
+                Some(mode) => {
+                    if window.windowed.is_none() {
+                        window.windowed = Some((window.size, window.position));
+                    }
+                    window.size = match mode {
+                        Fullscreen::Borderless(monitor) => {
+                            monitor.as_ref().map_or(SCREEN_SIZE, |monitor| monitor.size)
+                        }
+                        Fullscreen::Exclusive(mode) => mode.size,
+                    };
+                    window.position = (0, 0);
+                }
+                None => {
+                    if let Some((size, position)) = window.windowed.take() {
+                        window.size = size;
+                        window.position = position;
+                    }
+                }
+            }
+            window.fullscreen = fullscreen;
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn scale_factor(id: WindowId) -> f64 {
+        with_window(id, |window| window.scale_factor).unwrap_or(1.0)
+    }
+
+    pub fn set_title(id: WindowId, title: String) -> Result<(), Error> {
+        record(Call::SetTitle(id, title.clone()));
+        with_window(id, |window| window.options.title = title)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        record(Call::SetIcon(id, icon.clone()));
+        with_window(id, |window| window.options.icon = icon)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_small_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        record(Call::SetSmallIcon(id, icon.clone()));
+        with_window(id, |window| window.options.small_icon = icon)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error> {
+        record(Call::SetSize(id, width, height));
+        update(id, |window| window.size = (width, height)).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        record(Call::SetPosition(id, x, y));
+        update(id, |window| window.position = (x, y)).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Center the window on a virtual `SCREEN_SIZE` monitor
+    pub fn center_on_monitor(id: WindowId) -> Result<(), Error> {
+        record(Call::CenterOnMonitor(id));
+        update(id, |window| {
+            window.position = (
+                (SCREEN_SIZE.0 as i32 - window.size.0 as i32) / 2,
+                (SCREEN_SIZE.1 as i32 - window.size.1 as i32) / 2,
+            );
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error> {
+        with_window(id, |window| window.options.constraints)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Store the constraints and shrink or grow the window to fit them
+    pub fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error> {
+        record(Call::SetSizeConstraints(id, constraints));
+        update(id, |window| {
+            window.options.constraints = constraints;
+            if !window.options.fixed_size {
+                window.size = constraints.constrain(window.size, false);
+            }
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error> {
+        record(Call::SetOpacity(id, opacity));
+        with_window(id, |window| window.options.opacity = opacity)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error> {
+        record(Call::SetTransparent(id, transparent));
+        with_window(id, |window| window.options.transparent = transparent)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error> {
+        record(Call::SetClickThrough(id, click_through));
+        with_window(id, |window| window.options.click_through = click_through)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_level(id: WindowId, level: WindowLevel) -> Result<(), Error> {
+        record(Call::SetLevel(id, level));
+        with_window(id, |window| window.options.level = level)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_tool_window(id: WindowId, tool_window: bool) -> Result<(), Error> {
+        record(Call::SetToolWindow(id, tool_window));
+        with_window(id, |window| window.options.tool_window = tool_window)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_skip_taskbar(id: WindowId, skip_taskbar: bool) -> Result<(), Error> {
+        record(Call::SetSkipTaskbar(id, skip_taskbar));
+        with_window(id, |window| window.options.skip_taskbar = skip_taskbar)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_skip_pager(id: WindowId, skip_pager: bool) -> Result<(), Error> {
+        record(Call::SetSkipPager(id, skip_pager));
+        with_window(id, |window| window.options.skip_pager = skip_pager)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_cursor(id: WindowId, cursor: Cursor) -> Result<(), Error> {
+        record(Call::SetCursor(id, cursor.clone()));
+        with_window(id, |window| window.options.cursor = cursor)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_cursor_visible(id: WindowId, visible: bool) -> Result<(), Error> {
+        record(Call::SetCursorVisible(id, visible));
+        with_window(id, |window| window.options.cursor_visible = visible)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_cursor_grab(id: WindowId, grab: CursorGrab) -> Result<(), Error> {
+        record(Call::SetCursorGrab(id, grab));
+        with_window(id, |window| window.options.cursor_grab = grab)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_raw_mouse_motion(id: WindowId, enabled: bool) -> Result<(), Error> {
+        record(Call::SetRawMouseMotion(id, enabled));
+        with_window(id, |window| window.options.raw_mouse_motion = enabled)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_cursor_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        record(Call::SetCursorPosition(id, x, y));
+        with_window(id, |_| ()).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Keep a copy of the pixels so tests can check what was drawn
+    pub fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        record(Call::Present(id, width, height));
+        with_window(id, |window| {
+            window.frame = Some(Frame {
+                width,
+                height,
+                pixels: pixels[..width as usize * height as usize].to_vec(),
+            })
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// There is no native window to render to
+    pub fn raw_window_handle(_id: WindowId) -> Result<RawWindowHandle, HandleError> {
+        Err(HandleError::NotSupported)
+    }
+
+    pub fn raw_display_handle(_id: WindowId) -> Result<RawDisplayHandle, HandleError> {
+        Err(HandleError::NotSupported)
+    }
+
+    pub fn close(id: WindowId) -> Result<(), Error> {
+        record(Call::Close(id));
+        let removed = with_windows(|windows| {
+            let index = windows.iter().position(|window| window.id == id)?;
+            windows.remove(index);
+            if exit_after_close(id, windows.len()) {
+                QUIT.set(true);
+            }
+            Some(())
+        });
+        if removed.is_some() {
+            send(id, Event::Destroyed);
+        }
+        Ok(())
+    }
+}
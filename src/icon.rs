@@ -0,0 +1,163 @@
+//! Window icons loaded from RGBA pixels, PNG images or multi-size ICO files.
+//!
+//! A [`WindowIcon`] holds every size it was given, each backend picks the sizes it needs. Windows
+//! uses the image closest to the system icon size for the large and small icon, X11 passes all of
+//! them to the window manager through `_NET_WM_ICON`.
+//!
+//! ```no_run
+//! use storytree_native::prelude::*;
+//! use storytree_native::{Window, WindowIcon};
+//!
+//! let icon = WindowIcon::from_path("examples/images/NativeUI.ico").unwrap();
+//! let id = Window::builder().icon(icon).show().unwrap();
+//! id.set_icon(Some(WindowIcon::from_rgba(vec![0xFF; 16 * 16 * 4], 16, 16).unwrap()))
+//!     .unwrap();
+//! ```
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::error::Error;
+
+/// First bytes of every PNG file
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// One size of an icon as rows of RGBA bytes from top to bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconImage {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl IconImage {
+    /// Check the size and keep `width * height` RGBA pixels, extra bytes are ignored
+    pub(crate) fn new(mut rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::invalid_parameter(format!(
+                "Image size {}x{} is empty",
+                width, height
+            )));
+        }
+        let len = width as usize * height as usize * 4;
+        if rgba.len() < len {
+            return Err(Error::invalid_buffer(rgba.len() / 4, width, height));
+        }
+        rgba.truncate(len);
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the pixels as RGBA bytes with straight alpha
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// Get the pixels as `0xAARRGGBB` values
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(crate) fn argb(&self) -> impl Iterator<Item = u32> + '_ {
+        self.rgba
+            .chunks_exact(4)
+            .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+    }
+}
+
+impl From<ico::IconImage> for IconImage {
+    fn from(image: ico::IconImage) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.rgba_data().to_vec(),
+        }
+    }
+}
+
+/// Icon shown in the title bar, taskbar and task switcher
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowIcon {
+    images: Vec<IconImage>,
+}
+
+impl WindowIcon {
+    /// Create an icon from `width * height` RGBA pixels, extra bytes are ignored
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, Error> {
+        Ok(Self {
+            images: vec![IconImage::new(rgba, width, height)?],
+        })
+    }
+
+    /// Decode a PNG image
+    pub fn from_png(bytes: &[u8]) -> Result<Self, Error> {
+        let image = ico::IconImage::read_png(bytes)?;
+        Ok(Self {
+            images: vec![image.into()],
+        })
+    }
+
+    /// Decode every size in an ICO file, images that fail to decode are skipped
+    pub fn from_ico(bytes: &[u8]) -> Result<Self, Error> {
+        let icon_dir = ico::IconDir::read(Cursor::new(bytes))?;
+        let images: Vec<IconImage> = icon_dir
+            .entries()
+            .iter()
+            .filter_map(|entry| match entry.decode() {
+                Ok(image) => Some(image.into()),
+                Err(err) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("{}", Error::from(err));
+                    None
+                }
+            })
+            .collect();
+        if images.is_empty() {
+            return Err(Error::invalid_parameter(
+                "ICO file has no images that decode",
+            ));
+        }
+        Ok(Self { images })
+    }
+
+    /// Load a PNG or ICO file, the format is read from the file contents
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(PNG_SIGNATURE) {
+            WindowIcon::from_png(&bytes)
+        } else {
+            WindowIcon::from_ico(&bytes)
+        }
+    }
+
+    /// Add the sizes of another icon to this one
+    pub fn with(mut self, other: WindowIcon) -> Self {
+        self.images.extend(other.images);
+        self
+    }
+
+    /// Get every size of the icon
+    pub fn images(&self) -> &[IconImage] {
+        &self.images
+    }
+
+    /// Get the smallest image at least `size` pixels wide, or the largest one if none are
+    #[cfg_attr(target_os = "linux", allow(dead_code))]
+    pub(crate) fn best(&self, size: u32) -> &IconImage {
+        self.images
+            .iter()
+            .filter(|image| image.width >= size)
+            .min_by_key(|image| image.width)
+            .or_else(|| self.images.iter().max_by_key(|image| image.width))
+            .expect("icons have at least one image")
+    }
+}
@@ -1,25 +1,51 @@
-#[cfg(target_os = "unix")]
+#[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(feature = "config")]
+pub mod config;
+pub mod cursor;
+mod data;
+pub mod dpi;
 pub mod event;
+pub mod headless;
+pub mod icon;
+pub mod monitor;
+pub mod placement;
 pub mod style;
+pub mod surface;
 mod window;
-pub use window::Window;
+pub use cursor::{Cursor, CursorGrab, CursorIcon};
+pub use icon::WindowIcon;
+pub use monitor::{Monitor, VideoMode};
+pub use placement::WindowPlacement;
+pub use window::{
+    Backend, Fullscreen, HitTest, HitTestRegions, HitTester, ResizeEdge, SizeConstraints, Window,
+    WindowId, WindowLevel, WindowOptions,
+};
 pub mod error;
 pub mod modal;
+pub use raw_window_handle;
 pub mod prelude;
 
-/// Check if the window is in fullscreen mode
-pub fn is_maxamized(id: isize) -> bool {
-    #[cfg(target_os = "windows")]
-    windows::is_maxamized(id)
+use window::WindowContext;
+
+/// Check if the window is maximized
+pub fn is_maxamized(id: WindowId) -> bool {
+    Window::is_maximized(id)
 }
 
-pub fn toggle_fullscreen(id: isize) {
-    #[cfg(target_os = "windows")]
-    windows::window::toggle_fullscreen(id)
+/// Switch between borderless fullscreen on the window's monitor and the normal window
+pub fn toggle_fullscreen(id: WindowId) {
+    let fullscreen = match Window::fullscreen(id) {
+        Some(_) => None,
+        None => Some(Fullscreen::Borderless(None)),
+    };
+    if let Err(err) = Window::set_fullscreen(id, fullscreen) {
+        #[cfg(debug_assertions)]
+        eprintln!("{}", err);
+    }
 }
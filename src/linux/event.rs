@@ -0,0 +1,26 @@
+use crate::event::{Event, IntoEventResult, State};
+use crate::headless;
+use crate::window::{backend, Backend, WindowId};
+
+use super::{wayland, x11};
+
+pub fn close(id: WindowId) {
+    match backend() {
+        Backend::Headless => headless::event::close(id),
+        Backend::Wayland => wayland::event::close(id),
+        _ => x11::event::close(id),
+    }
+}
+
+pub fn run<R, F, T>(state: State<T>, callback: F)
+where
+    R: IntoEventResult,
+    F: (Fn(WindowId, Event, State<T>) -> R) + 'static + Sync + Send,
+    T: Send + Sync + Clone + 'static,
+{
+    match backend() {
+        Backend::Headless => headless::event::run(state, callback),
+        Backend::Wayland => wayland::event::run(state, callback),
+        _ => x11::event::run(state, callback),
+    }
+}
@@ -0,0 +1,16 @@
+pub mod event;
+pub mod monitor;
+mod mouse;
+pub mod wayland;
+pub mod window;
+pub mod x11;
+
+/// Check if the desktop prefers a dark theme.
+///
+/// Neither X11 nor Wayland have a system wide setting for this so the GTK theme override is used
+/// as a hint.
+pub fn is_dark_mode() -> bool {
+    std::env::var("GTK_THEME")
+        .map(|theme| theme.to_lowercase().contains("dark"))
+        .unwrap_or(false)
+}
@@ -0,0 +1,33 @@
+use crate::error::Error;
+use crate::headless;
+use crate::monitor::{Monitor, VideoMode};
+use crate::window::{backend, Backend, WindowId};
+
+use super::{wayland, x11};
+
+/// Call the same function in the monitor module of the active backend
+macro_rules! dispatch {
+    ($name: ident($($arg: expr),*)) => {
+        match backend() {
+            Backend::Headless => headless::monitor::$name($($arg),*),
+            Backend::Wayland => wayland::monitor::$name($($arg),*),
+            _ => x11::monitor::$name($($arg),*),
+        }
+    };
+}
+
+pub fn all() -> Result<Vec<Monitor>, Error> {
+    dispatch!(all())
+}
+
+pub fn primary() -> Result<Monitor, Error> {
+    dispatch!(primary())
+}
+
+pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+    dispatch!(from_window(id))
+}
+
+pub fn video_modes(monitor: &Monitor) -> Result<Vec<VideoMode>, Error> {
+    dispatch!(video_modes(monitor))
+}
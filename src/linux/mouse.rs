@@ -0,0 +1,30 @@
+use crate::event::mouse::{MouseButton, MouseEventType};
+use crate::window::WindowId;
+
+/// Max time in milliseconds between two presses for them to count as a double click
+const DOUBLE_CLICK_TIME: u32 = 500;
+
+/// X11 and Wayland have no double click event so presses are tracked to synthesize one
+#[derive(Default)]
+pub struct ClickTracker {
+    last: Option<(WindowId, MouseButton, u32)>,
+}
+
+impl ClickTracker {
+    /// Get the event type for a button press, detecting double clicks
+    pub fn press(&mut self, window: WindowId, button: MouseButton, time: u32) -> MouseEventType {
+        match self.last.take() {
+            Some((last_window, last, last_time))
+                if last_window == window
+                    && last == button
+                    && time.wrapping_sub(last_time) <= DOUBLE_CLICK_TIME =>
+            {
+                MouseEventType::Double(button)
+            }
+            _ => {
+                self.last = Some((window, button, time));
+                MouseEventType::Down(button)
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::event::keyboard::KeyCode;
+
+/// Linux evdev key code as defined in `linux/input-event-codes.h`.
+///
+/// Wayland sends these instead of keysyms. Without an xkb keymap parser they are mapped as if the
+/// keyboard uses the US layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scancode(pub u32);
+
+impl From<Scancode> for KeyCode {
+    fn from(v: Scancode) -> Self {
+        match v.0 {
+            1 => KeyCode::Escape,
+            2 => KeyCode::Char('1'),
+            3 => KeyCode::Char('2'),
+            4 => KeyCode::Char('3'),
+            5 => KeyCode::Char('4'),
+            6 => KeyCode::Char('5'),
+            7 => KeyCode::Char('6'),
+            8 => KeyCode::Char('7'),
+            9 => KeyCode::Char('8'),
+            10 => KeyCode::Char('9'),
+            11 => KeyCode::Char('0'),
+            12 => KeyCode::Char('-'),
+            13 => KeyCode::Char('+'),
+            14 => KeyCode::Back,
+            15 => KeyCode::Tab,
+            16 => KeyCode::Char('q'),
+            17 => KeyCode::Char('w'),
+            18 => KeyCode::Char('e'),
+            19 => KeyCode::Char('r'),
+            20 => KeyCode::Char('t'),
+            21 => KeyCode::Char('y'),
+            22 => KeyCode::Char('u'),
+            23 => KeyCode::Char('i'),
+            24 => KeyCode::Char('o'),
+            25 => KeyCode::Char('p'),
+            26 => KeyCode::Char('['),
+            27 => KeyCode::Char(']'),
+            28 | 96 => KeyCode::Return,
+            29 | 97 => KeyCode::Control,
+            30 => KeyCode::Char('a'),
+            31 => KeyCode::Char('s'),
+            32 => KeyCode::Char('d'),
+            33 => KeyCode::Char('f'),
+            34 => KeyCode::Char('g'),
+            35 => KeyCode::Char('h'),
+            36 => KeyCode::Char('j'),
+            37 => KeyCode::Char('k'),
+            38 => KeyCode::Char('l'),
+            39 => KeyCode::Char(';'),
+            40 => KeyCode::Char('\''),
+            41 => KeyCode::Char('~'),
+            42 | 54 => KeyCode::Shift,
+            43 => KeyCode::Char('\\'),
+            44 => KeyCode::Char('z'),
+            45 => KeyCode::Char('x'),
+            46 => KeyCode::Char('c'),
+            47 => KeyCode::Char('v'),
+            48 => KeyCode::Char('b'),
+            49 => KeyCode::Char('n'),
+            50 => KeyCode::Char('m'),
+            51 => KeyCode::Char(','),
+            52 => KeyCode::Char('.'),
+            53 => KeyCode::Char('/'),
+            55 => KeyCode::Multiply,
+            56 | 100 => KeyCode::Alt,
+            57 => KeyCode::Space,
+            58 => KeyCode::Capital,
+            59 => KeyCode::F1,
+            60 => KeyCode::F2,
+            61 => KeyCode::F3,
+            62 => KeyCode::F4,
+            63 => KeyCode::F5,
+            64 => KeyCode::F6,
+            65 => KeyCode::F7,
+            66 => KeyCode::F8,
+            67 => KeyCode::F9,
+            68 => KeyCode::F10,
+            69 => KeyCode::NumLock,
+            70 => KeyCode::Scroll,
+            71 => KeyCode::NumPad7,
+            72 => KeyCode::NumPad8,
+            73 => KeyCode::NumPad9,
+            74 => KeyCode::Subtract,
+            75 => KeyCode::NumPad4,
+            76 => KeyCode::NumPad5,
+            77 => KeyCode::NumPad6,
+            78 => KeyCode::Add,
+            79 => KeyCode::NumPad1,
+            80 => KeyCode::NumPad2,
+            81 => KeyCode::NumPad3,
+            82 => KeyCode::NumPad0,
+            83 => KeyCode::Decimal,
+            86 => KeyCode::OEM102,
+            87 => KeyCode::F11,
+            88 => KeyCode::F12,
+            92 => KeyCode::Convert,
+            94 => KeyCode::NonConvert,
+            98 => KeyCode::Divide,
+            99 => KeyCode::Snapshot,
+            102 => KeyCode::Home,
+            103 => KeyCode::Up,
+            104 => KeyCode::PageUp,
+            105 => KeyCode::Left,
+            106 => KeyCode::Right,
+            107 => KeyCode::End,
+            108 => KeyCode::Down,
+            109 => KeyCode::PageDown,
+            110 => KeyCode::Insert,
+            111 => KeyCode::Delete,
+            113 => KeyCode::VolumeMute,
+            114 => KeyCode::VolumeDown,
+            115 => KeyCode::VolumeUp,
+            119 => KeyCode::Pause,
+            121 => KeyCode::Separator,
+            122 => KeyCode::KanaHangul,
+            123 => KeyCode::Kanji,
+            125 => KeyCode::LWin,
+            126 => KeyCode::RWin,
+            127 => KeyCode::Apps,
+            138 => KeyCode::Help,
+            140 => KeyCode::LaunchApp2,
+            142 => KeyCode::Sleep,
+            155 => KeyCode::LaunchMail,
+            156 => KeyCode::BrowserFavorites,
+            157 => KeyCode::LaunchApp1,
+            158 => KeyCode::BrowserBack,
+            159 => KeyCode::BrowserForward,
+            163 => KeyCode::MediaNext,
+            164 => KeyCode::MediaPlayPause,
+            165 => KeyCode::MediaPrev,
+            166 => KeyCode::MediaStop,
+            172 => KeyCode::BrowserHome,
+            173 => KeyCode::BrowserRefresh,
+            183 => KeyCode::F13,
+            184 => KeyCode::F14,
+            185 => KeyCode::F15,
+            186 => KeyCode::F16,
+            187 => KeyCode::F17,
+            188 => KeyCode::F18,
+            189 => KeyCode::F19,
+            190 => KeyCode::F20,
+            191 => KeyCode::F21,
+            192 => KeyCode::F22,
+            193 => KeyCode::F23,
+            194 => KeyCode::F24,
+            207 => KeyCode::Play,
+            217 => KeyCode::BrowserSearch,
+            226 => KeyCode::LaunchMediaSelect,
+            353 => KeyCode::Select,
+            372 => KeyCode::Zoom,
+            other => KeyCode::Unkown(other as isize),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+mod keyboard;
+
+use wayland_client::protocol::{wl_keyboard, wl_pointer};
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+
+use crate::event::keyboard::{KeyCode, KeyEvent};
+use crate::event::mouse::{MouseButton, MouseEvent, MouseEventType};
+use crate::event::{Event, IntoEventResult, State};
+use crate::window::{HitTest, WindowId};
+
+use super::window::Window;
+use super::{window_id, with_client, State as WaylandState};
+use keyboard::Scancode;
+
+/// Ask a window to close. This goes through the event loop the same way the compositor's close
+/// request does so `Event::Close` is still delivered.
+pub fn close(id: WindowId) {
+    let _ = with_client(|client| {
+        client.state.events.push_back((id, Event::Close));
+        Ok(())
+    });
+}
+
+pub fn run<R, F, T>(state: State<T>, callback: F)
+where
+    R: IntoEventResult,
+    F: (Fn(WindowId, Event, State<T>) -> R) + 'static + Sync + Send,
+    T: Send + Sync + Clone + 'static,
+{
+    loop {
+        // The client is only borrowed while dispatching so the callback can use windows
+        let result = with_client(|client| {
+            let wayland = &mut client.state;
+            if wayland.events.is_empty() && !wayland.quit {
+                client.queue.blocking_dispatch(wayland)?;
+            }
+            Ok((wayland.events.drain(..).collect::<Vec<_>>(), wayland.quit))
+        });
+        let (events, quit) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                #[cfg(debug_assertions)]
+                eprintln!("{}", err);
+                break;
+            }
+        };
+
+        for (id, event) in events {
+            match event {
+                Event::Close => {
+                    let result = { callback(id, Event::Close, state.clone()).into_event_result() };
+                    if result {
+                        let _ = Window::close(id);
+                    }
+                }
+                Event::Destroyed => {
+                    callback(id, Event::Destroyed, state.clone());
+                    // Kept until now so the handler and data outlive the event
+                    crate::event::forget(id);
+                }
+                // Modal windows keep input from reaching their owner
+                Event::Keyboard(_) | Event::Mouse(_) if Window::is_blocked(id) => {}
+                event => {
+                    callback(id, event, state.clone());
+                }
+            }
+        }
+
+        if quit {
+            break;
+        }
+    }
+}
+
+fn mouse_event(position: (f64, f64), etype: MouseEventType) -> MouseEvent {
+    MouseEvent {
+        x: position.0.max(0.0) as u16,
+        y: position.1.max(0.0) as u16,
+        etype,
+    }
+}
+
+impl MouseButton {
+    /// Convert a linux `BTN_*` code into a mouse button
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0x110 => Some(MouseButton::Left),
+            0x111 => Some(MouseButton::Right),
+            0x112 => Some(MouseButton::Middle),
+            0x113 => Some(MouseButton::X1),
+            0x114 => Some(MouseButton::X2),
+            _ => None,
+        }
+    }
+}
+
+impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Enter { surface, .. } => {
+                let id = window_id(&surface);
+                state.keyboard_focus = Some(id);
+                state.events.push_back((id, Event::Focused(true)));
+            }
+            wl_keyboard::Event::Leave { surface, .. } => {
+                state.keyboard_focus = None;
+                state
+                    .events
+                    .push_back((window_id(&surface), Event::Focused(false)));
+            }
+            // Compositors leave key repeat to clients, `KeyHold` isn't synthesized yet
+            wl_keyboard::Event::Key {
+                key,
+                state: key_state,
+                ..
+            } => {
+                if let Some(id) = state.keyboard_focus {
+                    let code = KeyCode::from(Scancode(key));
+                    let event = match key_state {
+                        WEnum::Value(wl_keyboard::KeyState::Pressed) => KeyEvent::KeyDown(code),
+                        _ => KeyEvent::KeyUp(code),
+                    };
+                    state.events.push_back((id, Event::Keyboard(event)));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_pointer::WlPointer, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_pointer::WlPointer,
+        event: wl_pointer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_pointer::Event::Enter {
+                surface,
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                let id = window_id(&surface);
+                state.pointer_focus = Some(id);
+                state.set_pointer_position(surface_x, surface_y);
+                state.events.push_back((
+                    id,
+                    Event::Mouse(mouse_event(state.pointer_position, MouseEventType::Hover)),
+                ));
+            }
+            wl_pointer::Event::Leave { .. } => state.pointer_focus = None,
+            wl_pointer::Event::Motion {
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                state.set_pointer_position(surface_x, surface_y);
+                if let Some(id) = state.pointer_focus {
+                    state.events.push_back((
+                        id,
+                        Event::Mouse(mouse_event(state.pointer_position, MouseEventType::Move)),
+                    ));
+                }
+            }
+            wl_pointer::Event::Button {
+                serial,
+                time,
+                button,
+                state: button_state,
+            } => {
+                if let (Some(id), Some(button)) =
+                    (state.pointer_focus, MouseButton::from_code(button))
+                {
+                    let etype = match button_state {
+                        WEnum::Value(wl_pointer::ButtonState::Pressed) => {
+                            state.clicks.press(id, button, time)
+                        }
+                        _ => MouseEventType::Up(button),
+                    };
+                    // Left clicks on the frame regions of a window without decorations move,
+                    // resize and press buttons instead of reaching the app
+                    let position = state.pointer_position;
+                    let seat = state.seat.clone();
+                    let hit = match (button, state.window(id)) {
+                        (MouseButton::Left, Some(window)) => {
+                            window.frame_click(seat.as_ref(), serial, &etype, position)
+                        }
+                        _ => HitTest::Client,
+                    };
+                    match (hit, &etype) {
+                        (HitTest::Client, _) => state
+                            .events
+                            .push_back((id, Event::Mouse(mouse_event(position, etype)))),
+                        (HitTest::Close, MouseEventType::Up(_)) => {
+                            state.events.push_back((id, Event::Close))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            wl_pointer::Event::Axis { axis, value, .. } => {
+                if let Some(id) = state.pointer_focus {
+                    // Positive values scroll down or right
+                    let direction = if value > 0.0 { 1 } else { -1 };
+                    let etype = match axis {
+                        WEnum::Value(wl_pointer::Axis::HorizontalScroll) => {
+                            MouseEventType::HScroll(direction)
+                        }
+                        _ => MouseEventType::Scroll(-direction),
+                    };
+                    state
+                        .events
+                        .push_back((id, Event::Mouse(mouse_event(state.pointer_position, etype))));
+                }
+            }
+            _ => {}
+        }
+    }
+}
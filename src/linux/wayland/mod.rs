@@ -0,0 +1,308 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use wayland_client::backend::WaylandError;
+use wayland_client::protocol::{
+    wl_compositor::WlCompositor, wl_keyboard::WlKeyboard, wl_pointer::WlPointer,
+    wl_region::WlRegion, wl_registry, wl_seat, wl_shm::WlShm, wl_shm_pool::WlShmPool,
+    wl_surface::WlSurface,
+};
+use wayland_client::{
+    delegate_noop, ConnectError, Connection, Dispatch, DispatchError, EventQueue, Proxy,
+    QueueHandle, WEnum,
+};
+use wayland_protocols::xdg::decoration::zv1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1;
+use wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+
+use crate::error::Error;
+use crate::event::Event;
+use crate::linux::mouse::ClickTracker;
+use crate::window::WindowId;
+
+pub mod event;
+pub mod monitor;
+pub mod window;
+
+use monitor::Output;
+use window::Window;
+
+/// Client side state that wayland events are dispatched to
+pub struct State {
+    pub qh: QueueHandle<State>,
+
+    pub compositor: Option<WlCompositor>,
+    pub shm: Option<WlShm>,
+    pub wm_base: Option<XdgWmBase>,
+    pub decoration_manager: Option<ZxdgDecorationManagerV1>,
+    pub seat: Option<wl_seat::WlSeat>,
+    pub keyboard: Option<WlKeyboard>,
+    pub pointer: Option<WlPointer>,
+
+    pub windows: Vec<Window>,
+    pub outputs: Vec<Output>,
+    /// Events waiting to be passed to the app callback
+    pub events: VecDeque<(WindowId, Event)>,
+    /// Set when destroying a window ends the event loop under the app's `ExitPolicy`
+    pub quit: bool,
+
+    pub keyboard_focus: Option<WindowId>,
+    pub pointer_focus: Option<WindowId>,
+    /// Pointer position in buffer pixels
+    pub pointer_position: (f64, f64),
+    pub clicks: ClickTracker,
+}
+
+impl State {
+    pub fn window(&mut self, id: WindowId) -> Option<&mut Window> {
+        self.windows.iter_mut().find(|window| window.id() == id)
+    }
+
+    /// Queue events for whatever changed on a window since it was last reported
+    pub fn report_changes(&mut self, id: WindowId) {
+        if let Some(window) = self.window(id) {
+            let events = window.take_changes();
+            self.events
+                .extend(events.into_iter().map(|event| (id, event)));
+        }
+    }
+
+    /// Store a pointer position in surface coordinates scaled to the focused window's buffer
+    pub fn set_pointer_position(&mut self, x: f64, y: f64) {
+        let scale = self
+            .pointer_focus
+            .and_then(|id| self.window(id))
+            .map_or(1, |window| window.scale()) as f64;
+        self.pointer_position = (x * scale, y * scale);
+    }
+
+    /// Give each window the largest scale of the outputs it is on and redraw the ones that
+    /// changed. Windows that left every output keep their last scale.
+    pub fn update_scales(&mut self) {
+        for index in 0..self.windows.len() {
+            let window = &mut self.windows[index];
+            let scale = self
+                .outputs
+                .iter()
+                .filter(|output| window.outputs().contains(&output.id()))
+                .map(|output| output.scale)
+                .max()
+                .unwrap_or(window.scale());
+            if scale == window.scale() {
+                continue;
+            }
+            if let Err(err) = window.set_scale(scale) {
+                #[cfg(debug_assertions)]
+                eprintln!("{}", err);
+            }
+            let id = window.id();
+            let (width, height) = window.physical_size();
+            self.events.push_back((
+                id,
+                Event::ScaleFactorChanged {
+                    scale_factor: scale as f64,
+                    width,
+                    height,
+                },
+            ));
+            self.report_changes(id);
+        }
+    }
+}
+
+/// Connection to the wayland compositor shared by every window created on this thread.
+pub struct Client {
+    pub conn: Connection,
+    pub queue: EventQueue<State>,
+    pub state: State,
+}
+
+impl Client {
+    fn new() -> Result<Self, Error> {
+        let conn = Connection::connect_to_env()?;
+        let mut queue = conn.new_event_queue();
+        let qh = queue.handle();
+        conn.display().get_registry(&qh, ());
+
+        let mut state = State {
+            qh,
+            compositor: None,
+            shm: None,
+            wm_base: None,
+            decoration_manager: None,
+            seat: None,
+            keyboard: None,
+            pointer: None,
+            windows: Vec::new(),
+            outputs: Vec::new(),
+            events: VecDeque::new(),
+            quit: false,
+            keyboard_focus: None,
+            pointer_focus: None,
+            pointer_position: (0.0, 0.0),
+            clicks: ClickTracker::default(),
+        };
+        // First round trip binds the globals, the second receives their initial events
+        queue.roundtrip(&mut state)?;
+        queue.roundtrip(&mut state)?;
+
+        if state.compositor.is_none() || state.shm.is_none() || state.wm_base.is_none() {
+            return Err(Error {
+                code: 0,
+                message: "Wayland compositor is missing wl_compositor, wl_shm or xdg_wm_base"
+                    .into(),
+            });
+        }
+
+        Ok(Self { conn, queue, state })
+    }
+}
+
+thread_local! {
+    static CLIENT: RefCell<Option<Client>> = const { RefCell::new(None) };
+}
+
+/// Run a closure with the wayland client, connecting to the compositor on first use
+pub fn with_client<R>(f: impl FnOnce(&mut Client) -> Result<R, Error>) -> Result<R, Error> {
+    CLIENT.with(|client| {
+        let mut client = client.borrow_mut();
+        if client.is_none() {
+            *client = Some(Client::new()?);
+        }
+        f(client.as_mut().unwrap())
+    })
+}
+
+/// Run a closure with a window, flushing any requests it made and queueing events for the
+/// changes it made
+pub fn with_window<R>(id: WindowId, f: impl FnOnce(&mut Window) -> R) -> Option<R> {
+    with_client(|client| {
+        let result = client.state.window(id).map(f);
+        client.state.report_changes(id);
+        client.conn.flush()?;
+        Ok(result)
+    })
+    .ok()
+    .flatten()
+}
+
+impl From<ConnectError> for Error {
+    fn from(error: ConnectError) -> Self {
+        Self {
+            code: 0,
+            message: format!("Failed to connect to the wayland compositor: {}", error),
+        }
+    }
+}
+
+impl From<WaylandError> for Error {
+    fn from(error: WaylandError) -> Self {
+        Self {
+            code: 0,
+            message: format!("Wayland connection error: {}", error),
+        }
+    }
+}
+
+impl From<DispatchError> for Error {
+    fn from(error: DispatchError) -> Self {
+        match error {
+            DispatchError::Backend(error) => error.into(),
+            error => Self {
+                code: 0,
+                message: format!("Failed to dispatch wayland event: {}", error),
+            },
+        }
+    }
+}
+
+/// Windows are identified by the protocol id of their surface
+pub fn window_id(surface: &WlSurface) -> WindowId {
+    WindowId(surface.id().protocol_id() as isize)
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => match interface.as_str() {
+                "wl_compositor" => {
+                    state.compositor = Some(registry.bind(name, version.min(4), qh, ()));
+                }
+                "wl_shm" => state.shm = Some(registry.bind(name, 1, qh, ())),
+                "wl_seat" => state.seat = Some(registry.bind(name, version.min(5), qh, ())),
+                "xdg_wm_base" => state.wm_base = Some(registry.bind(name, 1, qh, ())),
+                "zxdg_decoration_manager_v1" => {
+                    state.decoration_manager = Some(registry.bind(name, 1, qh, ()));
+                }
+                "wl_output" => {
+                    let output = registry.bind(name, version.min(4), qh, ());
+                    state.outputs.push(Output::new(name, output));
+                }
+                _ => {}
+            },
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(index) = state.outputs.iter().position(|o| o.global == name) {
+                    let output = state.outputs.remove(index);
+                    if output.output.version() >= 3 {
+                        output.output.release();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for State {
+    fn event(
+        _: &mut Self,
+        wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for State {
+    fn event(
+        state: &mut Self,
+        seat: &wl_seat::WlSeat,
+        event: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_seat::Event::Capabilities {
+            capabilities: WEnum::Value(capabilities),
+        } = event
+        {
+            if capabilities.contains(wl_seat::Capability::Keyboard) && state.keyboard.is_none() {
+                state.keyboard = Some(seat.get_keyboard(qh, ()));
+            }
+            if capabilities.contains(wl_seat::Capability::Pointer) && state.pointer.is_none() {
+                state.pointer = Some(seat.get_pointer(qh, ()));
+            }
+        }
+    }
+}
+
+delegate_noop!(State: WlCompositor);
+delegate_noop!(State: WlShmPool);
+delegate_noop!(State: WlRegion);
+delegate_noop!(State: ZxdgDecorationManagerV1);
+delegate_noop!(State: ignore WlShm);
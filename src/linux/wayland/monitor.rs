@@ -0,0 +1,138 @@
+use wayland_client::protocol::wl_output::{self, WlOutput};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+
+use crate::error::Error;
+use crate::monitor::{Monitor, VideoMode};
+use crate::window::WindowId;
+
+use super::{with_client, State};
+
+/// A `wl_output` global and what the compositor told about it
+pub struct Output {
+    /// Registry name, used to find the output again when the global is removed
+    pub global: u32,
+    pub output: WlOutput,
+
+    pub name: String,
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+    pub refresh_rate: Option<u32>,
+    pub scale: i32,
+}
+
+impl Output {
+    pub fn new(global: u32, output: WlOutput) -> Self {
+        Self {
+            global,
+            output,
+            name: String::new(),
+            position: (0, 0),
+            size: (0, 0),
+            refresh_rate: None,
+            scale: 1,
+        }
+    }
+
+    /// Outputs are identified by their protocol id, the same way windows use their surface
+    pub fn id(&self) -> u32 {
+        self.output.id().protocol_id()
+    }
+
+    /// Wayland has no primary output or work area, the first output is used as the primary and
+    /// the work area is the whole output
+    fn monitor(&self, primary: bool) -> Monitor {
+        Monitor {
+            handle: self.id() as isize,
+            name: self.name.clone(),
+            position: self.position,
+            size: self.size,
+            work_area: (self.position.0, self.position.1, self.size.0, self.size.1),
+            refresh_rate: self.refresh_rate,
+            scale_factor: self.scale as f64,
+            primary,
+        }
+    }
+}
+
+pub fn all() -> Result<Vec<Monitor>, Error> {
+    with_client(|client| {
+        Ok(client
+            .state
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(index, output)| output.monitor(index == 0))
+            .collect())
+    })
+}
+
+pub fn primary() -> Result<Monitor, Error> {
+    all()?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::unsupported("Monitors without a wl_output"))
+}
+
+/// Get the first output the window's surface entered, surfaces only enter an output once they
+/// are shown
+pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+    let entered = with_client(|client| {
+        let state = &mut client.state;
+        let window = state.window(id).ok_or_else(|| Error::invalid_window(id))?;
+        Ok(window.outputs().first().copied())
+    })?;
+    let monitors = all()?;
+    match monitors
+        .iter()
+        .find(|monitor| Some(monitor.handle as u32) == entered)
+    {
+        Some(monitor) => Ok(monitor.clone()),
+        None => primary(),
+    }
+}
+
+/// Compositors own the output modes, clients can't switch them
+pub fn video_modes(_monitor: &Monitor) -> Result<Vec<VideoMode>, Error> {
+    Err(Error::unsupported("Video modes on Wayland"))
+}
+
+impl Dispatch<WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = output.id().protocol_id();
+        let Some(output) = state.outputs.iter_mut().find(|o| o.id() == id) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x, y, make, model, ..
+            } => {
+                output.position = (x, y);
+                // Only used until the compositor sends a name, which needs version 4
+                if output.name.is_empty() {
+                    output.name = format!("{} {}", make, model).trim().to_string();
+                }
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                refresh,
+            } if flags.contains(wl_output::Mode::Current) => {
+                output.size = (width as u32, height as u32);
+                output.refresh_rate = (refresh > 0).then_some(refresh as u32);
+            }
+            wl_output::Event::Scale { factor } => output.scale = factor,
+            wl_output::Event::Name { name } => output.name = name,
+            // Sent after a batch of changes, a new scale applies to the windows on the output
+            wl_output::Event::Done => state.update_scales(),
+            _ => {}
+        }
+    }
+}
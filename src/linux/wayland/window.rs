@@ -0,0 +1,977 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::ptr::NonNull;
+
+use raw_window_handle::{
+    HandleError, RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
+};
+use wayland_client::protocol::wl_buffer::{self, WlBuffer};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_shm::{self, WlShm};
+use wayland_client::protocol::wl_shm_pool::WlShmPool;
+use wayland_client::protocol::wl_surface::{self, WlSurface};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols::xdg::decoration::zv1::client::zxdg_toplevel_decoration_v1::{
+    self, Mode, ZxdgToplevelDecorationV1,
+};
+use wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, XdgToplevel};
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::dpi::{PhysicalSize, Size};
+use crate::error::Error;
+use crate::event::mouse::MouseEventType;
+use crate::event::{exit_after_close, set_handler, state_change, Event};
+use crate::icon::WindowIcon;
+use crate::linux::is_dark_mode;
+use crate::style::{premultiply, Theme};
+use crate::surface::stretch;
+use crate::window::{
+    Bounds, Fullscreen, HitTest, ResizeEdge, SizeConstraints, WindowId, WindowLevel, WindowOptions,
+    DEFAULT_SIZE,
+};
+
+use super::{window_id, with_client, with_window, State};
+
+/// Scale a surface size up to the buffer size
+fn to_physical(size: (u32, u32), scale: i32) -> (u32, u32) {
+    (size.0 * scale as u32, size.1 * scale as u32)
+}
+
+/// Scale a buffer size down to the surface size, surfaces are never empty
+fn to_logical(size: (u32, u32), scale: i32) -> (u32, u32) {
+    (
+        (size.0 / scale as u32).max(1),
+        (size.1 / scale as u32).max(1),
+    )
+}
+
+/// Get the xdg-shell edge for dragging an edge
+fn resize_edge(edge: ResizeEdge) -> xdg_toplevel::ResizeEdge {
+    match edge {
+        ResizeEdge::Top => xdg_toplevel::ResizeEdge::Top,
+        ResizeEdge::Bottom => xdg_toplevel::ResizeEdge::Bottom,
+        ResizeEdge::Left => xdg_toplevel::ResizeEdge::Left,
+        ResizeEdge::Right => xdg_toplevel::ResizeEdge::Right,
+        ResizeEdge::TopLeft => xdg_toplevel::ResizeEdge::TopLeft,
+        ResizeEdge::TopRight => xdg_toplevel::ResizeEdge::TopRight,
+        ResizeEdge::BottomLeft => xdg_toplevel::ResizeEdge::BottomLeft,
+        ResizeEdge::BottomRight => xdg_toplevel::ResizeEdge::BottomRight,
+    }
+}
+
+/// Resolve the background color for a theme as a premultiplied ARGB pixel. There is no
+/// protocol for window opacity so it is applied to the pixel.
+fn background_pixel(options: &WindowOptions) -> u32 {
+    let background = options.background;
+    let color = match options.theme {
+        Theme::Light => background.light(),
+        Theme::Dark => background.dark(),
+        Theme::Auto => background.color(is_dark_mode()),
+    };
+    premultiply(color, options.transparent, options.opacity)
+}
+
+/// Buffers a window draws frames into, the compositor reads one while the next is written
+const MAX_BUFFERS: usize = 2;
+
+/// Shared memory buffer filled with the window's background color
+struct Buffer {
+    buffer: WlBuffer,
+    pool: WlShmPool,
+    size: (u32, u32),
+    file: File,
+    /// Attached since the compositor last released it, writing would tear the shown frame
+    busy: bool,
+}
+
+impl Buffer {
+    fn new(
+        shm: &WlShm,
+        qh: &QueueHandle<State>,
+        id: WindowId,
+        size: (u32, u32),
+        pixel: u32,
+    ) -> Result<Self, Error> {
+        let (width, height) = (size.0.max(1), size.1.max(1));
+        let len = (width * height * 4) as usize;
+
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(format!("storytree-{}", uuid::Uuid::new_v4()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // Only the file descriptor is needed, the compositor gets its own copy of it
+        std::fs::remove_file(&path)?;
+
+        let pixels = pixel.to_le_bytes();
+        file.write_all(&pixels.repeat(len / 4))?;
+
+        let pool = shm.create_pool(file.as_fd(), len as i32, qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            (width * 4) as i32,
+            wl_shm::Format::Argb8888,
+            qh,
+            id,
+        );
+        Ok(Self {
+            buffer,
+            pool,
+            size,
+            file,
+            busy: false,
+        })
+    }
+
+    /// Overwrite the pixels, the compositor reads them again after the next commit
+    fn write(&self, pixels: &[u32]) -> Result<(), Error> {
+        let bytes: Vec<u8> = pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_le_bytes())
+            .collect();
+        self.file.write_all_at(&bytes, 0)?;
+        Ok(())
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+    }
+}
+
+pub struct Window {
+    id: WindowId,
+    options: WindowOptions,
+
+    compositor: WlCompositor,
+    shm: WlShm,
+    qh: QueueHandle<State>,
+    surface: WlSurface,
+    xdg_surface: XdgSurface,
+    toplevel: XdgToplevel,
+    decoration: Option<ZxdgToplevelDecorationV1>,
+    /// Buffers of the current size, the first one has the frame last presented
+    buffers: Vec<Buffer>,
+    /// Frame held back until a buffer is released, with its size
+    pending_frame: Option<(Vec<u32>, (u32, u32))>,
+
+    /// Surface size in logical pixels, the buffer is `scale` times larger
+    size: (u32, u32),
+    /// Largest scale of the outputs the surface is on
+    scale: i32,
+    /// Initial commit was made and the surface waits for or has a configure
+    mapped: bool,
+    /// Compositors don't report minimized windows, this is set when asked to minimize and cleared
+    /// once the window is activated again
+    minimized: bool,
+    maximized: bool,
+    fullscreen: bool,
+    /// Fullscreen mode last asked for, the compositor decides if the window actually is
+    requested_fullscreen: Option<Fullscreen>,
+    /// Surface size before the window was maximized or made fullscreen
+    windowed_size: (u32, u32),
+    /// Size and `(minimized, maximized)` state last passed to the app callback
+    reported_size: Option<(u32, u32)>,
+    reported_state: (bool, bool),
+    /// Protocol ids of the outputs the surface is on, in the order it entered them
+    outputs: Vec<u32>,
+    /// Decoration mode the compositor agreed on, client side means no title bar is drawn
+    server_side_decorations: bool,
+}
+
+impl Window {
+    pub fn id(&self) -> WindowId {
+        self.id
+    }
+
+    pub fn options(&self) -> &WindowOptions {
+        &self.options
+    }
+
+    pub fn outputs(&self) -> &[u32] {
+        &self.outputs
+    }
+
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    /// Change the buffer scale, the surface keeps its logical size so the buffer grows or shrinks
+    pub fn set_scale(&mut self, scale: i32) -> Result<(), Error> {
+        self.scale = scale;
+        self.write_size_limits();
+        if self.mapped {
+            self.draw()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the buffer size in physical pixels
+    pub fn physical_size(&self) -> (u32, u32) {
+        to_physical(self.size, self.scale)
+    }
+
+    /// Act on a left click at a buffer position the way the frame of a window with decorations
+    /// would. Presses start moving or resizing, releases press the buttons. Closing is left to
+    /// the caller since it goes through the event queue.
+    pub fn frame_click(
+        &mut self,
+        seat: Option<&WlSeat>,
+        serial: u32,
+        etype: &MouseEventType,
+        position: (f64, f64),
+    ) -> HitTest {
+        let hit = self
+            .options
+            .hit_test(position.0 as i32, position.1 as i32, self.physical_size());
+        let pressed = matches!(etype, MouseEventType::Down(_) | MouseEventType::Double(_));
+        match (hit, etype) {
+            (HitTest::Caption, MouseEventType::Double(_))
+            | (HitTest::Maximize, MouseEventType::Up(_)) => {
+                if self.maximized {
+                    self.toplevel.unset_maximized();
+                } else {
+                    self.toplevel.set_maximized();
+                }
+            }
+            (HitTest::Caption, _) if pressed => {
+                if let Some(seat) = seat {
+                    self.toplevel._move(seat, serial);
+                }
+            }
+            (HitTest::Resize(edge), _) if pressed => {
+                if let Some(seat) = seat {
+                    self.toplevel.resize(seat, serial, resize_edge(edge));
+                }
+            }
+            (HitTest::Minimize, MouseEventType::Up(_)) => {
+                self.toplevel.set_minimized();
+                self.minimized = true;
+            }
+            _ => {}
+        }
+        hit
+    }
+
+    pub fn server_side_decorations(&self) -> bool {
+        self.server_side_decorations
+    }
+
+    /// Get the events for what changed since the last call
+    pub fn take_changes(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        if let Some(event) = state_change(self.reported_state, self.minimized, self.maximized) {
+            events.push(event);
+        }
+        self.reported_state = (self.minimized, self.maximized);
+        // The size is only known to the app once the surface is shown
+        let size = self.physical_size();
+        if self.mapped && self.reported_size.replace(size) != Some(size) {
+            events.push(Event::Resized {
+                width: size.0,
+                height: size.1,
+            });
+        }
+        events
+    }
+
+    /// Make the initial commit, the compositor answers with a configure that draws the window
+    fn map(&mut self) {
+        if !self.mapped {
+            self.mapped = true;
+            self.surface.commit();
+        }
+    }
+
+    /// Drop the buffers that don't have the physical size and add one filled with the background
+    /// color if none is left, or if every buffer is busy and `spare` asks for another one
+    fn resize_buffers(&mut self, spare: bool) -> Result<(), Error> {
+        let size = self.physical_size();
+        self.buffers.retain(|buffer| buffer.size == size);
+        let busy = self.buffers.iter().all(|buffer| buffer.busy);
+        if self.buffers.is_empty() || (spare && busy && self.buffers.len() < MAX_BUFFERS) {
+            let pixel = background_pixel(&self.options);
+            let buffer = Buffer::new(&self.shm, &self.qh, self.id, size, pixel)?;
+            self.buffers.push(buffer);
+        }
+        Ok(())
+    }
+
+    /// Copy a frame into a released buffer, scaled to its size, and present it. The frame waits
+    /// for a release when the compositor still reads every buffer.
+    fn draw_pixels(&mut self, pixels: &[u32], size: (u32, u32)) -> Result<(), Error> {
+        self.resize_buffers(true)?;
+        let Some(index) = self.buffers.iter().position(|buffer| !buffer.busy) else {
+            self.pending_frame = Some((pixels.to_vec(), size));
+            return Ok(());
+        };
+        self.pending_frame = None;
+        let buffer = self.buffers.remove(index);
+        self.buffers.insert(0, buffer);
+        let buffer = &self.buffers[0];
+        buffer.write(&stretch(pixels, size, buffer.size))?;
+        self.draw()
+    }
+
+    /// Take a buffer back from the compositor and draw the frame that waited for it
+    fn release(&mut self, released: &WlBuffer) -> Result<(), Error> {
+        if let Some(buffer) = self.buffers.iter_mut().find(|b| b.buffer == *released) {
+            buffer.busy = false;
+        }
+        match self.pending_frame.take() {
+            Some((pixels, size)) if self.mapped => self.draw_pixels(&pixels, size),
+            _ => Ok(()),
+        }
+    }
+
+    /// Present the last frame, a new buffer is filled with the background color. Configures
+    /// reuse the buffer so a presented frame stays until the size or the background changes.
+    fn draw(&mut self) -> Result<(), Error> {
+        let size = self.physical_size();
+        self.resize_buffers(false)?;
+        let buffer = &mut self.buffers[0];
+        buffer.busy = true;
+        // Buffer scale needs version 3 of wl_surface, older compositors can't scale
+        if self.surface.version() >= 3 {
+            self.surface.set_buffer_scale(self.scale);
+        }
+        self.surface.attach(Some(&buffer.buffer), 0, 0);
+        self.surface
+            .damage_buffer(0, 0, size.0 as i32, size.1 as i32);
+        self.surface.commit();
+        Ok(())
+    }
+
+    /// Use an empty input region so clicks reach the surfaces below, or reset it to the whole
+    /// surface. Applied with the next commit.
+    fn write_input_region(&self) {
+        if self.options.click_through {
+            let region = self.compositor.create_region(&self.qh, ());
+            self.surface.set_input_region(Some(&region));
+            region.destroy();
+        } else {
+            self.surface.set_input_region(None);
+        }
+    }
+
+    /// Redraw with a new background pixel
+    fn repaint(&mut self) -> Result<(), Error> {
+        self.buffers.clear();
+        if self.mapped {
+            self.draw()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Pass the size limits to the compositor, zero leaves a limit unset. Aspect ratio and
+    /// increments have no protocol and are applied to the sizes the compositor suggests instead.
+    fn write_size_limits(&self) {
+        let (min, max) = if self.options.fixed_size {
+            (self.size, self.size)
+        } else {
+            // Constraints are physical pixels, the compositor wants surface sizes. Zero means
+            // no limit so these aren't clamped like `to_logical` does.
+            let constraints = &self.options.constraints;
+            let scale = self.scale as u32;
+            let min = constraints.min_size.unwrap_or((0, 0));
+            let max = constraints.max_size.unwrap_or((0, 0));
+            (
+                (min.0.div_ceil(scale), min.1.div_ceil(scale)),
+                (max.0 / scale, max.1 / scale),
+            )
+        };
+        self.toplevel.set_min_size(min.0 as i32, min.1 as i32);
+        self.toplevel.set_max_size(max.0 as i32, max.1 as i32);
+    }
+
+    pub fn create(options: WindowOptions) -> Result<WindowId, Error> {
+        with_client(|client| {
+            let state = &mut client.state;
+            let parent = match options.owner {
+                Some(owner) => Some(
+                    state
+                        .window(owner)
+                        .map(|window| window.toplevel.clone())
+                        .ok_or_else(|| Error::invalid_window(owner))?,
+                ),
+                None => None,
+            };
+            let qh = state.qh.clone();
+            let compositor = state.compositor.as_ref().unwrap();
+            let wm_base = state.wm_base.as_ref().unwrap();
+
+            let surface = compositor.create_surface(&qh, ());
+            let id = window_id(&surface);
+            let xdg_surface = wm_base.get_xdg_surface(&surface, &qh, id);
+            let toplevel = xdg_surface.get_toplevel(&qh, id);
+            toplevel.set_title(options.title.clone());
+            // Compositors keep child toplevels above their parent, there is no modal hint
+            toplevel.set_parent(parent.as_ref());
+
+            // Surfaces start with a scale of 1 until they enter an output
+            let mut size = options
+                .size
+                .map_or(DEFAULT_SIZE, |size| size.to_physical(1.0).into());
+            if !options.fixed_size {
+                size = options.constraints.constrain(size, false);
+            }
+
+            // Ask for a title bar from the compositor, without the protocol or if the compositor
+            // wants client side decorations the window is shown without one
+            let mode = if options.decorations {
+                Mode::ServerSide
+            } else {
+                Mode::ClientSide
+            };
+            let decoration = state.decoration_manager.as_ref().map(|manager| {
+                let decoration = manager.get_toplevel_decoration(&toplevel, &qh, id);
+                decoration.set_mode(mode);
+                decoration
+            });
+
+            let show = options.show;
+            set_handler(id, options.handler.as_ref());
+            state.windows.push(Window {
+                id,
+                options,
+                compositor: compositor.clone(),
+                shm: state.shm.clone().unwrap(),
+                qh,
+                surface,
+                xdg_surface,
+                toplevel,
+                decoration,
+                buffers: Vec::new(),
+                pending_frame: None,
+                size,
+                scale: 1,
+                mapped: false,
+                minimized: false,
+                maximized: false,
+                fullscreen: false,
+                requested_fullscreen: None,
+                windowed_size: size,
+                reported_size: None,
+                reported_state: (false, false),
+                outputs: Vec::new(),
+                server_side_decorations: false,
+            });
+
+            let window = state.windows.last_mut().unwrap();
+            window.write_size_limits();
+            if window.options.click_through {
+                window.write_input_region();
+            }
+            if show {
+                window.map();
+            }
+            client.conn.flush()?;
+            Ok(id)
+        })
+    }
+
+    pub fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.options.theme = theme;
+            window.repaint()
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    /// Show the window
+    pub fn show(id: WindowId) {
+        with_window(id, |window| window.map());
+    }
+
+    /// Hide the window
+    pub fn hide(id: WindowId) {
+        with_window(id, |window| {
+            // Committing a null buffer unmaps the surface
+            window.surface.attach(None, 0, 0);
+            window.surface.commit();
+            window.mapped = false;
+        });
+    }
+
+    /// Minimize the window
+    pub fn minimize(id: WindowId) {
+        with_window(id, |window| {
+            window.toplevel.set_minimized();
+            window.minimized = true;
+        });
+    }
+
+    /// Restore the window
+    pub fn restore(id: WindowId) {
+        with_window(id, |window| {
+            window.map();
+            window.toplevel.unset_maximized();
+            window.minimized = false;
+        });
+    }
+
+    /// Maximize the window
+    pub fn maximize(id: WindowId) {
+        with_window(id, |window| {
+            window.map();
+            window.toplevel.set_maximized();
+            window.minimized = false;
+        });
+    }
+
+    /// Get the size of the surface's buffer
+    pub fn client_size(id: WindowId) -> Result<(u32, u32), Error> {
+        with_window(id, |window| window.physical_size()).ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Get the size of the window, decorations drawn by the compositor aren't reported so this
+    /// is the same as the surface size
+    pub fn outer_size(id: WindowId) -> Result<(u32, u32), Error> {
+        Window::client_size(id)
+    }
+
+    /// Wayland doesn't let clients know where their windows are
+    pub fn position(_id: WindowId) -> Result<(i32, i32), Error> {
+        Err(Error::unsupported("Window position on Wayland"))
+    }
+
+    /// Get the window title
+    pub fn title(id: WindowId) -> Result<String, Error> {
+        with_window(id, |window| window.options.title.clone())
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Check if the window has a buffer attached and isn't minimized
+    pub fn exists(id: WindowId) -> bool {
+        with_window(id, |_| ()).is_some()
+    }
+
+    pub fn is_visible(id: WindowId) -> bool {
+        with_window(id, |window| window.mapped && !window.minimized).unwrap_or(false)
+    }
+
+    /// Check if the window is minimized
+    pub fn is_minimized(id: WindowId) -> bool {
+        with_window(id, |window| window.minimized).unwrap_or(false)
+    }
+
+    /// Check if the window is maximized
+    pub fn is_maximized(id: WindowId) -> bool {
+        with_window(id, |window| window.maximized).unwrap_or(false)
+    }
+
+    /// Only the size is known, Wayland doesn't let clients know where their windows are
+    pub fn normal_bounds(id: WindowId) -> Result<Bounds, Error> {
+        with_window(id, |window| {
+            let size = match window.maximized || window.fullscreen {
+                true => window.windowed_size,
+                false => window.size,
+            };
+            (None, to_physical(size, window.scale))
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Check if the window is fullscreen
+    pub fn is_fullscreen(id: WindowId) -> bool {
+        with_window(id, |window| window.fullscreen).unwrap_or(false)
+    }
+
+    pub fn fullscreen(id: WindowId) -> Option<Fullscreen> {
+        with_window(id, |window| {
+            window.fullscreen.then(|| {
+                (window.requested_fullscreen.clone()).unwrap_or(Fullscreen::Borderless(None))
+            })
+        })
+        .flatten()
+    }
+
+    /// Clients can't change output modes, exclusive fullscreen is not supported
+    pub fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        let output = match &fullscreen {
+            Some(Fullscreen::Exclusive(_)) => {
+                return Err(Error::unsupported("Exclusive fullscreen on Wayland"))
+            }
+            Some(Fullscreen::Borderless(Some(monitor))) => Some(with_client(|client| {
+                client
+                    .state
+                    .outputs
+                    .iter()
+                    .find(|output| output.id() as isize == monitor.handle)
+                    .map(|output| output.output.clone())
+                    .ok_or_else(|| Error::invalid_parameter("Monitor is no longer connected"))
+            })?),
+            _ => None,
+        };
+        with_window(id, |window| {
+            if fullscreen.is_some() {
+                window.toplevel.set_fullscreen(output.as_ref());
+                // The compositor only configures fullscreen once the surface is mapped
+                window.map();
+            } else {
+                window.toplevel.unset_fullscreen();
+            }
+            window.requested_fullscreen = fullscreen;
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    pub fn set_title(id: WindowId, title: String) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.toplevel.set_title(title.clone());
+            window.options.title = title;
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Resize the surface, the compositor can still pick another size in its next configure
+    pub fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.size = to_logical((width, height), window.scale);
+            window.options.size = Some(Size::Physical(PhysicalSize::new(width, height)));
+            if window.options.fixed_size {
+                window.write_size_limits();
+            }
+            if window.mapped {
+                window.draw()
+            } else {
+                Ok(())
+            }
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    /// Wayland doesn't let clients move their windows
+    pub fn set_position(_id: WindowId, _x: i32, _y: i32) -> Result<(), Error> {
+        Err(Error::unsupported("Window position on Wayland"))
+    }
+
+    /// The compositor decides where windows are placed
+    pub fn center_on_monitor(_id: WindowId) -> Result<(), Error> {
+        Err(Error::unsupported("Window position on Wayland"))
+    }
+
+    pub fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error> {
+        with_window(id, |window| window.options.constraints)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Send the new limits to the compositor and resize the window if it doesn't fit them
+    pub fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.options.constraints = constraints;
+            window.write_size_limits();
+            let size = window.physical_size();
+            let fitted = constraints.constrain(size, false);
+            if window.options.fixed_size || fitted == size {
+                window.surface.commit();
+                return Ok(());
+            }
+            window.size = to_logical(fitted, window.scale);
+            if window.mapped {
+                window.draw()
+            } else {
+                window.surface.commit();
+                Ok(())
+            }
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    pub fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.options.opacity = opacity;
+            window.repaint()
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    pub fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.options.transparent = transparent;
+            window.repaint()
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    /// Draw a frame, windows that haven't been configured yet can't have a buffer attached and
+    /// skip it. They get `Event::Repaint` once they can draw.
+    pub fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        with_window(id, |window| match window.mapped {
+            true => window.draw_pixels(pixels, (width, height)),
+            false => Ok(()),
+        })
+        .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    pub fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error> {
+        with_window(id, |window| {
+            window.options.click_through = click_through;
+            window.write_input_region();
+            window.surface.commit();
+        })
+        .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Check if a shown modal window owned by this one should keep input from reaching it
+    pub fn is_blocked(id: WindowId) -> bool {
+        with_client(|client| {
+            Ok(client.state.windows.iter().any(|window| {
+                window.options.modal && window.options.owner == Some(id) && window.mapped
+            }))
+        })
+        .unwrap_or(false)
+    }
+
+    /// Compositors take the icon from the desktop file matching the app id, `xdg_toplevel` can't
+    /// set one
+    pub fn set_icon(_id: WindowId, _icon: Option<WindowIcon>) -> Result<(), Error> {
+        Err(Error::unsupported("Window icons on Wayland"))
+    }
+
+    pub fn set_small_icon(_id: WindowId, _icon: Option<WindowIcon>) -> Result<(), Error> {
+        Err(Error::unsupported("Window icons on Wayland"))
+    }
+
+    /// Cursors are set on the pointer with a surface from a cursor theme, which isn't loaded
+    pub fn set_cursor(_id: WindowId, _cursor: Cursor) -> Result<(), Error> {
+        Err(Error::unsupported("Cursors on Wayland"))
+    }
+
+    pub fn set_cursor_visible(_id: WindowId, _visible: bool) -> Result<(), Error> {
+        Err(Error::unsupported("Cursors on Wayland"))
+    }
+
+    /// Needs the pointer constraints protocol, which isn't bound
+    pub fn set_cursor_grab(_id: WindowId, _grab: CursorGrab) -> Result<(), Error> {
+        Err(Error::unsupported("Cursor grabs on Wayland"))
+    }
+
+    /// Needs the relative pointer protocol, which isn't bound
+    pub fn set_raw_mouse_motion(_id: WindowId, _enabled: bool) -> Result<(), Error> {
+        Err(Error::unsupported("Raw mouse motion on Wayland"))
+    }
+
+    /// Clients can't move the pointer on Wayland
+    pub fn set_cursor_position(_id: WindowId, _x: i32, _y: i32) -> Result<(), Error> {
+        Err(Error::unsupported("Moving the cursor on Wayland"))
+    }
+
+    /// `xdg_toplevel` has no stacking, the compositor decides which window is on top
+    pub fn set_level(_id: WindowId, _level: WindowLevel) -> Result<(), Error> {
+        Err(Error::unsupported("Window levels on Wayland"))
+    }
+
+    /// Wayland has no window types, the closest is a dialog with a parent
+    pub fn set_tool_window(_id: WindowId, _tool_window: bool) -> Result<(), Error> {
+        Err(Error::unsupported("Tool windows on Wayland"))
+    }
+
+    /// Taskbars and pagers list every toplevel, there is no hint to leave one out
+    pub fn set_skip_taskbar(_id: WindowId, _skip_taskbar: bool) -> Result<(), Error> {
+        Err(Error::unsupported("Skipping the taskbar on Wayland"))
+    }
+
+    pub fn set_skip_pager(_id: WindowId, _skip_pager: bool) -> Result<(), Error> {
+        Err(Error::unsupported("Skipping the pager on Wayland"))
+    }
+
+    /// Get the buffer scale, compositors only support whole scale factors through `wl_output`
+    pub fn scale_factor(id: WindowId) -> f64 {
+        with_window(id, |window| window.scale as f64).unwrap_or(1.0)
+    }
+
+    pub fn raw_window_handle(id: WindowId) -> Result<RawWindowHandle, HandleError> {
+        with_window(id, |window| {
+            NonNull::new(window.surface.id().as_ptr().cast())
+        })
+        .flatten()
+        .map(|surface| WaylandWindowHandle::new(surface).into())
+        .ok_or(HandleError::Unavailable)
+    }
+
+    pub fn raw_display_handle(_id: WindowId) -> Result<RawDisplayHandle, HandleError> {
+        with_client(|client| Ok(NonNull::new(client.conn.backend().display_ptr().cast())))
+            .ok()
+            .flatten()
+            .map(|display| WaylandDisplayHandle::new(display).into())
+            .ok_or(HandleError::Unavailable)
+    }
+
+    pub fn close(id: WindowId) -> Result<(), Error> {
+        with_client(|client| {
+            let state = &mut client.state;
+            if let Some(index) = state.windows.iter().position(|window| window.id == id) {
+                state.windows.remove(index);
+                state.events.push_back((id, Event::Destroyed));
+                if exit_after_close(id, state.windows.len()) {
+                    state.quit = true;
+                }
+            }
+            client.conn.flush()?;
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        if let Some(decoration) = self.decoration.take() {
+            decoration.destroy();
+        }
+        self.buffers.clear();
+        self.toplevel.destroy();
+        self.xdg_surface.destroy();
+        self.surface.destroy();
+    }
+}
+
+impl Dispatch<WlSurface, ()> for State {
+    fn event(
+        state: &mut Self,
+        surface: &WlSurface,
+        event: wl_surface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(window) = state.window(window_id(surface)) else {
+            return;
+        };
+        match event {
+            wl_surface::Event::Enter { output } => {
+                window.outputs.push(output.id().protocol_id());
+            }
+            wl_surface::Event::Leave { output } => {
+                let id = output.id().protocol_id();
+                window.outputs.retain(|output| *output != id);
+            }
+            _ => {}
+        }
+        state.update_scales();
+    }
+}
+
+impl Dispatch<WlBuffer, WindowId> for State {
+    fn event(
+        state: &mut Self,
+        buffer: &WlBuffer,
+        event: wl_buffer::Event,
+        id: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let (wl_buffer::Event::Release, Some(window)) = (event, state.window(*id)) {
+            if let Err(err) = window.release(buffer) {
+                #[cfg(debug_assertions)]
+                eprintln!("{}", err);
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, WindowId> for State {
+    fn event(
+        state: &mut Self,
+        xdg_surface: &XdgSurface,
+        event: xdg_surface::Event,
+        id: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            xdg_surface.ack_configure(serial);
+            // The toplevel configure before this one is applied now
+            state.report_changes(*id);
+            if let Some(window) = state.window(*id) {
+                if window.mapped {
+                    if let Err(err) = window.draw() {
+                        #[cfg(debug_assertions)]
+                        eprintln!("{}", err);
+                    }
+                    state.events.push_back((*id, Event::Repaint));
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, WindowId> for State {
+    fn event(
+        state: &mut Self,
+        _: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        id: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
+                if let Some(window) = state.window(*id) {
+                    let states = states
+                        .chunks_exact(4)
+                        .map(|s| u32::from_ne_bytes([s[0], s[1], s[2], s[3]]))
+                        .collect::<Vec<_>>();
+                    if !window.maximized && !window.fullscreen {
+                        window.windowed_size = window.size;
+                    }
+                    window.maximized = states.contains(&(xdg_toplevel::State::Maximized as u32));
+                    window.fullscreen = states.contains(&(xdg_toplevel::State::Fullscreen as u32));
+                    // Zero means the client picks the size
+                    if width > 0 && height > 0 && !window.options.fixed_size {
+                        let size = (width as u32, height as u32);
+                        // Maximized and fullscreen windows have to fill the suggested size
+                        window.size = if window.maximized || window.fullscreen {
+                            size
+                        } else {
+                            let physical = to_physical(size, window.scale);
+                            let fitted = window.options.constraints.constrain(physical, false);
+                            to_logical(fitted, window.scale)
+                        };
+                    }
+                    if states.contains(&(xdg_toplevel::State::Activated as u32)) {
+                        window.minimized = false;
+                    }
+                }
+            }
+            xdg_toplevel::Event::Close => state.events.push_back((*id, Event::Close)),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZxdgToplevelDecorationV1, WindowId> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZxdgToplevelDecorationV1,
+        event: zxdg_toplevel_decoration_v1::Event,
+        id: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zxdg_toplevel_decoration_v1::Event::Configure { mode } = event {
+            if let Some(window) = state.window(*id) {
+                window.server_side_decorations = mode == WEnum::Value(Mode::ServerSide);
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use raw_window_handle::{HandleError, RawDisplayHandle, RawWindowHandle};
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::error::Error;
+use crate::headless;
+use crate::icon::WindowIcon;
+use crate::style::Theme;
+use crate::window::{
+    backend, build, select_backend, Backend, Bounds, Builder, Fullscreen, SizeConstraints,
+    WindowBuilder, WindowContext, WindowId, WindowLevel, WindowOptions,
+};
+
+use super::{wayland, x11};
+
+/// Call the same associated function on the window type of the active backend
+macro_rules! dispatch {
+    ($name: ident($($arg: expr),*)) => {
+        match backend() {
+            Backend::Headless => headless::Window::$name($($arg),*),
+            Backend::Wayland => wayland::window::Window::$name($($arg),*),
+            _ => x11::window::Window::$name($($arg),*),
+        }
+    };
+}
+
+/// Window on the X11, Wayland or headless backend
+pub enum Window {
+    X11(x11::window::Window),
+    Wayland(Box<wayland::window::Window>),
+    Headless(headless::Window),
+}
+
+impl WindowContext for Window {
+    type Builder = Builder;
+
+    fn create(options: WindowOptions) -> Result<WindowId, Error> {
+        build(options, |options| match select_backend(options.backend)? {
+            Backend::Headless => headless::Window::create(options),
+            Backend::Wayland => wayland::window::Window::create(options),
+            _ => x11::window::Window::create(options),
+        })
+    }
+
+    fn builder() -> Box<Self::Builder> {
+        Box::new(Builder::new())
+    }
+
+    /// Show the window
+    fn show(id: WindowId) {
+        dispatch!(show(id))
+    }
+
+    /// Hide the window
+    fn hide(id: WindowId) {
+        dispatch!(hide(id))
+    }
+
+    /// Minimize the window
+    fn minimize(id: WindowId) {
+        dispatch!(minimize(id))
+    }
+
+    /// Restore the window
+    fn restore(id: WindowId) {
+        dispatch!(restore(id))
+    }
+
+    /// Maximize the window
+    fn maximize(id: WindowId) {
+        dispatch!(maximize(id))
+    }
+
+    fn close(id: WindowId) -> Result<(), Error> {
+        dispatch!(close(id))
+    }
+
+    fn client_size(id: WindowId) -> Result<(u32, u32), Error> {
+        dispatch!(client_size(id))
+    }
+
+    fn outer_size(id: WindowId) -> Result<(u32, u32), Error> {
+        dispatch!(outer_size(id))
+    }
+
+    fn position(id: WindowId) -> Result<(i32, i32), Error> {
+        dispatch!(position(id))
+    }
+
+    fn title(id: WindowId) -> Result<String, Error> {
+        dispatch!(title(id))
+    }
+
+    fn exists(id: WindowId) -> bool {
+        dispatch!(exists(id))
+    }
+
+    fn is_visible(id: WindowId) -> bool {
+        dispatch!(is_visible(id))
+    }
+
+    fn is_minimized(id: WindowId) -> bool {
+        dispatch!(is_minimized(id))
+    }
+
+    fn is_maximized(id: WindowId) -> bool {
+        dispatch!(is_maximized(id))
+    }
+
+    fn is_fullscreen(id: WindowId) -> bool {
+        dispatch!(is_fullscreen(id))
+    }
+
+    fn normal_bounds(id: WindowId) -> Result<Bounds, Error> {
+        dispatch!(normal_bounds(id))
+    }
+
+    fn fullscreen(id: WindowId) -> Option<Fullscreen> {
+        dispatch!(fullscreen(id))
+    }
+
+    fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        dispatch!(set_fullscreen(id, fullscreen))
+    }
+
+    fn scale_factor(id: WindowId) -> f64 {
+        dispatch!(scale_factor(id))
+    }
+
+    fn set_title(id: WindowId, title: String) -> Result<(), Error> {
+        dispatch!(set_title(id, title))
+    }
+
+    fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error> {
+        dispatch!(set_theme(id, theme))
+    }
+
+    fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error> {
+        dispatch!(set_size(id, width, height))
+    }
+
+    fn set_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        dispatch!(set_position(id, x, y))
+    }
+
+    fn center_on_monitor(id: WindowId) -> Result<(), Error> {
+        dispatch!(center_on_monitor(id))
+    }
+
+    fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error> {
+        dispatch!(size_constraints(id))
+    }
+
+    fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error> {
+        dispatch!(set_size_constraints(id, constraints))
+    }
+
+    fn set_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        dispatch!(set_icon(id, icon))
+    }
+
+    fn set_small_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        dispatch!(set_small_icon(id, icon))
+    }
+
+    fn set_cursor(id: WindowId, cursor: Cursor) -> Result<(), Error> {
+        dispatch!(set_cursor(id, cursor))
+    }
+
+    fn set_cursor_visible(id: WindowId, visible: bool) -> Result<(), Error> {
+        dispatch!(set_cursor_visible(id, visible))
+    }
+
+    fn set_cursor_grab(id: WindowId, grab: CursorGrab) -> Result<(), Error> {
+        dispatch!(set_cursor_grab(id, grab))
+    }
+
+    fn set_raw_mouse_motion(id: WindowId, enabled: bool) -> Result<(), Error> {
+        dispatch!(set_raw_mouse_motion(id, enabled))
+    }
+
+    fn set_cursor_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        dispatch!(set_cursor_position(id, x, y))
+    }
+
+    fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error> {
+        dispatch!(set_opacity(id, opacity))
+    }
+
+    fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error> {
+        dispatch!(set_transparent(id, transparent))
+    }
+
+    fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error> {
+        dispatch!(set_click_through(id, click_through))
+    }
+
+    fn set_level(id: WindowId, level: WindowLevel) -> Result<(), Error> {
+        dispatch!(set_level(id, level))
+    }
+
+    fn set_tool_window(id: WindowId, tool_window: bool) -> Result<(), Error> {
+        dispatch!(set_tool_window(id, tool_window))
+    }
+
+    fn set_skip_taskbar(id: WindowId, skip_taskbar: bool) -> Result<(), Error> {
+        dispatch!(set_skip_taskbar(id, skip_taskbar))
+    }
+
+    fn set_skip_pager(id: WindowId, skip_pager: bool) -> Result<(), Error> {
+        dispatch!(set_skip_pager(id, skip_pager))
+    }
+
+    fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        dispatch!(present(id, pixels, width, height))
+    }
+
+    fn raw_window_handle(id: WindowId) -> Result<RawWindowHandle, HandleError> {
+        dispatch!(raw_window_handle(id))
+    }
+
+    fn raw_display_handle(id: WindowId) -> Result<RawDisplayHandle, HandleError> {
+        dispatch!(raw_display_handle(id))
+    }
+}
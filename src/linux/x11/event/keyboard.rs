@@ -0,0 +1,134 @@
+use crate::event::keyboard::KeyCode;
+
+/// X11 keysym as defined in `X11/keysymdef.h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keysym(pub u32);
+
+impl From<Keysym> for KeyCode {
+    fn from(v: Keysym) -> Self {
+        // Try function keys, then keypad, then latin-1 chars, if nothing works then return unknown with the raw value
+        match v.0 {
+            0xff69 => KeyCode::Cancel,
+            0xff08 => KeyCode::Back,
+            0xff09 | 0xfe20 => KeyCode::Tab,
+            0xff0b => KeyCode::Clear,
+            0xff0d | 0xff8d => KeyCode::Return,
+            0xffe1 | 0xffe2 => KeyCode::Shift,
+            0xffe3 | 0xffe4 => KeyCode::Control,
+            0xffe9 | 0xffea | 0xffe7 | 0xffe8 | 0xfe03 => KeyCode::Alt,
+            0xff13 => KeyCode::Pause,
+            0xffe5 => KeyCode::Capital,
+            0xff2d | 0xff31 => KeyCode::KanaHangul,
+            0xff38 => KeyCode::Junja,
+            0xff21 => KeyCode::Kanji,
+            0xff1b => KeyCode::Escape,
+            0xff23 => KeyCode::Convert,
+            0xff22 => KeyCode::NonConvert,
+            0xff7e => KeyCode::ModeChange,
+            0x20 | 0xff80 => KeyCode::Space,
+            0xff55 | 0xff9a => KeyCode::PageUp,
+            0xff56 | 0xff9b => KeyCode::PageDown,
+            0xff57 | 0xff9c => KeyCode::End,
+            0xff50 | 0xff95 => KeyCode::Home,
+            0xff51 | 0xff96 => KeyCode::Left,
+            0xff52 | 0xff97 => KeyCode::Up,
+            0xff53 | 0xff98 => KeyCode::Right,
+            0xff54 | 0xff99 => KeyCode::Down,
+            0xff60 => KeyCode::Select,
+            0xff61 => KeyCode::Snapshot,
+            0xff62 => KeyCode::Execute,
+            0xff63 | 0xff9e => KeyCode::Insert,
+            0xffff | 0xff9f => KeyCode::Delete,
+            0xff6a => KeyCode::Help,
+            0xffeb => KeyCode::LWin,
+            0xffec => KeyCode::RWin,
+            0xff67 => KeyCode::Apps,
+            0x1008ff2f => KeyCode::Sleep,
+            0xffb0 => KeyCode::NumPad0,
+            0xffb1 => KeyCode::NumPad1,
+            0xffb2 => KeyCode::NumPad2,
+            0xffb3 => KeyCode::NumPad3,
+            0xffb4 => KeyCode::NumPad4,
+            0xffb5 => KeyCode::NumPad5,
+            0xffb6 => KeyCode::NumPad6,
+            0xffb7 => KeyCode::NumPad7,
+            0xffb8 => KeyCode::NumPad8,
+            0xffb9 => KeyCode::NumPad9,
+            0xffaa => KeyCode::Multiply,
+            0xffab => KeyCode::Add,
+            0xffac => KeyCode::Separator,
+            0xffad => KeyCode::Subtract,
+            0xffae => KeyCode::Decimal,
+            0xffaf => KeyCode::Divide,
+            0xffbe => KeyCode::F1,
+            0xffbf => KeyCode::F2,
+            0xffc0 => KeyCode::F3,
+            0xffc1 => KeyCode::F4,
+            0xffc2 => KeyCode::F5,
+            0xffc3 => KeyCode::F6,
+            0xffc4 => KeyCode::F7,
+            0xffc5 => KeyCode::F8,
+            0xffc6 => KeyCode::F9,
+            0xffc7 => KeyCode::F10,
+            0xffc8 => KeyCode::F11,
+            0xffc9 => KeyCode::F12,
+            0xffca => KeyCode::F13,
+            0xffcb => KeyCode::F14,
+            0xffcc => KeyCode::F15,
+            0xffcd => KeyCode::F16,
+            0xffce => KeyCode::F17,
+            0xffcf => KeyCode::F18,
+            0xffd0 => KeyCode::F19,
+            0xffd1 => KeyCode::F20,
+            0xffd2 => KeyCode::F21,
+            0xffd3 => KeyCode::F22,
+            0xffd4 => KeyCode::F23,
+            0xffd5 => KeyCode::F24,
+            0xff7f => KeyCode::NumLock,
+            0xff14 => KeyCode::Scroll,
+            0x1008ff26 => KeyCode::BrowserBack,
+            0x1008ff27 => KeyCode::BrowserForward,
+            0x1008ff29 => KeyCode::BrowserRefresh,
+            0x1008ff28 => KeyCode::BrowserStop,
+            0x1008ff1b => KeyCode::BrowserSearch,
+            0x1008ff30 => KeyCode::BrowserFavorites,
+            0x1008ff18 => KeyCode::BrowserHome,
+            0x1008ff12 => KeyCode::VolumeMute,
+            0x1008ff11 => KeyCode::VolumeDown,
+            0x1008ff13 => KeyCode::VolumeUp,
+            0x1008ff17 => KeyCode::MediaNext,
+            0x1008ff16 => KeyCode::MediaPrev,
+            0x1008ff15 => KeyCode::MediaStop,
+            0x1008ff14 => KeyCode::MediaPlayPause,
+            0x1008ff19 => KeyCode::LaunchMail,
+            0x1008ff32 => KeyCode::LaunchMediaSelect,
+            0x1008ff4a => KeyCode::LaunchApp1,
+            0x1008ff4b => KeyCode::LaunchApp2,
+            0xfd0e => KeyCode::Attention,
+            0xfd1c => KeyCode::CrSel,
+            0xfd1d => KeyCode::ExSel,
+            0xfd06 => KeyCode::EraseEof,
+            0xfd16 => KeyCode::Play,
+            0x1008ff8b => KeyCode::Zoom,
+            0xfd0a => KeyCode::Pa1,
+            0x3d => KeyCode::Char('+'),
+            0x60 => KeyCode::Char('~'),
+            char @ 0x21..=0x7e | char @ 0xa0..=0xff => match char::from_u32(char) {
+                Some(v) => {
+                    if v.is_alphabetic() {
+                        KeyCode::Char(v.to_ascii_lowercase())
+                    } else {
+                        KeyCode::Char(v)
+                    }
+                }
+                None => KeyCode::Unkown(char as isize),
+            },
+            // Unicode keysyms carry the code point in the lower bits
+            char @ 0x01000100..=0x0110ffff => match char::from_u32(char & 0x00ff_ffff) {
+                Some(v) => KeyCode::Char(v.to_lowercase().next().unwrap_or(v)),
+                None => KeyCode::Unkown(char as isize),
+            },
+            other => KeyCode::Unkown(other as isize),
+        }
+    }
+}
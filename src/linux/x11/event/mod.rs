@@ -0,0 +1,383 @@
+mod keyboard;
+mod mouse;
+
+use std::collections::HashMap;
+
+use x11rb::connection::Connection as _;
+use x11rb::protocol::xinput::{self, ConnectionExt as _, Device, XIEventMask};
+use x11rb::protocol::xproto::{
+    AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt, EventMask,
+    KeyPressEvent, NotifyDetail, NotifyMode,
+};
+use x11rb::protocol::Event as XEvent;
+
+use crate::dpi::PhysicalSize;
+use crate::error::Error;
+use crate::event::keyboard::{KeyCode, KeyEvent};
+use crate::event::mouse::MouseEventType;
+use crate::event::{exit_after_close, state_change, Event, IntoEventResult, State};
+
+use super::window::{forget, windows, Window};
+use super::{connection, Connection};
+use crate::linux::mouse::ClickTracker;
+use crate::window::{HitTest, WindowId};
+use keyboard::Keysym;
+use mouse::{button_release, mouse_event, raw_motion};
+
+/// Ask a window to close. This goes through the event loop the same way the window manager's
+/// close button does so `Event::Close` is still delivered.
+pub fn close(id: WindowId) {
+    if let Ok(connection) = connection() {
+        let atoms = &connection.atoms;
+        let _ = connection.conn.send_event(
+            false,
+            id.0 as u32,
+            EventMask::NO_EVENT,
+            ClientMessageEvent::new(
+                32,
+                id.0 as u32,
+                atoms.WM_PROTOCOLS,
+                [atoms.WM_DELETE_WINDOW, 0, 0, 0, 0],
+            ),
+        );
+        let _ = connection.conn.flush();
+    }
+}
+
+fn toggle_maximized(id: WindowId) {
+    if Window::is_maximized(id) {
+        Window::restore(id);
+    } else {
+        Window::maximize(id);
+    }
+}
+
+/// Window state last passed to the callback, X11 only tells what it is now
+#[derive(Default)]
+struct Reported {
+    size: Option<(u32, u32)>,
+    position: Option<(i32, i32)>,
+    minimized: bool,
+    maximized: bool,
+}
+
+/// Get the window a keyboard or mouse event is for
+fn input_window(event: &XEvent) -> Option<u32> {
+    match event {
+        XEvent::KeyPress(event) | XEvent::KeyRelease(event) => Some(event.event),
+        XEvent::ButtonPress(event) | XEvent::ButtonRelease(event) => Some(event.event),
+        XEvent::MotionNotify(event) => Some(event.event),
+        _ => None,
+    }
+}
+
+/// Ask for XInput 2 raw motion, it isn't tied to a window so it is selected on the root window
+/// and passed to the focused one
+fn select_raw_motion(connection: &Connection, root: u32) -> Result<(), Error> {
+    let conn = &connection.conn;
+    let version = conn.xinput_xi_query_version(2, 0)?.reply()?;
+    if version.major_version < 2 {
+        return Err(Error::unsupported("Raw mouse motion without XInput 2"));
+    }
+    conn.xinput_xi_select_events(
+        root,
+        &[xinput::EventMask {
+            deviceid: Device::ALL_MASTER.into(),
+            mask: vec![XIEventMask::RAW_MOTION],
+        }],
+    )?;
+    Ok(())
+}
+
+fn key_code(event: &KeyPressEvent) -> KeyCode {
+    connection().map_or(KeyCode::Unkown(event.detail as isize), |connection| {
+        KeyCode::from(Keysym(connection.keysym(event.detail)))
+    })
+}
+
+pub fn run<R, F, T>(state: State<T>, callback: F)
+where
+    R: IntoEventResult,
+    F: (Fn(WindowId, Event, State<T>) -> R) + 'static + Sync + Send,
+    T: Send + Sync + Clone + 'static,
+{
+    let connection = match connection() {
+        Ok(connection) => connection,
+        Err(err) => {
+            #[cfg(debug_assertions)]
+            eprintln!("{}", err);
+            return;
+        }
+    };
+    let conn = &connection.conn;
+    let atoms = &connection.atoms;
+
+    let mut clicks = ClickTracker::default();
+    let mut pending: Option<XEvent> = None;
+    let mut reported: HashMap<WindowId, Reported> = HashMap::new();
+    let mut focused: Option<WindowId> = None;
+
+    // Desktops change `Xft.dpi` on the root window when the scale changes
+    let root = connection.screen().root;
+    let _ = conn.change_window_attributes(
+        root,
+        &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+    );
+    let mut scale_factor = connection.scale_factor();
+    if let Err(err) = select_raw_motion(&connection, root) {
+        #[cfg(debug_assertions)]
+        eprintln!("{}", err);
+    }
+
+    loop {
+        let event = match pending.take() {
+            Some(event) => event,
+            None => {
+                let _ = conn.flush();
+                match conn.wait_for_event() {
+                    Ok(event) => event,
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        eprintln!("{:?}", err);
+                        break;
+                    }
+                }
+            }
+        };
+
+        // Modal windows keep input from reaching their owner
+        if let Some(window) = input_window(&event) {
+            if Window::is_blocked(WindowId(window as isize)) {
+                continue;
+            }
+        }
+
+        match event {
+            XEvent::Expose(event) if event.count == 0 => {
+                callback(
+                    WindowId(event.window as isize),
+                    Event::Repaint,
+                    state.clone(),
+                );
+            }
+            XEvent::ClientMessage(event)
+                if event.type_ == atoms.WM_PROTOCOLS
+                    && event.data.as_data32()[0] == atoms.WM_DELETE_WINDOW =>
+            {
+                let result = {
+                    callback(WindowId(event.window as isize), Event::Close, state.clone())
+                        .into_event_result()
+                };
+                if result {
+                    let _ = Window::close(WindowId(event.window as isize));
+                }
+            }
+            XEvent::DestroyNotify(event) if event.event == event.window => {
+                let id = WindowId(event.window as isize);
+                reported.remove(&id);
+                if focused == Some(id) {
+                    focused = None;
+                }
+                callback(id, Event::Destroyed, state.clone());
+                // Windows destroyed by someone else are still in the registry
+                let remaining = forget(id);
+                if exit_after_close(id, remaining) {
+                    break;
+                }
+            }
+            XEvent::ConfigureNotify(event) if event.event == event.window => {
+                let id = WindowId(event.window as isize);
+                let size = (event.width as u32, event.height as u32);
+                // Reparented windows get coordinates relative to the frame, ask for the real ones
+                let position = Window::position(id).ok();
+                let last = reported.entry(id).or_default();
+                if last.size.replace(size) != Some(size) {
+                    callback(
+                        id,
+                        Event::Resized {
+                            width: size.0,
+                            height: size.1,
+                        },
+                        state.clone(),
+                    );
+                }
+                if let Some((x, y)) = position {
+                    Window::remember_bounds(id, (x, y), size);
+                    if last.position.replace((x, y)) != Some((x, y)) {
+                        callback(id, Event::Moved { x, y }, state.clone());
+                    }
+                }
+            }
+            XEvent::PropertyNotify(event)
+                if event.window == root && event.atom == u32::from(AtomEnum::RESOURCE_MANAGER) =>
+            {
+                let previous = scale_factor;
+                scale_factor = connection.scale_factor();
+                if scale_factor == previous {
+                    continue;
+                }
+                for id in windows() {
+                    let Ok(size) = Window::client_size(id) else {
+                        continue;
+                    };
+                    let size = PhysicalSize::from(size)
+                        .to_logical(previous)
+                        .to_physical(scale_factor);
+                    callback(
+                        id,
+                        Event::ScaleFactorChanged {
+                            scale_factor,
+                            width: size.width,
+                            height: size.height,
+                        },
+                        state.clone(),
+                    );
+                    let _ = Window::set_size(id, size.width, size.height);
+                }
+            }
+            XEvent::PropertyNotify(event) if event.atom == atoms._NET_WM_STATE => {
+                let id = WindowId(event.window as isize);
+                let minimized = Window::is_minimized(id);
+                let maximized = Window::is_maximized(id);
+                let last = reported.entry(id).or_default();
+                let was = (last.minimized, last.maximized);
+                (last.minimized, last.maximized) = (minimized, maximized);
+                if let Some(event) = state_change(was, minimized, maximized) {
+                    callback(id, event, state.clone());
+                }
+            }
+            // Focus moving because of keyboard grabs or the pointer isn't a real focus change
+            XEvent::FocusIn(event)
+                if event.mode == NotifyMode::NORMAL && event.detail != NotifyDetail::POINTER =>
+            {
+                let id = WindowId(event.event as isize);
+                focused = Some(id);
+                if let Err(err) = Window::update_grab(id, true) {
+                    #[cfg(debug_assertions)]
+                    eprintln!("{}", err);
+                }
+                callback(id, Event::Focused(true), state.clone());
+            }
+            XEvent::FocusOut(event)
+                if event.mode == NotifyMode::NORMAL && event.detail != NotifyDetail::POINTER =>
+            {
+                let id = WindowId(event.event as isize);
+                if focused == Some(id) {
+                    focused = None;
+                }
+                let _ = Window::update_grab(id, false);
+                callback(id, Event::Focused(false), state.clone());
+            }
+            XEvent::XinputRawMotion(event) => {
+                let Some(id) = focused.filter(|id| Window::raw_mouse_motion(*id)) else {
+                    continue;
+                };
+                let (dx, dy) = raw_motion(&event);
+                // Buttons and wheels also send raw events, with only their own valuators
+                if dx != 0.0 || dy != 0.0 {
+                    callback(id, Event::MouseMotion { dx, dy }, state.clone());
+                }
+            }
+            XEvent::KeyPress(event) => {
+                callback(
+                    WindowId(event.event as isize),
+                    Event::Keyboard(KeyEvent::KeyDown(key_code(&event))),
+                    state.clone(),
+                );
+            }
+            XEvent::KeyRelease(event) => {
+                // Auto repeat sends a release immediately followed by a press with the same time
+                let next = conn.poll_for_event().ok().flatten();
+                match next {
+                    Some(XEvent::KeyPress(press))
+                        if press.detail == event.detail && press.time == event.time =>
+                    {
+                        callback(
+                            WindowId(event.event as isize),
+                            Event::Keyboard(KeyEvent::KeyHold(key_code(&press))),
+                            state.clone(),
+                        );
+                    }
+                    next => {
+                        pending = next;
+                        callback(
+                            WindowId(event.event as isize),
+                            Event::Keyboard(KeyEvent::KeyUp(key_code(&event))),
+                            state.clone(),
+                        );
+                    }
+                }
+            }
+            XEvent::ButtonPress(event) => {
+                let id = WindowId(event.event as isize);
+                let mouse = clicks.button_press(&event);
+                // Left presses on the frame regions of a window without decorations go to the
+                // window manager instead of the app
+                let hit = match event.detail {
+                    1 => Window::hit_test(id, event.event_x, event.event_y),
+                    _ => HitTest::Client,
+                };
+                match (hit, &mouse.etype) {
+                    (HitTest::Client, _) => {
+                        callback(id, Event::Mouse(mouse), state.clone());
+                    }
+                    (HitTest::Caption, MouseEventType::Double(_)) => toggle_maximized(id),
+                    (HitTest::Caption | HitTest::Resize(_), _) => {
+                        let root = (event.root_x, event.root_y);
+                        if let Err(err) = Window::move_resize(id, hit, root, event.detail) {
+                            #[cfg(debug_assertions)]
+                            eprintln!("{}", err);
+                        }
+                    }
+                    // Buttons act on release like native ones
+                    _ => {}
+                }
+            }
+            XEvent::ButtonRelease(event) => {
+                let id = WindowId(event.event as isize);
+                let hit = match event.detail {
+                    1 => Window::hit_test(id, event.event_x, event.event_y),
+                    _ => HitTest::Client,
+                };
+                match hit {
+                    HitTest::Minimize => Window::minimize(id),
+                    HitTest::Maximize => toggle_maximized(id),
+                    HitTest::Close => close(id),
+                    _ => {
+                        callback(id, Event::Mouse(button_release(&event)), state.clone());
+                    }
+                }
+            }
+            // A locked pointer only moves to be put back, raw motion reports the movement
+            XEvent::MotionNotify(event)
+                if Window::recenter_pointer(
+                    WindowId(event.event as isize),
+                    event.event_x,
+                    event.event_y,
+                ) => {}
+            XEvent::MotionNotify(event) => {
+                callback(
+                    WindowId(event.event as isize),
+                    Event::Mouse(mouse_event(
+                        event.event_x,
+                        event.event_y,
+                        MouseEventType::Move,
+                    )),
+                    state.clone(),
+                );
+            }
+            XEvent::EnterNotify(event) => {
+                callback(
+                    WindowId(event.event as isize),
+                    Event::Mouse(mouse_event(
+                        event.event_x,
+                        event.event_y,
+                        MouseEventType::Hover,
+                    )),
+                    state.clone(),
+                );
+            }
+            _ => {}
+        }
+    }
+}
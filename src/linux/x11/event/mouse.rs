@@ -0,0 +1,68 @@
+use x11rb::protocol::xinput::{Fp3232, RawMotionEvent};
+use x11rb::protocol::xproto::ButtonPressEvent;
+
+use crate::event::mouse::{MouseButton, MouseEvent, MouseEventType};
+use crate::linux::mouse::ClickTracker;
+use crate::window::WindowId;
+
+impl ClickTracker {
+    /// Convert an X11 button press into a mouse event
+    pub fn button_press(&mut self, event: &ButtonPressEvent) -> MouseEvent {
+        let etype = match event.detail {
+            4 => MouseEventType::Scroll(1),
+            5 => MouseEventType::Scroll(-1),
+            6 => MouseEventType::HScroll(-1),
+            7 => MouseEventType::HScroll(1),
+            detail => match MouseButton::from_detail(detail) {
+                Some(button) => self.press(WindowId(event.event as isize), button, event.time),
+                None => MouseEventType::Ignore,
+            },
+        };
+        mouse_event(event.event_x, event.event_y, etype)
+    }
+}
+
+/// Convert an X11 button release into a mouse event
+pub fn button_release(event: &ButtonPressEvent) -> MouseEvent {
+    let etype = match MouseButton::from_detail(event.detail) {
+        Some(button) => MouseEventType::Up(button),
+        // Wheel buttons send a release right after the press
+        None => MouseEventType::Ignore,
+    };
+    mouse_event(event.event_x, event.event_y, etype)
+}
+
+pub fn mouse_event(x: i16, y: i16, etype: MouseEventType) -> MouseEvent {
+    MouseEvent {
+        x: x.max(0) as u16,
+        y: y.max(0) as u16,
+        etype,
+    }
+}
+
+/// Get the unaccelerated x and y movement of an XInput 2 raw motion event. Values are only sent
+/// for the valuators set in the mask, valuators 0 and 1 are the x and y axes of a mouse.
+pub fn raw_motion(event: &RawMotionEvent) -> (f64, f64) {
+    let mask = event.valuator_mask.first().copied().unwrap_or(0);
+    let mut values = event.axisvalues_raw.iter().map(fixed_to_f64);
+    let dx = if mask & 1 != 0 { values.next() } else { None };
+    let dy = if mask & 2 != 0 { values.next() } else { None };
+    (dx.unwrap_or(0.0), dy.unwrap_or(0.0))
+}
+
+fn fixed_to_f64(value: &Fp3232) -> f64 {
+    value.integral as f64 + value.frac as f64 / (1u64 << 32) as f64
+}
+
+impl MouseButton {
+    pub fn from_detail(detail: u8) -> Option<Self> {
+        match detail {
+            1 => Some(MouseButton::Left),
+            2 => Some(MouseButton::Middle),
+            3 => Some(MouseButton::Right),
+            8 => Some(MouseButton::X1),
+            9 => Some(MouseButton::X2),
+            _ => None,
+        }
+    }
+}
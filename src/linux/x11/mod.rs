@@ -0,0 +1,474 @@
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use x11rb::atom_manager;
+use x11rb::connection::Connection as _;
+use x11rb::cursor::Handle;
+use x11rb::errors::{ConnectError, ConnectionError, ReplyError, ReplyOrIdError};
+use x11rb::protocol::render::{self, ConnectionExt as _, PictType};
+use x11rb::protocol::xproto::{
+    AtomEnum, ClientMessageEvent, ConnectionExt, CreateGCAux, EventMask, ImageFormat, ImageOrder,
+    Rectangle, Screen, VisualClass, Visualid,
+};
+use x11rb::resource_manager;
+use x11rb::xcb_ffi::XCBConnection;
+use x11rb::NONE;
+
+use crate::cursor::{CursorIcon, CustomCursor};
+use crate::error::Error;
+use crate::style::premultiply;
+
+pub mod event;
+pub mod monitor;
+pub mod window;
+
+atom_manager! {
+    pub Atoms: AtomsCookie {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_CHANGE_STATE,
+        UTF8_STRING,
+        _NET_WM_NAME,
+        _NET_WM_ICON,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_FULLSCREEN,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_BELOW,
+        _NET_WM_STATE_SKIP_TASKBAR,
+        _NET_WM_STATE_SKIP_PAGER,
+        _NET_WM_STATE_MODAL,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_NORMAL,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        _NET_FRAME_EXTENTS,
+        _NET_WORKAREA,
+        _NET_WM_MOVERESIZE,
+        _NET_WM_WINDOW_OPACITY,
+        _MOTIF_WM_HINTS,
+        _GTK_THEME_VARIANT,
+    }
+}
+
+/// `_NET_WM_STATE` client message actions
+pub const NET_WM_STATE_REMOVE: u32 = 0;
+pub const NET_WM_STATE_ADD: u32 = 1;
+
+/// `_NET_WM_MOVERESIZE` direction that moves the window instead of resizing it
+pub const NET_WM_MOVERESIZE_MOVE: u32 = 8;
+
+/// `WM_CHANGE_STATE` value used to iconify a window
+const ICONIC_STATE: u32 = 3;
+
+/// Connection to the X server shared by every window created on this thread.
+pub struct Connection {
+    /// libxcb connection so C libraries like Vulkan can draw to the windows with it
+    pub conn: XCBConnection,
+    pub screen: usize,
+    pub atoms: Atoms,
+
+    /// Keyboard mapping used to translate keycodes into keysyms
+    pub min_keycode: u8,
+    pub keysyms_per_keycode: u8,
+    pub keysyms: Vec<u32>,
+
+    /// Xcursor theme loader, `None` when the resource database can't be read
+    cursor_handle: OnceCell<Option<Handle>>,
+    /// Theme cursors loaded so far, they are shared by every window
+    cursors: RefCell<HashMap<CursorIcon, u32>>,
+    /// Cursor without visible pixels used to hide the pointer, `NONE` until it is needed
+    blank_cursor: Cell<u32>,
+}
+
+impl Connection {
+    fn new() -> Result<Self, Error> {
+        let (conn, screen) = XCBConnection::connect(None)?;
+        let atoms = Atoms::new(&conn)?.reply()?;
+
+        let setup = conn.setup();
+        let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)?
+            .reply()?;
+
+        Ok(Self {
+            conn,
+            screen,
+            atoms,
+            min_keycode,
+            keysyms_per_keycode: mapping.keysyms_per_keycode,
+            keysyms: mapping.keysyms,
+            cursor_handle: OnceCell::new(),
+            cursors: RefCell::new(HashMap::new()),
+            blank_cursor: Cell::new(NONE),
+        })
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.conn.setup().roots[self.screen]
+    }
+
+    /// Get the `xcb_connection_t` of the connection for C libraries
+    pub fn raw_xcb_connection(&self) -> Option<NonNull<c_void>> {
+        NonNull::new(self.conn.get_raw_xcb_connection())
+    }
+
+    /// Find a 32 bit TrueColor visual, windows need one to be transparent per pixel
+    pub fn argb_visual(&self) -> Option<Visualid> {
+        self.screen()
+            .allowed_depths
+            .iter()
+            .filter(|depth| depth.depth == 32)
+            .flat_map(|depth| &depth.visuals)
+            .find(|visual| visual.class == VisualClass::TRUE_COLOR)
+            .map(|visual| visual.visual_id)
+    }
+
+    /// Load a cursor from the Xcursor theme, x11rb falls back to the core cursor font for the
+    /// older names. Cursors no name was found for are `NONE`, which shows the parent's cursor.
+    pub fn cursor(&self, icon: CursorIcon) -> Result<u32, Error> {
+        if let Some(cursor) = self.cursors.borrow().get(&icon) {
+            return Ok(*cursor);
+        }
+        let handle = self
+            .cursor_handle
+            .get_or_init(|| {
+                let database = resource_manager::new_from_default(&self.conn).ok()?;
+                Handle::new(&self.conn, self.screen, &database)
+                    .ok()?
+                    .reply()
+                    .ok()
+            })
+            .as_ref()
+            .ok_or_else(|| Error::unsupported("Cursors without a resource database"))?;
+        let mut cursor = NONE;
+        for name in icon.names() {
+            cursor = handle.load_cursor(&self.conn, name)?;
+            if cursor != NONE {
+                break;
+            }
+        }
+        self.cursors.borrow_mut().insert(icon, cursor);
+        Ok(cursor)
+    }
+
+    /// Get a cursor with a fully transparent 1x1 image
+    pub fn blank_cursor(&self) -> Result<u32, Error> {
+        if self.blank_cursor.get() != NONE {
+            return Ok(self.blank_cursor.get());
+        }
+        let root = self.screen().root;
+        let (pixmap, gc, cursor) = (
+            self.conn.generate_id()?,
+            self.conn.generate_id()?,
+            self.conn.generate_id()?,
+        );
+        // Pixmap contents start undefined, a cleared mask hides every pixel
+        self.conn.create_pixmap(1, pixmap, root, 1, 1)?;
+        self.conn
+            .create_gc(gc, pixmap, &CreateGCAux::new().foreground(0))?;
+        self.conn.poly_fill_rectangle(
+            pixmap,
+            gc,
+            &[Rectangle {
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1,
+            }],
+        )?;
+        self.conn
+            .create_cursor(cursor, pixmap, pixmap, 0, 0, 0, 0, 0, 0, 0, 0)?;
+        self.conn.free_gc(gc)?;
+        self.conn.free_pixmap(pixmap)?;
+        self.blank_cursor.set(cursor);
+        Ok(cursor)
+    }
+
+    /// Create an ARGB cursor from an image with the RENDER extension, the caller frees it
+    pub fn create_cursor(&self, custom: &CustomCursor) -> Result<u32, Error> {
+        let format = self
+            .conn
+            .render_query_pict_formats()?
+            .reply()?
+            .formats
+            .into_iter()
+            .find(|format| {
+                let direct = &format.direct;
+                format.depth == 32
+                    && format.type_ == PictType::DIRECT
+                    && (direct.alpha_shift, direct.red_shift, direct.green_shift) == (24, 16, 8)
+                    && direct.blue_shift == 0
+            })
+            .ok_or_else(|| Error::unsupported("Cursor images without a 32 bit picture format"))?;
+
+        let image = custom.image();
+        let (width, height) = (image.width() as u16, image.height() as u16);
+        let lsb_first = self.conn.setup().image_byte_order == ImageOrder::LSB_FIRST;
+        // RENDER blends premultiplied pixels
+        let data: Vec<u8> = image
+            .argb()
+            .map(|pixel| premultiply(pixel, true, 1.0))
+            .flat_map(|pixel| match lsb_first {
+                true => pixel.to_le_bytes(),
+                false => pixel.to_be_bytes(),
+            })
+            .collect();
+
+        let (pixmap, gc, picture, cursor) = (
+            self.conn.generate_id()?,
+            self.conn.generate_id()?,
+            self.conn.generate_id()?,
+            self.conn.generate_id()?,
+        );
+        self.conn
+            .create_pixmap(32, pixmap, self.screen().root, width, height)?;
+        self.conn.create_gc(gc, pixmap, &CreateGCAux::new())?;
+        self.conn.put_image(
+            ImageFormat::Z_PIXMAP,
+            pixmap,
+            gc,
+            width,
+            height,
+            0,
+            0,
+            0,
+            32,
+            &data,
+        )?;
+        self.conn.render_create_picture(
+            picture,
+            pixmap,
+            format.id,
+            &render::CreatePictureAux::new(),
+        )?;
+        let (x, y) = custom.hotspot();
+        self.conn
+            .render_create_cursor(cursor, picture, x as u16, y as u16)?;
+        self.conn.render_free_picture(picture)?;
+        self.conn.free_gc(gc)?;
+        self.conn.free_pixmap(pixmap)?;
+        Ok(cursor)
+    }
+
+    /// Get the unshifted keysym for a keycode
+    pub fn keysym(&self, keycode: u8) -> u32 {
+        let index =
+            (keycode.saturating_sub(self.min_keycode) as usize) * self.keysyms_per_keycode as usize;
+        self.keysyms.get(index).copied().unwrap_or(0)
+    }
+
+    /// Ask the window manager to add, remove or toggle `_NET_WM_STATE` atoms on a window
+    pub fn set_wm_state(
+        &self,
+        window: u32,
+        action: u32,
+        first: u32,
+        second: u32,
+    ) -> Result<(), Error> {
+        self.send_to_root(ClientMessageEvent::new(
+            32,
+            window,
+            self.atoms._NET_WM_STATE,
+            [action, first, second, 1, 0],
+        ))
+    }
+
+    /// Ask the window manager to iconify a window
+    pub fn iconify(&self, window: u32) -> Result<(), Error> {
+        self.send_to_root(ClientMessageEvent::new(
+            32,
+            window,
+            self.atoms.WM_CHANGE_STATE,
+            [ICONIC_STATE, 0, 0, 0, 0],
+        ))
+    }
+
+    /// Check if `_NET_WM_STATE` on a window contains an atom
+    pub fn has_wm_state(&self, window: u32, state: u32) -> bool {
+        self.conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                0,
+                1024,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| {
+                reply
+                    .value32()
+                    .map(|mut atoms| atoms.any(|atom| atom == state))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Get the `[left, right, top, bottom]` size of the frame the window manager draws around a
+    /// window, zero when there is none
+    pub fn frame_extents(&self, window: u32) -> [u32; 4] {
+        self.conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_FRAME_EXTENTS,
+                AtomEnum::CARDINAL,
+                0,
+                4,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|extents| extents.collect::<Vec<_>>()))
+            .and_then(|extents| extents.try_into().ok())
+            .unwrap_or([0; 4])
+    }
+
+    /// Get the `(x, y, width, height)` area of the desktop not covered by panels, the whole screen
+    /// when the window manager doesn't report one
+    pub fn work_area(&self) -> (i32, i32, u32, u32) {
+        let screen = self.screen();
+        self.conn
+            .get_property(
+                false,
+                screen.root,
+                self.atoms._NET_WORKAREA,
+                AtomEnum::CARDINAL,
+                0,
+                4,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|area| area.collect::<Vec<_>>()))
+            .filter(|area| area.len() == 4)
+            .map(|area| (area[0] as i32, area[1] as i32, area[2], area[3]))
+            .unwrap_or((
+                0,
+                0,
+                screen.width_in_pixels as u32,
+                screen.height_in_pixels as u32,
+            ))
+    }
+
+    /// Get the `Xft.dpi` resource desktops set to scale applications, X11 has no DPI per monitor
+    pub fn xft_dpi(&self) -> Option<f64> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                self.screen().root,
+                AtomEnum::RESOURCE_MANAGER,
+                AtomEnum::STRING,
+                0,
+                u32::MAX,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        String::from_utf8_lossy(&reply.value)
+            .lines()
+            .find_map(|line| line.strip_prefix("Xft.dpi:"))
+            .and_then(|dpi| dpi.trim().parse().ok())
+            .filter(|dpi: &f64| *dpi > 0.0)
+    }
+
+    /// Get the scale factor from `Xft.dpi`, `1.0` when it isn't set
+    pub fn scale_factor(&self) -> f64 {
+        self.xft_dpi().map_or(1.0, |dpi| dpi / 96.0)
+    }
+
+    /// Hand a pointer drag that started at a root position over to the window manager, which
+    /// moves or resizes the window until the button is released. The pointer grab the press
+    /// started has to be released first or the window manager can't take it.
+    pub fn move_resize(
+        &self,
+        window: u32,
+        root: (i16, i16),
+        direction: u32,
+        button: u8,
+    ) -> Result<(), Error> {
+        self.conn.ungrab_pointer(x11rb::CURRENT_TIME)?;
+        self.send_to_root(ClientMessageEvent::new(
+            32,
+            window,
+            self.atoms._NET_WM_MOVERESIZE,
+            [root.0 as u32, root.1 as u32, direction, button as u32, 1],
+        ))
+    }
+
+    fn send_to_root(&self, event: ClientMessageEvent) -> Result<(), Error> {
+        self.conn.send_event(
+            false,
+            self.screen().root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+thread_local! {
+    static CONNECTION: RefCell<Option<Rc<Connection>>> = const { RefCell::new(None) };
+}
+
+/// Get the connection to the X server, opening it on first use
+pub fn connection() -> Result<Rc<Connection>, Error> {
+    CONNECTION.with(|connection| {
+        let mut connection = connection.borrow_mut();
+        if let Some(connection) = connection.as_ref() {
+            return Ok(connection.clone());
+        }
+        let opened = Rc::new(Connection::new()?);
+        *connection = Some(opened.clone());
+        Ok(opened)
+    })
+}
+
+impl From<ConnectError> for Error {
+    fn from(error: ConnectError) -> Self {
+        Self {
+            code: 0,
+            message: format!("Failed to connect to the X server: {}", error),
+        }
+    }
+}
+
+impl From<ConnectionError> for Error {
+    fn from(error: ConnectionError) -> Self {
+        Self {
+            code: 0,
+            message: format!("X server connection error: {}", error),
+        }
+    }
+}
+
+impl From<ReplyError> for Error {
+    fn from(error: ReplyError) -> Self {
+        match error {
+            ReplyError::ConnectionError(error) => error.into(),
+            ReplyError::X11Error(error) => Self {
+                code: error.error_code as isize,
+                message: format!("X11 request failed: {:?}", error.error_kind),
+            },
+        }
+    }
+}
+
+impl From<ReplyOrIdError> for Error {
+    fn from(error: ReplyOrIdError) -> Self {
+        match error {
+            ReplyOrIdError::IdsExhausted => Self {
+                code: 0,
+                message: "X server resource ids exhausted".into(),
+            },
+            ReplyOrIdError::ConnectionError(error) => error.into(),
+            ReplyOrIdError::X11Error(error) => ReplyError::X11Error(error).into(),
+        }
+    }
+}
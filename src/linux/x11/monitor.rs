@@ -0,0 +1,267 @@
+use x11rb::connection::Connection as _;
+use x11rb::protocol::randr::{
+    ConnectionExt as _, Crtc, GetScreenResourcesCurrentReply, Mode, ModeFlag, ModeInfo, Output,
+    SetConfig,
+};
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::CURRENT_TIME;
+
+use crate::error::Error;
+use crate::monitor::{Monitor, VideoMode};
+use crate::window::WindowId;
+
+use super::window::Window;
+use super::{connection, Connection};
+
+/// Get the refresh rate of the mode the first output of a monitor is driven with
+fn refresh_rate(
+    connection: &Connection,
+    resources: &GetScreenResourcesCurrentReply,
+    output: Output,
+) -> Option<u32> {
+    let conn = &connection.conn;
+    let timestamp = resources.config_timestamp;
+    let output = conn
+        .randr_get_output_info(output, timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    let crtc = conn
+        .randr_get_crtc_info(output.crtc, timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    let mode = resources.modes.iter().find(|mode| mode.id == crtc.mode)?;
+    mode_refresh_rate(mode)
+}
+
+/// Compute the refresh rate of a mode from its pixel clock and timings
+fn mode_refresh_rate(mode: &ModeInfo) -> Option<u32> {
+    let mut lines = mode.vtotal as u64;
+    if mode.mode_flags.contains(ModeFlag::DOUBLE_SCAN) {
+        lines *= 2;
+    }
+    if mode.mode_flags.contains(ModeFlag::INTERLACE) {
+        lines /= 2;
+    }
+    let pixels = mode.htotal as u64 * lines;
+    (pixels > 0).then(|| (mode.dot_clock as u64 * 1000 / pixels) as u32)
+}
+
+/// Get the part of a rectangle that is on a monitor, `None` when they don't overlap
+fn intersect(
+    area: (i32, i32, u32, u32),
+    position: (i32, i32),
+    size: (u32, u32),
+) -> Option<(i32, i32, u32, u32)> {
+    let left = area.0.max(position.0);
+    let top = area.1.max(position.1);
+    let right = (area.0 + area.2 as i32).min(position.0 + size.0 as i32);
+    let bottom = (area.1 + area.3 as i32).min(position.1 + size.1 as i32);
+    (right > left && bottom > top)
+        .then(|| (left, top, (right - left) as u32, (bottom - top) as u32))
+}
+
+/// List the monitors RandR 1.5 reports. The work area comes from `_NET_WORKAREA` which covers
+/// the whole desktop, it is cut down to each monitor.
+pub fn all() -> Result<Vec<Monitor>, Error> {
+    let connection = connection()?;
+    let conn = &connection.conn;
+    let screen = connection.screen();
+    let scale_factor = connection.scale_factor();
+    let work_area = connection.work_area();
+
+    let monitors = conn
+        .randr_get_monitors(screen.root, true)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .map(|reply| reply.monitors)
+        .unwrap_or_default();
+    if monitors.is_empty() {
+        // Without RandR the screen is the only monitor there is
+        let size = (
+            screen.width_in_pixels as u32,
+            screen.height_in_pixels as u32,
+        );
+        return Ok(vec![Monitor {
+            handle: screen.root as isize,
+            name: String::from("Screen"),
+            position: (0, 0),
+            size,
+            work_area,
+            refresh_rate: None,
+            scale_factor,
+            primary: true,
+        }]);
+    }
+
+    let resources = conn
+        .randr_get_screen_resources_current(screen.root)?
+        .reply()?;
+    monitors
+        .iter()
+        .map(|info| {
+            let name = conn.get_atom_name(info.name)?.reply()?.name;
+            let position = (info.x as i32, info.y as i32);
+            let size = (info.width as u32, info.height as u32);
+            Ok(Monitor {
+                handle: info.name as isize,
+                name: String::from_utf8_lossy(&name).into_owned(),
+                position,
+                size,
+                work_area: intersect(work_area, position, size)
+                    .unwrap_or((position.0, position.1, size.0, size.1)),
+                refresh_rate: info
+                    .outputs
+                    .first()
+                    .and_then(|output| refresh_rate(&connection, &resources, *output)),
+                scale_factor,
+                primary: info.primary,
+            })
+        })
+        .collect()
+}
+
+/// Get the monitor marked as primary, or the first one when none is
+pub fn primary() -> Result<Monitor, Error> {
+    let mut monitors = all()?;
+    let index = monitors
+        .iter()
+        .position(|monitor| monitor.primary)
+        .unwrap_or(0);
+    Ok(monitors.swap_remove(index))
+}
+
+/// Get the monitor with the largest part of the window frame on it
+pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+    let position = Window::position(id)?;
+    let size = Window::outer_size(id)?;
+    let monitors = all()?;
+    let covered = |monitor: &Monitor| {
+        let area = (
+            monitor.position.0,
+            monitor.position.1,
+            monitor.size.0,
+            monitor.size.1,
+        );
+        intersect(area, position, size).map_or(0, |(_, _, w, h)| w as u64 * h as u64)
+    };
+    match monitors.iter().max_by_key(|monitor| covered(monitor)) {
+        Some(monitor) if covered(monitor) > 0 => Ok(monitor.clone()),
+        _ => primary(),
+    }
+}
+
+/// Get the output driving a monitor, RandR monitors made of several outputs use the first one
+fn monitor_output(connection: &Connection, monitor: &Monitor) -> Result<Output, Error> {
+    connection
+        .conn
+        .randr_get_monitors(connection.screen().root, true)?
+        .reply()?
+        .monitors
+        .into_iter()
+        .find(|info| info.name as isize == monitor.handle)
+        .and_then(|info| info.outputs.first().copied())
+        .ok_or_else(|| Error::invalid_parameter("Monitor has no RandR output"))
+}
+
+/// List the modes of the monitor's output, every mode uses the depth of the root window
+pub fn video_modes(monitor: &Monitor) -> Result<Vec<VideoMode>, Error> {
+    let connection = connection()?;
+    let conn = &connection.conn;
+    let screen = connection.screen();
+    if monitor.handle == screen.root as isize {
+        // Without RandR the screen can't be switched to another mode
+        return Ok(vec![VideoMode {
+            size: monitor.size,
+            bit_depth: screen.root_depth as u16,
+            refresh_rate: monitor.refresh_rate,
+            monitor: monitor.clone(),
+        }]);
+    }
+
+    let output = monitor_output(&connection, monitor)?;
+    let resources = conn
+        .randr_get_screen_resources_current(screen.root)?
+        .reply()?;
+    let info = conn
+        .randr_get_output_info(output, resources.config_timestamp)?
+        .reply()?;
+    Ok(resources
+        .modes
+        .iter()
+        .filter(|mode| info.modes.contains(&mode.id))
+        .map(|mode| VideoMode {
+            size: (mode.width as u32, mode.height as u32),
+            bit_depth: screen.root_depth as u16,
+            refresh_rate: mode_refresh_rate(mode),
+            monitor: monitor.clone(),
+        })
+        .collect())
+}
+
+/// Drive a CRTC with another mode, keeping its position, rotation and outputs
+fn set_crtc_mode(connection: &Connection, crtc: Crtc, mode: Mode) -> Result<(), Error> {
+    let conn = &connection.conn;
+    let resources = conn
+        .randr_get_screen_resources_current(connection.screen().root)?
+        .reply()?;
+    let info = conn
+        .randr_get_crtc_info(crtc, resources.config_timestamp)?
+        .reply()?;
+    let reply = conn
+        .randr_set_crtc_config(
+            crtc,
+            CURRENT_TIME,
+            resources.config_timestamp,
+            info.x,
+            info.y,
+            mode,
+            info.rotation,
+            &info.outputs,
+        )?
+        .reply()?;
+    if reply.status != SetConfig::SUCCESS {
+        return Err(Error::busy(format!(
+            "Changing the video mode failed with {:?}",
+            reply.status
+        )));
+    }
+    conn.flush()?;
+    Ok(())
+}
+
+/// Switch the CRTC of the mode's monitor to the mode, returns the CRTC and the mode it had
+pub fn set_video_mode(connection: &Connection, mode: &VideoMode) -> Result<(Crtc, Mode), Error> {
+    let conn = &connection.conn;
+    let output = monitor_output(connection, mode.monitor())?;
+    let resources = conn
+        .randr_get_screen_resources_current(connection.screen().root)?
+        .reply()?;
+    let info = conn
+        .randr_get_output_info(output, resources.config_timestamp)?
+        .reply()?;
+    let target = resources
+        .modes
+        .iter()
+        .find(|candidate| {
+            info.modes.contains(&candidate.id)
+                && (candidate.width as u32, candidate.height as u32) == mode.size
+                && mode_refresh_rate(candidate) == mode.refresh_rate
+        })
+        .ok_or_else(|| Error::invalid_parameter("Video mode is not supported by the monitor"))?;
+    if info.crtc == 0 {
+        return Err(Error::invalid_parameter("Monitor is turned off"));
+    }
+    let previous = conn
+        .randr_get_crtc_info(info.crtc, resources.config_timestamp)?
+        .reply()?
+        .mode;
+    set_crtc_mode(connection, info.crtc, target.id)?;
+    Ok((info.crtc, previous))
+}
+
+/// Put a CRTC back to the mode it had before [`set_video_mode`]
+pub fn restore_video_mode(connection: &Connection, crtc: Crtc, mode: Mode) -> Result<(), Error> {
+    set_crtc_mode(connection, crtc, mode)
+}
@@ -0,0 +1,1281 @@
+use std::num::NonZeroU32;
+use std::ops::DerefMut;
+use std::sync::Mutex;
+
+use raw_window_handle::{
+    HandleError, RawDisplayHandle, RawWindowHandle, XcbDisplayHandle, XcbWindowHandle,
+};
+use x11rb::connection::{Connection as _, RequestConnection as _};
+use x11rb::properties::{AspectRatio, WmSizeHints};
+use x11rb::protocol::shape::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{
+    AtomEnum, ChangeWindowAttributesAux, ClipOrdering, ColormapAlloc, ConfigureWindowAux,
+    ConnectionExt, CreateGCAux, CreateWindowAux, EventMask, GrabMode, GrabStatus, ImageFormat,
+    ImageOrder, MapState, PropMode, WindowClass,
+};
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::dpi::{PhysicalSize, Size};
+use crate::error::Error;
+use crate::event::{self, set_handler};
+use crate::icon::WindowIcon;
+use crate::linux::is_dark_mode;
+use crate::style::{premultiply, Background, Theme};
+use crate::surface::stretch;
+use crate::window::{
+    Bounds, Fullscreen, HitTest, ResizeEdge, SizeConstraints, WindowId, WindowLevel, WindowOptions,
+    DEFAULT_SIZE,
+};
+
+use super::{
+    connection, monitor, Connection, NET_WM_MOVERESIZE_MOVE, NET_WM_STATE_ADD, NET_WM_STATE_REMOVE,
+};
+
+/// `_MOTIF_WM_HINTS` flag saying the decorations field is set
+const MWM_HINTS_DECORATIONS: u32 = 1 << 1;
+
+thread_local! {
+    static WINDOWS: Mutex<Vec<Window>> = const { Mutex::new(Vec::new()) }
+}
+
+fn with_window<R>(id: WindowId, f: impl FnOnce(&mut Window) -> R) -> Option<R> {
+    WINDOWS.with(|windows| {
+        let mut windows = windows.lock().unwrap();
+        let windows = windows.deref_mut();
+        windows
+            .iter_mut()
+            .find(|window| window.handle == id.0 as u32)
+            .map(f)
+    })
+}
+
+/// Get every window created on this thread
+pub fn windows() -> Vec<WindowId> {
+    WINDOWS.with(|windows| {
+        windows
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|window| WindowId(window.handle as isize))
+            .collect()
+    })
+}
+
+/// Drop a destroyed window from the registry and free its colormap and cursor, along with its
+/// handler and data. Returns how many windows are left.
+pub fn forget(id: WindowId) -> usize {
+    event::forget(id);
+    let (window, remaining) = WINDOWS.with(|windows| {
+        let mut windows = windows.lock().unwrap();
+        let window = windows
+            .iter()
+            .position(|window| window.handle == id.0 as u32)
+            .map(|index| windows.remove(index));
+        (window, windows.len())
+    });
+    if let (Some(window), Ok(connection)) = (window, connection()) {
+        let _ = window.free(&connection);
+    }
+    remaining
+}
+
+/// Write the window title as both the legacy and the UTF-8 property. `WM_NAME` is Latin-1, so
+/// characters outside of it are replaced by `?` there.
+fn write_title(connection: &Connection, handle: u32, title: &str) -> Result<(), Error> {
+    let latin1: Vec<u8> = title
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect();
+    connection.conn.change_property8(
+        PropMode::REPLACE,
+        handle,
+        AtomEnum::WM_NAME,
+        AtomEnum::STRING,
+        &latin1,
+    )?;
+    connection.conn.change_property8(
+        PropMode::REPLACE,
+        handle,
+        connection.atoms._NET_WM_NAME,
+        connection.atoms.UTF8_STRING,
+        title.as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Write `WM_NORMAL_HINTS`, locking the window to a size when it isn't resizable and passing the
+/// size constraints on to the window manager otherwise
+fn write_size_hints(
+    connection: &Connection,
+    handle: u32,
+    fixed_size: bool,
+    size: (u32, u32),
+    constraints: &SizeConstraints,
+) -> Result<(), Error> {
+    let pair = |(width, height): (u32, u32)| (width as i32, height as i32);
+    let mut hints = WmSizeHints::new();
+    if fixed_size {
+        hints.min_size = Some(pair(size));
+        hints.max_size = Some(pair(size));
+    } else {
+        hints.min_size = constraints.min_size.map(pair);
+        hints.max_size = constraints.max_size.map(pair);
+        hints.size_increment = constraints.resize_increments.map(pair);
+        hints.base_size = constraints
+            .resize_increments
+            .map(|_| pair(constraints.min_size.unwrap_or((0, 0))));
+        hints.aspect = constraints.aspect_ratio.map(|(width, height)| {
+            let ratio = AspectRatio::new(width as i32, height as i32);
+            (ratio, ratio)
+        });
+    }
+    hints.set_normal_hints(&connection.conn, handle)?;
+    Ok(())
+}
+
+/// Get the `_NET_WM_MOVERESIZE` direction for dragging an edge
+fn move_resize_direction(edge: ResizeEdge) -> u32 {
+    match edge {
+        ResizeEdge::TopLeft => 0,
+        ResizeEdge::Top => 1,
+        ResizeEdge::TopRight => 2,
+        ResizeEdge::Right => 3,
+        ResizeEdge::BottomRight => 4,
+        ResizeEdge::Bottom => 5,
+        ResizeEdge::BottomLeft => 6,
+        ResizeEdge::Left => 7,
+    }
+}
+
+/// Write every size of the large and small icon as `_NET_WM_ICON`, each image is its width and
+/// height followed by ARGB pixels. Window managers pick the size they need.
+fn write_icon(connection: &Connection, handle: u32, options: &WindowOptions) -> Result<(), Error> {
+    let data: Vec<u32> = options
+        .icon
+        .iter()
+        .chain(&options.small_icon)
+        .flat_map(|icon| icon.images())
+        .flat_map(|image| {
+            [image.width(), image.height()]
+                .into_iter()
+                .chain(image.argb())
+        })
+        .collect();
+    if data.is_empty() {
+        connection
+            .conn
+            .delete_property(handle, connection.atoms._NET_WM_ICON)?;
+    } else {
+        connection.conn.change_property32(
+            PropMode::REPLACE,
+            handle,
+            connection.atoms._NET_WM_ICON,
+            AtomEnum::CARDINAL,
+            &data,
+        )?;
+    }
+    Ok(())
+}
+
+/// Write `_NET_WM_WINDOW_OPACITY`, compositing window managers fade the whole window with it
+fn write_opacity(connection: &Connection, handle: u32, opacity: f32) -> Result<(), Error> {
+    let atom = connection.atoms._NET_WM_WINDOW_OPACITY;
+    if opacity >= 1.0 {
+        connection.conn.delete_property(handle, atom)?;
+    } else {
+        let opacity = (opacity.max(0.0) as f64 * u32::MAX as f64) as u32;
+        connection.conn.change_property32(
+            PropMode::REPLACE,
+            handle,
+            atom,
+            AtomEnum::CARDINAL,
+            &[opacity],
+        )?;
+    }
+    Ok(())
+}
+
+/// Get the `_NET_WM_STATE` atoms for the level and taskbar options of a window, tool windows
+/// are left out of the taskbar like on Windows
+fn wm_states(connection: &Connection, options: &WindowOptions) -> Vec<u32> {
+    let atoms = &connection.atoms;
+    let mut states = Vec::new();
+    match options.level {
+        WindowLevel::AlwaysOnTop => states.push(atoms._NET_WM_STATE_ABOVE),
+        WindowLevel::AlwaysOnBottom => states.push(atoms._NET_WM_STATE_BELOW),
+        WindowLevel::Normal => {}
+    }
+    if options.skip_taskbar || options.tool_window {
+        states.push(atoms._NET_WM_STATE_SKIP_TASKBAR);
+    }
+    if options.skip_pager {
+        states.push(atoms._NET_WM_STATE_SKIP_PAGER);
+    }
+    if options.modal && options.owner.is_some() {
+        states.push(atoms._NET_WM_STATE_MODAL);
+    }
+    states
+}
+
+/// Add or remove a `_NET_WM_STATE` atom. The window manager only takes requests for windows it
+/// manages, hidden windows get the property changed directly and it is read when they are shown.
+fn write_wm_state(
+    connection: &Connection,
+    handle: u32,
+    add: bool,
+    state: u32,
+) -> Result<(), Error> {
+    let id = WindowId(handle as isize);
+    if Window::is_visible(id) || Window::is_minimized(id) {
+        let action = if add {
+            NET_WM_STATE_ADD
+        } else {
+            NET_WM_STATE_REMOVE
+        };
+        return connection.set_wm_state(handle, action, state, 0);
+    }
+    let atoms = &connection.atoms;
+    let reply = connection
+        .conn
+        .get_property(false, handle, atoms._NET_WM_STATE, AtomEnum::ATOM, 0, 1024)?
+        .reply()?;
+    let mut states: Vec<u32> = reply
+        .value32()
+        .map(|states| states.filter(|&atom| atom != state).collect())
+        .unwrap_or_default();
+    if add {
+        states.push(state);
+    }
+    connection.conn.change_property32(
+        PropMode::REPLACE,
+        handle,
+        atoms._NET_WM_STATE,
+        AtomEnum::ATOM,
+        &states,
+    )?;
+    Ok(())
+}
+
+/// Mark the window as a utility window, most window managers only read this when the window is
+/// shown
+fn write_window_type(connection: &Connection, handle: u32, tool_window: bool) -> Result<(), Error> {
+    let atoms = &connection.atoms;
+    let window_type = match tool_window {
+        true => atoms._NET_WM_WINDOW_TYPE_UTILITY,
+        false => atoms._NET_WM_WINDOW_TYPE_NORMAL,
+    };
+    connection.conn.change_property32(
+        PropMode::REPLACE,
+        handle,
+        atoms._NET_WM_WINDOW_TYPE,
+        AtomEnum::ATOM,
+        &[window_type],
+    )?;
+    Ok(())
+}
+
+/// Give the window an empty input shape so clicks reach the windows below, or reset it to the
+/// whole window
+fn write_input_shape(
+    connection: &Connection,
+    handle: u32,
+    click_through: bool,
+) -> Result<(), Error> {
+    if click_through {
+        connection.conn.shape_rectangles(
+            shape::SO::SET,
+            shape::SK::INPUT,
+            ClipOrdering::UNSORTED,
+            handle,
+            0,
+            0,
+            &[],
+        )?;
+    } else {
+        connection
+            .conn
+            .shape_mask(shape::SO::SET, shape::SK::INPUT, handle, 0, 0, x11rb::NONE)?;
+    }
+    Ok(())
+}
+
+/// Resolve the background color for a theme.
+///
+/// The default visual on TrueColor displays uses `0xRRGGBB` pixels which matches how
+/// [`Background`] stores colors. ARGB visuals take premultiplied `0xAARRGGBB` pixels.
+fn background_pixel(theme: Theme, background: Background, argb: bool, transparent: bool) -> u32 {
+    let color = match theme {
+        Theme::Light => background.light(),
+        Theme::Dark => background.dark(),
+        Theme::Auto => background.color(is_dark_mode()),
+    };
+    if argb {
+        premultiply(color, transparent, 1.0)
+    } else {
+        color
+    }
+}
+
+fn is_dark(theme: Theme) -> bool {
+    match theme {
+        Theme::Light => false,
+        Theme::Dark => true,
+        Theme::Auto => is_dark_mode(),
+    }
+}
+
+pub struct Window {
+    handle: u32,
+    options: WindowOptions,
+    /// Colormap of the ARGB visual, only transparent windows have one
+    colormap: Option<u32>,
+    /// Cursor created from a custom image, theme cursors are owned by the connection
+    cursor: Option<u32>,
+    /// Set while the window holds the pointer grab for its `cursor_grab`
+    grabbed: bool,
+    /// Fullscreen mode last asked for, the window manager decides if the window actually is
+    fullscreen: Option<Fullscreen>,
+    /// Position and size from the last configure while the window was neither maximized nor
+    /// fullscreen
+    normal_bounds: Option<((i32, i32), (u32, u32))>,
+    /// CRTC switched for exclusive fullscreen and the mode it was driven with before
+    display_mode: Option<(u32, u32)>,
+}
+
+impl Window {
+    pub fn options(&self) -> &WindowOptions {
+        &self.options
+    }
+
+    /// Free the server resources of a destroyed window
+    fn free(self, connection: &Connection) -> Result<(), Error> {
+        if let Some(colormap) = self.colormap {
+            connection.conn.free_colormap(colormap)?;
+        }
+        if let Some(cursor) = self.cursor {
+            connection.conn.free_cursor(cursor)?;
+        }
+        if let Some((crtc, mode)) = self.display_mode {
+            monitor::restore_video_mode(connection, crtc, mode)?;
+        }
+        Ok(())
+    }
+
+    /// Set the cursor from the options, a custom cursor it replaces is freed
+    fn write_cursor(&mut self, connection: &Connection) -> Result<(), Error> {
+        let (cursor, custom) = match (&self.options.cursor, self.options.cursor_visible) {
+            (_, false) => (connection.blank_cursor()?, None),
+            (Cursor::Icon(icon), true) => (connection.cursor(*icon)?, None),
+            (Cursor::Custom(custom), true) => {
+                let cursor = connection.create_cursor(custom)?;
+                (cursor, Some(cursor))
+            }
+        };
+        connection.conn.change_window_attributes(
+            self.handle,
+            &ChangeWindowAttributesAux::new().cursor(cursor),
+        )?;
+        if let Some(previous) = std::mem::replace(&mut self.cursor, custom) {
+            connection.conn.free_cursor(previous)?;
+        }
+        Ok(())
+    }
+
+    /// Grab the pointer for a confined or locked cursor while the window has focus, or release
+    /// the grab. X11 has no pointer lock so a locked pointer is confined and put back in the
+    /// middle whenever it moves.
+    fn write_grab(&mut self, connection: &Connection, focused: bool) -> Result<(), Error> {
+        let conn = &connection.conn;
+        if !focused || self.options.cursor_grab == CursorGrab::None {
+            if std::mem::take(&mut self.grabbed) {
+                conn.ungrab_pointer(CURRENT_TIME)?;
+            }
+            return Ok(());
+        }
+        let reply = conn
+            .grab_pointer(
+                true,
+                self.handle,
+                EventMask::BUTTON_PRESS | EventMask::BUTTON_RELEASE | EventMask::POINTER_MOTION,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+                self.handle,
+                NONE,
+                CURRENT_TIME,
+            )?
+            .reply()?;
+        if reply.status != GrabStatus::SUCCESS {
+            return Err(Error::busy(format!(
+                "Grabbing the pointer failed with {:?}",
+                reply.status
+            )));
+        }
+        self.grabbed = true;
+        if self.options.cursor_grab == CursorGrab::Locked {
+            let geometry = conn.get_geometry(self.handle)?.reply()?;
+            let (x, y) = (geometry.width as i16 / 2, geometry.height as i16 / 2);
+            conn.warp_pointer(NONE, self.handle, 0, 0, 0, 0, x, y)?;
+        }
+        Ok(())
+    }
+
+    pub fn create(options: WindowOptions) -> Result<WindowId, Error> {
+        let connection = connection()?;
+        let conn = &connection.conn;
+        let atoms = &connection.atoms;
+        let root = connection.screen().root;
+
+        if let Some(owner) = options.owner {
+            if with_window(owner, |_| ()).is_none() {
+                return Err(Error::invalid_window(owner));
+            }
+        }
+
+        let handle = conn.generate_id()?;
+        let mut size = options.size.map_or(DEFAULT_SIZE, |size| {
+            size.to_physical(connection.scale_factor()).into()
+        });
+        if !options.fixed_size {
+            size = options.constraints.constrain(size, false);
+        }
+        let (width, height) = size;
+
+        // Without an ARGB visual the window is created opaque
+        let visual = options
+            .transparent
+            .then(|| connection.argb_visual())
+            .flatten();
+        let colormap = match visual {
+            Some(visual) => {
+                let colormap = conn.generate_id()?;
+                conn.create_colormap(ColormapAlloc::NONE, colormap, root, visual)?;
+                Some(colormap)
+            }
+            None => None,
+        };
+        let mut aux = CreateWindowAux::new();
+        if let Some(colormap) = colormap {
+            // Windows that don't use their parent's visual need their own colormap and border
+            // pixel or the server refuses them
+            aux = aux.colormap(colormap).border_pixel(0);
+        }
+        conn.create_window(
+            visual.map_or(COPY_DEPTH_FROM_PARENT, |_| 32),
+            handle,
+            root,
+            0,
+            0,
+            width as u16,
+            height as u16,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            visual.unwrap_or(x11rb::COPY_FROM_PARENT),
+            &aux.background_pixel(background_pixel(
+                options.theme,
+                options.background,
+                colormap.is_some(),
+                options.transparent,
+            ))
+            .event_mask(
+                EventMask::EXPOSURE
+                    | EventMask::STRUCTURE_NOTIFY
+                    | EventMask::KEY_PRESS
+                    | EventMask::KEY_RELEASE
+                    | EventMask::BUTTON_PRESS
+                    | EventMask::BUTTON_RELEASE
+                    | EventMask::POINTER_MOTION
+                    | EventMask::ENTER_WINDOW
+                    | EventMask::LEAVE_WINDOW
+                    | EventMask::FOCUS_CHANGE
+                    | EventMask::PROPERTY_CHANGE,
+            ),
+        )?;
+
+        // Let the window manager ask before closing so `Event::Close` can be handled
+        conn.change_property32(
+            PropMode::REPLACE,
+            handle,
+            atoms.WM_PROTOCOLS,
+            AtomEnum::ATOM,
+            &[atoms.WM_DELETE_WINDOW],
+        )?;
+        write_title(&connection, handle, &options.title)?;
+        write_size_hints(
+            &connection,
+            handle,
+            options.fixed_size,
+            (width, height),
+            &options.constraints,
+        )?;
+        if !options.decorations {
+            // There is no EWMH hint for this, window managers still follow the Motif one
+            conn.change_property32(
+                PropMode::REPLACE,
+                handle,
+                atoms._MOTIF_WM_HINTS,
+                atoms._MOTIF_WM_HINTS,
+                &[MWM_HINTS_DECORATIONS, 0, 0, 0, 0],
+            )?;
+        }
+
+        write_icon(&connection, handle, &options)?;
+
+        let show = options.show;
+        if options.opacity < 1.0 {
+            write_opacity(&connection, handle, options.opacity)?;
+        }
+        if options.click_through {
+            write_input_shape(&connection, handle, true)?;
+        }
+        if options.tool_window {
+            write_window_type(&connection, handle, true)?;
+        }
+        if let Some(owner) = options.owner {
+            // Window managers keep transient windows above their owner and minimize them with it
+            conn.change_property32(
+                PropMode::REPLACE,
+                handle,
+                AtomEnum::WM_TRANSIENT_FOR,
+                AtomEnum::WINDOW,
+                &[owner.0 as u32],
+            )?;
+        }
+        // Clients set the initial states themselves before the window is mapped
+        let states = wm_states(&connection, &options);
+        if !states.is_empty() {
+            conn.change_property32(
+                PropMode::REPLACE,
+                handle,
+                atoms._NET_WM_STATE,
+                AtomEnum::ATOM,
+                &states,
+            )?;
+        }
+
+        let mut window = Window {
+            handle,
+            options,
+            colormap,
+            cursor: None,
+            grabbed: false,
+            fullscreen: None,
+            normal_bounds: None,
+            display_mode: None,
+        };
+        window.write_theme(window.options.theme)?;
+        // Windows show the cursor of the root window until they set their own
+        if window.options.cursor != Cursor::default() || !window.options.cursor_visible {
+            window.write_cursor(&connection)?;
+        }
+        set_handler(WindowId(handle as isize), window.options.handler.as_ref());
+        WINDOWS.with(|windows| windows.lock().unwrap().push(window));
+
+        if show {
+            Window::show(WindowId(handle as isize));
+        }
+        conn.flush()?;
+        Ok(WindowId(handle as isize))
+    }
+
+    pub fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error> {
+        with_window(id, |window| window.write_theme(theme))
+            .ok_or_else(|| Error::invalid_window(id))?
+    }
+
+    /// Hint the theme to the window manager and repaint the background in its color
+    fn write_theme(&mut self, theme: Theme) -> Result<(), Error> {
+        let connection = connection()?;
+        let conn = &connection.conn;
+        self.options.theme = theme;
+
+        // Window managers that follow GTK use this hint to theme the title bar
+        let variant = if is_dark(theme) { "dark" } else { "light" };
+        conn.change_property8(
+            PropMode::REPLACE,
+            self.handle,
+            connection.atoms._GTK_THEME_VARIANT,
+            connection.atoms.UTF8_STRING,
+            variant.as_bytes(),
+        )?;
+        conn.change_window_attributes(
+            self.handle,
+            &ChangeWindowAttributesAux::new().background_pixel(background_pixel(
+                theme,
+                self.options.background,
+                self.colormap.is_some(),
+                self.options.transparent,
+            )),
+        )?;
+        // Repaint the background with the new color, this generates an expose event
+        conn.clear_area(true, self.handle, 0, 0, 0, 0)?;
+        conn.flush()?;
+        Ok(())
+    }
+
+    /// Show the window
+    pub fn show(id: WindowId) {
+        if let Ok(connection) = connection() {
+            let _ = connection.conn.map_window(id.0 as u32);
+            let _ = connection.conn.flush();
+        }
+    }
+
+    /// Hide the window
+    pub fn hide(id: WindowId) {
+        if let Ok(connection) = connection() {
+            let _ = connection.conn.unmap_window(id.0 as u32);
+            let _ = connection.conn.flush();
+        }
+    }
+
+    /// Minimize the window
+    pub fn minimize(id: WindowId) {
+        if let Ok(connection) = connection() {
+            let _ = connection.iconify(id.0 as u32);
+        }
+    }
+
+    /// Restore the window
+    pub fn restore(id: WindowId) {
+        if let Ok(connection) = connection() {
+            let _ = connection.set_wm_state(
+                id.0 as u32,
+                NET_WM_STATE_REMOVE,
+                connection.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                connection.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            );
+            // Mapping an iconified window brings it back
+            let _ = connection.conn.map_window(id.0 as u32);
+            let _ = connection.conn.flush();
+        }
+    }
+
+    /// Maximize the window
+    pub fn maximize(id: WindowId) {
+        if let Ok(connection) = connection() {
+            let _ = connection.conn.map_window(id.0 as u32);
+            let _ = connection.set_wm_state(
+                id.0 as u32,
+                NET_WM_STATE_ADD,
+                connection.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                connection.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+            );
+        }
+    }
+
+    /// Get the size of the area inside the window frame
+    pub fn client_size(id: WindowId) -> Result<(u32, u32), Error> {
+        let connection = connection()?;
+        let geometry = connection.conn.get_geometry(id.0 as u32)?.reply()?;
+        Ok((geometry.width as u32, geometry.height as u32))
+    }
+
+    /// Get the size of the window including the frame drawn by the window manager
+    pub fn outer_size(id: WindowId) -> Result<(u32, u32), Error> {
+        let (width, height) = Window::client_size(id)?;
+        let [left, right, top, bottom] = connection()?.frame_extents(id.0 as u32);
+        Ok((width + left + right, height + top + bottom))
+    }
+
+    /// Get the position of the top left corner of the window frame on the screen
+    pub fn position(id: WindowId) -> Result<(i32, i32), Error> {
+        let connection = connection()?;
+        // The window is usually reparented into a frame so its own geometry is relative to that
+        let origin = connection
+            .conn
+            .translate_coordinates(id.0 as u32, connection.screen().root, 0, 0)?
+            .reply()?;
+        let [left, _, top, _] = connection.frame_extents(id.0 as u32);
+        Ok((
+            origin.dst_x as i32 - left as i32,
+            origin.dst_y as i32 - top as i32,
+        ))
+    }
+
+    /// Get the window title
+    pub fn title(id: WindowId) -> Result<String, Error> {
+        let connection = connection()?;
+        let atoms = &connection.atoms;
+        let reply = connection
+            .conn
+            .get_property(
+                false,
+                id.0 as u32,
+                atoms._NET_WM_NAME,
+                atoms.UTF8_STRING,
+                0,
+                1024,
+            )?
+            .reply()?;
+        if !reply.value.is_empty() {
+            return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+        let reply = connection
+            .conn
+            .get_property(
+                false,
+                id.0 as u32,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                0,
+                1024,
+            )?
+            .reply()?;
+        Ok(String::from_utf8_lossy(&reply.value).into_owned())
+    }
+
+    /// Check if the window is mapped and not minimized
+    pub fn exists(id: WindowId) -> bool {
+        with_window(id, |_| ()).is_some()
+    }
+
+    pub fn is_visible(id: WindowId) -> bool {
+        connection()
+            .ok()
+            .and_then(|connection| {
+                let cookie = connection.conn.get_window_attributes(id.0 as u32).ok()?;
+                cookie.reply().ok()
+            })
+            .map(|attributes| attributes.map_state == MapState::VIEWABLE)
+            .unwrap_or(false)
+    }
+
+    /// Check if the window is minimized
+    pub fn is_minimized(id: WindowId) -> bool {
+        connection()
+            .map(|connection| {
+                connection.has_wm_state(id.0 as u32, connection.atoms._NET_WM_STATE_HIDDEN)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Check if the window is maximized
+    pub fn is_maximized(id: WindowId) -> bool {
+        connection()
+            .map(|connection| {
+                connection.has_wm_state(id.0 as u32, connection.atoms._NET_WM_STATE_MAXIMIZED_VERT)
+                    && connection
+                        .has_wm_state(id.0 as u32, connection.atoms._NET_WM_STATE_MAXIMIZED_HORZ)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Keep the bounds of a configure to restore later, unless the window manager made the
+    /// window cover the monitor
+    pub fn remember_bounds(id: WindowId, position: (i32, i32), size: (u32, u32)) {
+        if !Window::is_maximized(id) && !Window::is_fullscreen(id) {
+            with_window(id, |window| window.normal_bounds = Some((position, size)));
+        }
+    }
+
+    pub fn normal_bounds(id: WindowId) -> Result<Bounds, Error> {
+        let remembered = with_window(id, |window| window.normal_bounds)
+            .ok_or_else(|| Error::invalid_window(id))?;
+        match remembered {
+            Some((position, size)) if Window::is_maximized(id) || Window::is_fullscreen(id) => {
+                Ok((Some(position), size))
+            }
+            _ => Ok((Some(Window::position(id)?), Window::client_size(id)?)),
+        }
+    }
+
+    /// Check if the window is fullscreen
+    pub fn is_fullscreen(id: WindowId) -> bool {
+        connection()
+            .map(|connection| {
+                connection.has_wm_state(id.0 as u32, connection.atoms._NET_WM_STATE_FULLSCREEN)
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn fullscreen(id: WindowId) -> Option<Fullscreen> {
+        if !Window::is_fullscreen(id) {
+            return None;
+        }
+        with_window(id, |window| {
+            (window.fullscreen.clone()).unwrap_or(Fullscreen::Borderless(None))
+        })
+    }
+
+    /// Window managers make a window fullscreen on the monitor it is on, so it is moved to the
+    /// target monitor first. Exclusive fullscreen switches the monitor's CRTC to the mode and
+    /// switches it back when the window leaves fullscreen or is destroyed.
+    pub fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        let connection = connection()?;
+        let handle = id.0 as u32;
+        let state = connection.atoms._NET_WM_STATE_FULLSCREEN;
+
+        let previous = with_window(id, |window| window.display_mode.take())
+            .ok_or_else(|| Error::invalid_window(id))?;
+        if let Some((crtc, mode)) = previous {
+            monitor::restore_video_mode(&connection, crtc, mode)?;
+        }
+        let (target, display_mode) = match &fullscreen {
+            Some(Fullscreen::Exclusive(mode)) => (
+                Some(mode.monitor().clone()),
+                Some(monitor::set_video_mode(&connection, mode)?),
+            ),
+            Some(Fullscreen::Borderless(monitor)) => (monitor.clone(), None),
+            None => (None, None),
+        };
+
+        if fullscreen.is_some() {
+            if let Some(monitor) = target {
+                if Window::is_fullscreen(id) {
+                    connection.set_wm_state(handle, NET_WM_STATE_REMOVE, state, 0)?;
+                }
+                connection.conn.configure_window(
+                    handle,
+                    &ConfigureWindowAux::new()
+                        .x(monitor.position.0)
+                        .y(monitor.position.1),
+                )?;
+            }
+            connection.conn.map_window(handle)?;
+            connection.set_wm_state(handle, NET_WM_STATE_ADD, state, 0)?;
+        } else {
+            connection.set_wm_state(handle, NET_WM_STATE_REMOVE, state, 0)?;
+        }
+        connection.conn.flush()?;
+        with_window(id, |window| {
+            window.fullscreen = fullscreen;
+            window.display_mode = display_mode;
+        });
+        Ok(())
+    }
+
+    /// X11 has one scale factor for every monitor, see [`Connection::scale_factor`]
+    pub fn scale_factor(id: WindowId) -> f64 {
+        connection()
+            .ok()
+            .filter(|_| with_window(id, |_| ()).is_some())
+            .map_or(1.0, |connection| connection.scale_factor())
+    }
+
+    pub fn set_title(id: WindowId, title: String) -> Result<(), Error> {
+        let connection = connection()?;
+        write_title(&connection, id.0 as u32, &title)?;
+        connection.conn.flush()?;
+        with_window(id, |window| window.options.title = title);
+        Ok(())
+    }
+
+    pub fn set_cursor(id: WindowId, cursor: Cursor) -> Result<(), Error> {
+        Window::update_cursor(id, |options| options.cursor = cursor)
+    }
+
+    pub fn set_cursor_visible(id: WindowId, visible: bool) -> Result<(), Error> {
+        Window::update_cursor(id, |options| options.cursor_visible = visible)
+    }
+
+    pub fn set_cursor_grab(id: WindowId, grab: CursorGrab) -> Result<(), Error> {
+        let connection = connection()?;
+        let focus = connection.conn.get_input_focus()?.reply()?.focus;
+        with_window(id, |window| {
+            window.options.cursor_grab = grab;
+            window.write_grab(&connection, focus == window.handle)
+        })
+        .ok_or_else(|| Error::invalid_window(id))??;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// XInput2 raw motion is selected on the root window, this only decides if it is passed on
+    pub fn set_raw_mouse_motion(id: WindowId, enabled: bool) -> Result<(), Error> {
+        with_window(id, |window| window.options.raw_mouse_motion = enabled)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Check if the raw mouse movement of the focused window goes to the app
+    pub fn raw_mouse_motion(id: WindowId) -> bool {
+        with_window(id, |window| window.options.raw_mouse_motion()).unwrap_or(false)
+    }
+
+    /// Take the pointer grab again when the window gets focus and release it when the window
+    /// loses focus, the pointer shouldn't stay trapped in a window the user switched away from
+    pub fn update_grab(id: WindowId, focused: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| window.write_grab(&connection, focused)).unwrap_or(Ok(()))
+    }
+
+    /// Put a locked pointer back in the middle of the window after it moved to `x` and `y`,
+    /// returns `false` when the window isn't holding the pointer in place
+    pub fn recenter_pointer(id: WindowId, x: i16, y: i16) -> bool {
+        let locked = with_window(id, |window| {
+            window.grabbed && window.options.cursor_grab == CursorGrab::Locked
+        });
+        if locked != Some(true) {
+            return false;
+        }
+        if let Ok((width, height)) = Window::client_size(id) {
+            let center = (width as i16 / 2, height as i16 / 2);
+            // Warping to the middle sends a motion event there too
+            if (x, y) != center {
+                let _ = Window::set_cursor_position(id, center.0 as i32, center.1 as i32);
+            }
+        }
+        true
+    }
+
+    pub fn set_cursor_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        if with_window(id, |_| ()).is_none() {
+            return Err(Error::invalid_window(id));
+        }
+        let connection = connection()?;
+        let clamp = |value: i32| value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        connection
+            .conn
+            .warp_pointer(NONE, id.0 as u32, 0, 0, 0, 0, clamp(x), clamp(y))?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// Change the cursor options and set the cursor again
+    fn update_cursor(id: WindowId, f: impl FnOnce(&mut WindowOptions)) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| {
+            f(&mut window.options);
+            window.write_cursor(&connection)
+        })
+        .ok_or_else(|| Error::invalid_window(id))??;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        Window::update_icon(id, |options| options.icon = icon)
+    }
+
+    pub fn set_small_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        Window::update_icon(id, |options| options.small_icon = icon)
+    }
+
+    /// Change an icon and write both of them again
+    fn update_icon(id: WindowId, f: impl FnOnce(&mut WindowOptions)) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| {
+            f(&mut window.options);
+            write_icon(&connection, window.handle, &window.options)
+        })
+        .ok_or_else(|| Error::invalid_window(id))??;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error> {
+        let connection = connection()?;
+        let (fixed_size, constraints) = with_window(id, |window| {
+            window.options.size = Some(Size::Physical(PhysicalSize::new(width, height)));
+            (window.options.fixed_size, window.options.constraints)
+        })
+        .unwrap_or_default();
+        // The window manager would refuse to resize a window locked to its old size
+        if fixed_size {
+            write_size_hints(
+                &connection,
+                id.0 as u32,
+                fixed_size,
+                (width, height),
+                &constraints,
+            )?;
+        }
+        connection.conn.configure_window(
+            id.0 as u32,
+            &ConfigureWindowAux::new().width(width).height(height),
+        )?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// Move the window frame, window managers place the frame at the requested position since
+    /// windows use the default `NorthWest` gravity
+    pub fn set_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        let connection = connection()?;
+        connection
+            .conn
+            .configure_window(id.0 as u32, &ConfigureWindowAux::new().x(x).y(y))?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// Center the window in the work area of the monitor most of it is on
+    pub fn center_on_monitor(id: WindowId) -> Result<(), Error> {
+        let (x, y, width, height) = super::monitor::from_window(id)?.work_area;
+        let (outer_width, outer_height) = Window::outer_size(id)?;
+        Window::set_position(
+            id,
+            x + (width as i32 - outer_width as i32) / 2,
+            y + (height as i32 - outer_height as i32) / 2,
+        )
+    }
+
+    pub fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error> {
+        with_window(id, |window| window.options.constraints)
+            .ok_or_else(|| Error::invalid_window(id))
+    }
+
+    /// Replace `WM_NORMAL_HINTS` and resize the window if it doesn't fit the new constraints
+    pub fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error> {
+        let connection = connection()?;
+        let fixed_size = with_window(id, |window| {
+            window.options.constraints = constraints;
+            window.options.fixed_size
+        })
+        .ok_or_else(|| Error::invalid_window(id))?;
+        let size = Window::client_size(id)?;
+        write_size_hints(&connection, id.0 as u32, fixed_size, size, &constraints)?;
+        connection.conn.flush()?;
+
+        let fitted = constraints.constrain(size, false);
+        if !fixed_size && fitted != size {
+            Window::set_size(id, fitted.0, fitted.1)?;
+        }
+        Ok(())
+    }
+
+    /// Check if a shown modal window owned by this one should keep input from reaching it
+    pub fn is_blocked(id: WindowId) -> bool {
+        let modals: Vec<WindowId> = WINDOWS.with(|windows| {
+            windows
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|window| window.options.modal && window.options.owner == Some(id))
+                .map(|window| WindowId(window.handle as isize))
+                .collect()
+        });
+        modals.into_iter().any(Window::is_visible)
+    }
+
+    /// Get what a point of the window acts as, decorated windows are client area only
+    pub fn hit_test(id: WindowId, x: i16, y: i16) -> HitTest {
+        if with_window(id, |window| window.options.decorations).unwrap_or(true) {
+            return HitTest::Client;
+        }
+        let size = Window::client_size(id).unwrap_or_default();
+        with_window(id, |window| {
+            window.options.hit_test(x as i32, y as i32, size)
+        })
+        .unwrap_or_default()
+    }
+
+    /// Let the window manager move or resize the window for a press on a caption or resize
+    /// region, other regions are ignored
+    pub fn move_resize(
+        id: WindowId,
+        hit: HitTest,
+        root: (i16, i16),
+        button: u8,
+    ) -> Result<(), Error> {
+        let direction = match hit {
+            HitTest::Caption => NET_WM_MOVERESIZE_MOVE,
+            HitTest::Resize(edge) => move_resize_direction(edge),
+            _ => return Ok(()),
+        };
+        connection()?.move_resize(id.0 as u32, root, direction, button)
+    }
+
+    pub fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| window.options.opacity = opacity)
+            .ok_or_else(|| Error::invalid_window(id))?;
+        write_opacity(&connection, id.0 as u32, opacity)?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// Switch between the alpha of the background color and an opaque background. The ARGB
+    /// visual this needs can only be picked when the window is created.
+    pub fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        let conn = &connection.conn;
+        let pixel = with_window(id, |window| {
+            if transparent && window.colormap.is_none() {
+                return Err(Error::unsupported(
+                    "Transparency on an X11 window that wasn't created transparent",
+                ));
+            }
+            window.options.transparent = transparent;
+            Ok(background_pixel(
+                window.options.theme,
+                window.options.background,
+                window.colormap.is_some(),
+                transparent,
+            ))
+        })
+        .ok_or_else(|| Error::invalid_window(id))??;
+        conn.change_window_attributes(
+            id.0 as u32,
+            &ChangeWindowAttributesAux::new().background_pixel(pixel),
+        )?;
+        conn.clear_area(true, id.0 as u32, 0, 0, 0, 0)?;
+        conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| window.options.click_through = click_through)
+            .ok_or_else(|| Error::invalid_window(id))?;
+        write_input_shape(&connection, id.0 as u32, click_through)?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_level(id: WindowId, level: WindowLevel) -> Result<(), Error> {
+        let connection = connection()?;
+        let atoms = &connection.atoms;
+        with_window(id, |window| window.options.level = level)
+            .ok_or_else(|| Error::invalid_window(id))?;
+        let handle = id.0 as u32;
+        write_wm_state(
+            &connection,
+            handle,
+            level == WindowLevel::AlwaysOnTop,
+            atoms._NET_WM_STATE_ABOVE,
+        )?;
+        write_wm_state(
+            &connection,
+            handle,
+            level == WindowLevel::AlwaysOnBottom,
+            atoms._NET_WM_STATE_BELOW,
+        )?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_tool_window(id: WindowId, tool_window: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        let skip_taskbar = with_window(id, |window| {
+            window.options.tool_window = tool_window;
+            window.options.skip_taskbar || tool_window
+        })
+        .ok_or_else(|| Error::invalid_window(id))?;
+        write_window_type(&connection, id.0 as u32, tool_window)?;
+        write_wm_state(
+            &connection,
+            id.0 as u32,
+            skip_taskbar,
+            connection.atoms._NET_WM_STATE_SKIP_TASKBAR,
+        )?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_skip_taskbar(id: WindowId, skip_taskbar: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        let skip_taskbar = with_window(id, |window| {
+            window.options.skip_taskbar = skip_taskbar;
+            skip_taskbar || window.options.tool_window
+        })
+        .ok_or_else(|| Error::invalid_window(id))?;
+        write_wm_state(
+            &connection,
+            id.0 as u32,
+            skip_taskbar,
+            connection.atoms._NET_WM_STATE_SKIP_TASKBAR,
+        )?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    pub fn set_skip_pager(id: WindowId, skip_pager: bool) -> Result<(), Error> {
+        let connection = connection()?;
+        with_window(id, |window| window.options.skip_pager = skip_pager)
+            .ok_or_else(|| Error::invalid_window(id))?;
+        write_wm_state(
+            &connection,
+            id.0 as u32,
+            skip_pager,
+            connection.atoms._NET_WM_STATE_SKIP_PAGER,
+        )?;
+        connection.conn.flush()?;
+        Ok(())
+    }
+
+    /// Scale the pixels to the client size and send them with `PutImage`, in bands of rows that
+    /// fit in one request. Depth 24 and 32 images both use 32 bits per pixel on every server
+    /// that is still around.
+    pub fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        let connection = connection()?;
+        let conn = &connection.conn;
+        let handle = id.0 as u32;
+        let depth = with_window(id, |window| match window.colormap {
+            Some(_) => 32,
+            None => connection.screen().root_depth,
+        })
+        .ok_or_else(|| Error::invalid_window(id))?;
+        let geometry = conn.get_geometry(handle)?.reply()?;
+        let size = (geometry.width as u32, geometry.height as u32);
+        if size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+        let pixels = stretch(pixels, (width, height), size);
+
+        // Image data is sent in the byte order the server asks for
+        let lsb_first = conn.setup().image_byte_order == ImageOrder::LSB_FIRST;
+        // Leave room for the request header
+        let rows = ((conn.maximum_request_bytes() - 32) / (size.0 as usize * 4)).max(1);
+        let gc = conn.generate_id()?;
+        conn.create_gc(gc, handle, &CreateGCAux::new().graphics_exposures(0))?;
+        for (band, chunk) in pixels.chunks(rows * size.0 as usize).enumerate() {
+            let data: Vec<u8> = chunk
+                .iter()
+                .flat_map(|pixel| match lsb_first {
+                    true => pixel.to_le_bytes(),
+                    false => pixel.to_be_bytes(),
+                })
+                .collect();
+            conn.put_image(
+                ImageFormat::Z_PIXMAP,
+                handle,
+                gc,
+                size.0 as u16,
+                (chunk.len() / size.0 as usize) as u16,
+                0,
+                (band * rows) as i16,
+                0,
+                depth,
+                &data,
+            )?;
+        }
+        conn.free_gc(gc)?;
+        conn.flush()?;
+        Ok(())
+    }
+
+    pub fn raw_window_handle(id: WindowId) -> Result<RawWindowHandle, HandleError> {
+        with_window(id, |window| window.handle)
+            .and_then(NonZeroU32::new)
+            .map(|handle| XcbWindowHandle::new(handle).into())
+            .ok_or(HandleError::Unavailable)
+    }
+
+    /// The display is a libxcb connection, see [`Connection::raw_xcb_connection`]
+    pub fn raw_display_handle(_id: WindowId) -> Result<RawDisplayHandle, HandleError> {
+        let connection = connection().map_err(|_| HandleError::Unavailable)?;
+        let raw = connection
+            .raw_xcb_connection()
+            .ok_or(HandleError::Unavailable)?;
+        Ok(XcbDisplayHandle::new(Some(raw), connection.screen as i32).into())
+    }
+
+    pub fn close(id: WindowId) -> Result<(), Error> {
+        let window = WINDOWS.with(|windows| {
+            let mut windows = windows.lock().unwrap();
+            let index = windows
+                .iter()
+                .position(|window| window.handle == id.0 as u32)?;
+            Some(windows.remove(index))
+        });
+        let connection = connection()?;
+        connection.conn.destroy_window(id.0 as u32)?;
+        if let Some(window) = window {
+            window.free(&connection)?;
+        }
+        connection.conn.flush()?;
+        Ok(())
+    }
+}
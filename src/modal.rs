@@ -2,6 +2,8 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 
 use crate::error::Error;
+use crate::headless::{self, DialogKind};
+use crate::window::WindowId;
 
 // TODO: Add more button combinations
 #[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
@@ -71,15 +73,34 @@ impl ColorDialog {
         self
     }
 
-    pub fn show_with(&self, parent: isize) -> Result<DialogAction, Error> {
+    pub fn show_with(&self, parent: WindowId) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::Color, Some(parent));
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::ColorPicker::new(self.initial_color, self.custom_colors.clone()).show_with(parent)
+        {
+            crate::windows::modal::ColorPicker::new(self.initial_color, self.custom_colors.clone()).show_with(parent.0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = parent;
+            Err(Error::unsupported("Color dialog"))
+        }
     }
 
     /// Show the color dialog
     pub fn show(&self) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::Color, None);
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::ColorPicker::new(self.initial_color, self.custom_colors.clone()).show()
+        {
+            crate::windows::modal::ColorPicker::new(self.initial_color, self.custom_colors.clone()).show()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err(Error::unsupported("Color dialog"))
+        }
     }
 
     pub fn custom_colors(mut self, colors: Vec<u32>) -> Self {
@@ -224,38 +245,95 @@ impl FileDialog {
 
     /// Take the current options and create an open file dialog
     pub fn open_file(&self) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::OpenFile, None);
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).pick_file(0)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).pick_file(0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err(Error::unsupported("File dialog"))
+        }
     }
 
     /// Take the current options and create a save file dialog
     pub fn save_file(&self) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::SaveFile, None);
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).save_file(0)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).save_file(0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err(Error::unsupported("File dialog"))
+        }
     }
 
     /// Take the current options and create an open folder dialog
     pub fn open_folder(&self) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::OpenFolder, None);
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).pick_folder(0)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).pick_folder(0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            Err(Error::unsupported("File dialog"))
+        }
     }
 
     /// Take the current options and create an open file dialog
-    pub fn open_file_with(&self, parent: isize) -> Result<DialogAction, Error> {
+    pub fn open_file_with(&self, parent: WindowId) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::OpenFile, Some(parent));
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).pick_file(parent)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).pick_file(parent.0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = parent;
+            Err(Error::unsupported("File dialog"))
+        }
     }
 
     /// Take the current options and create a save file dialog
-    pub fn save_file_with(&self, parent: isize) -> Result<DialogAction, Error> {
+    pub fn save_file_with(&self, parent: WindowId) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::SaveFile, Some(parent));
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).save_file(parent)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).save_file(parent.0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = parent;
+            Err(Error::unsupported("File dialog"))
+        }
     }
 
     /// Take the current options and create an open folder dialog
-    pub fn open_folder_with(&self, parent: isize) -> Result<DialogAction, Error> {
+    pub fn open_folder_with(&self, parent: WindowId) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::OpenFolder, Some(parent));
+        }
         #[cfg(target_os = "windows")]
-        crate::windows::modal::CommonFileDialog::new(&self).pick_folder(parent)
+        {
+            crate::windows::modal::CommonFileDialog::new(&self).pick_folder(parent.0)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = parent;
+            Err(Error::unsupported("File dialog"))
+        }
     }
 }
 
@@ -294,6 +372,9 @@ impl Prompt {
 
     /// Show the dialog
     pub fn show(&self) -> Button {
+        if headless::is_active() {
+            return headless::prompt(self.title, self.message);
+        }
         #[cfg(target_os = "windows")]
         {
             crate::windows::modal::MsgBox::new(self)
@@ -347,18 +428,33 @@ impl Font {
         self
     }
 
-    pub fn show_with(&self, parent: isize) -> Result<DialogAction, Error> {
-        #[cfg(target_os = "windows")]
-        crate::windows::modal::FontDialog {
-            point_size: self.size,
-            weight: self.weight,
-            italic: self.italic,
-            underline: self.underline,
-            strikethrough: self.strikethrough,
-        }
-        .show(parent)
+    pub fn show_with(&self, parent: WindowId) -> Result<DialogAction, Error> {
+        self.open(Some(parent))
     }
+
     pub fn show(&self) -> Result<DialogAction, Error> {
-        self.show_with(0)
+        self.open(None)
+    }
+
+    fn open(&self, parent: Option<WindowId>) -> Result<DialogAction, Error> {
+        if headless::is_active() {
+            return headless::dialog(DialogKind::Font, parent);
+        }
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows::modal::FontDialog {
+                point_size: self.size,
+                weight: self.weight,
+                italic: self.italic,
+                underline: self.underline,
+                strikethrough: self.strikethrough,
+            }
+            .show(parent.map_or(0, |parent| parent.0))
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = parent;
+            Err(Error::unsupported("Font dialog"))
+        }
     }
 }
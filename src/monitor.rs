@@ -0,0 +1,123 @@
+//! Displays connected to the system.
+//!
+//! ```no_run
+//! use storytree_native::Monitor;
+//!
+//! for monitor in Monitor::all().unwrap() {
+//!     println!("{}: {:?} at {:?}", monitor.name, monitor.size, monitor.position);
+//!     for mode in monitor.video_modes().unwrap() {
+//!         println!("  {:?} {:?} mHz", mode.size, mode.refresh_rate);
+//!     }
+//! }
+//! ```
+
+use crate::error::Error;
+use crate::window::WindowId;
+
+/// A display and its current mode.
+///
+/// Values are read when the monitor is queried and don't follow later changes. Positions are in
+/// the virtual screen that spans every monitor, the primary monitor starts at `(0, 0)` on Win32
+/// and X11.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /// `HMONITOR` on Win32, the RandR monitor name atom on X11 and the `wl_output` id on Wayland
+    pub(crate) handle: isize,
+
+    pub name: String,
+    /// Top left corner of the monitor
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+    /// Area not covered by taskbars and docks as `(x, y, width, height)`
+    pub work_area: (i32, i32, u32, u32),
+    /// Refresh rate in millihertz, `None` when the system doesn't report one
+    pub refresh_rate: Option<u32>,
+    /// Physical pixels per logical pixel, `1.0` is 96 DPI
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+impl Monitor {
+    /// Get every connected monitor
+    pub fn all() -> Result<Vec<Monitor>, Error> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows::monitor::all()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            crate::linux::monitor::all()
+        }
+    }
+
+    /// Get the monitor the taskbar or main menu is on
+    pub fn primary() -> Result<Monitor, Error> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows::monitor::primary()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            crate::linux::monitor::primary()
+        }
+    }
+
+    /// Get the monitor that most of the window is on
+    pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows::monitor::from_window(id)
+        }
+        #[cfg(target_os = "linux")]
+        {
+            crate::linux::monitor::from_window(id)
+        }
+    }
+
+    /// Get the native handle value of the monitor
+    pub fn as_raw(&self) -> isize {
+        self.handle
+    }
+
+    /// Get the modes the monitor can be switched to for exclusive fullscreen, largest and
+    /// fastest first. Wayland compositors don't let clients change modes.
+    pub fn video_modes(&self) -> Result<Vec<VideoMode>, Error> {
+        #[cfg(target_os = "windows")]
+        let mut modes = crate::windows::monitor::video_modes(self)?;
+        #[cfg(target_os = "linux")]
+        let mut modes = crate::linux::monitor::video_modes(self)?;
+        modes.sort_by(|a, b| {
+            let area = |mode: &VideoMode| mode.size.0 as u64 * mode.size.1 as u64;
+            (area(b), b.refresh_rate, b.bit_depth).cmp(&(area(a), a.refresh_rate, a.bit_depth))
+        });
+        // Drivers list the same mode once for every scaling option
+        modes.dedup();
+        Ok(modes)
+    }
+
+    /// Check if a point in the virtual screen is on this monitor
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.position.0
+            && y >= self.position.1
+            && ((x - self.position.0) as u32) < self.size.0
+            && ((y - self.position.1) as u32) < self.size.1
+    }
+}
+
+/// A resolution, color depth and refresh rate a monitor supports
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMode {
+    pub size: (u32, u32),
+    /// Bits per pixel
+    pub bit_depth: u16,
+    /// Refresh rate in millihertz, `None` when the system doesn't report one
+    pub refresh_rate: Option<u32>,
+    pub(crate) monitor: Monitor,
+}
+
+impl VideoMode {
+    /// Get the monitor the mode is for
+    pub fn monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+}
@@ -0,0 +1,323 @@
+//! Saving where a window is and putting it back there on the next launch.
+//!
+//! A [`WindowPlacement`] holds the monitor, the bounds the window has when it is neither
+//! maximized nor fullscreen and whether it is maximized or fullscreen. It is written as a short
+//! versioned `key=value` text, keys added by later releases are skipped when parsing.
+//! Restoring moves the window back inside the work area of a connected monitor when the monitor
+//! layout changed in between.
+//!
+//! ```no_run
+//! use storytree_native::prelude::*;
+//! use storytree_native::placement::WindowPlacement;
+//! use storytree_native::Window;
+//!
+//! let saved = std::fs::read_to_string("window.txt").ok();
+//! let mut builder = Window::builder().title("Editor");
+//! if let Some(placement) = saved.and_then(|text| text.parse::<WindowPlacement>().ok()) {
+//!     builder = builder.placement(placement);
+//! }
+//! let id = builder.show().unwrap();
+//!
+//! std::fs::write("window.txt", id.placement().unwrap().to_string()).unwrap();
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::monitor::Monitor;
+use crate::window::{Fullscreen, Window, WindowContext, WindowId};
+
+/// Version written on the first line, parsing rejects texts from a newer format
+const FORMAT_VERSION: u32 = 1;
+
+/// Where a window is on the desktop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowPlacement {
+    /// Name of the monitor the window is on
+    pub monitor: Option<String>,
+    /// Position of the window frame, `None` on Wayland where windows don't know where they are
+    pub position: Option<(i32, i32)>,
+    /// Size of the area inside the window frame in physical pixels
+    pub size: (u32, u32),
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+impl WindowPlacement {
+    /// Read the placement of a window, the position and size are the ones it goes back to when
+    /// it is restored
+    pub fn of(id: WindowId) -> Result<Self, Error> {
+        let (position, size) = Window::normal_bounds(id)?;
+        Ok(Self {
+            monitor: Monitor::from_window(id).ok().map(|monitor| monitor.name),
+            position,
+            size,
+            maximized: Window::is_maximized(id),
+            fullscreen: Window::is_fullscreen(id),
+        })
+    }
+
+    /// Move the placement inside the work area of its monitor. A monitor that is gone is replaced
+    /// by the one under the position, or the primary one. `frame` is added to the size when
+    /// checking that the window fits.
+    fn clamp(&self, monitors: &[Monitor], frame: (u32, u32)) -> (Self, Option<Monitor>) {
+        let monitor = monitors
+            .iter()
+            .find(|monitor| Some(&monitor.name) == self.monitor.as_ref())
+            .or_else(|| {
+                let (x, y) = self.position?;
+                monitors.iter().find(|monitor| monitor.contains(x, y))
+            })
+            .or_else(|| monitors.iter().find(|monitor| monitor.primary))
+            .or_else(|| monitors.first());
+        let Some(monitor) = monitor else {
+            return (self.clone(), None);
+        };
+
+        let (left, top, width, height) = monitor.work_area;
+        let outer = (
+            self.size.0.saturating_add(frame.0).min(width),
+            self.size.1.saturating_add(frame.1).min(height),
+        );
+        let placement = Self {
+            monitor: Some(monitor.name.clone()),
+            position: self.position.map(|(x, y)| {
+                (
+                    x.clamp(left, left + (width - outer.0) as i32),
+                    y.clamp(top, top + (height - outer.1) as i32),
+                )
+            }),
+            size: (
+                outer.0.saturating_sub(frame.0).max(1),
+                outer.1.saturating_sub(frame.1).max(1),
+            ),
+            ..self.clone()
+        };
+        (placement, Some(monitor.clone()))
+    }
+
+    /// Move and resize a window to the clamped placement, returns the monitor it was clamped to
+    pub(crate) fn apply_bounds(&self, id: WindowId) -> Result<Option<Monitor>, Error> {
+        if Window::is_fullscreen(id) {
+            Window::set_fullscreen(id, None)?;
+        }
+        if Window::is_maximized(id) {
+            Window::restore(id);
+        }
+        let (outer, client) = (Window::outer_size(id)?, Window::client_size(id)?);
+        let frame = (
+            outer.0.saturating_sub(client.0),
+            outer.1.saturating_sub(client.1),
+        );
+        let (placement, monitor) = self.clamp(&Monitor::all()?, frame);
+
+        // Placements saved on X11 still restore on Wayland, which can't move windows
+        if let (Some((x, y)), Ok(_)) = (placement.position, Window::position(id)) {
+            Window::set_position(id, x, y)?;
+        }
+        Window::set_size(id, placement.size.0, placement.size.1)?;
+        Ok(monitor)
+    }
+
+    /// Maximize the window or make it fullscreen on the monitor, this shows it
+    pub(crate) fn apply_state(&self, id: WindowId, monitor: Option<Monitor>) -> Result<(), Error> {
+        if self.maximized {
+            Window::maximize(id);
+        }
+        if self.fullscreen {
+            Window::set_fullscreen(id, Some(Fullscreen::Borderless(monitor)))?;
+        }
+        Ok(())
+    }
+
+    /// Give a window the placement, see [`WindowId::set_placement`]
+    pub(crate) fn apply(&self, id: WindowId) -> Result<(), Error> {
+        let monitor = self.apply_bounds(id)?;
+        self.apply_state(id, monitor)
+    }
+}
+
+impl fmt::Display for WindowPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version={}", FORMAT_VERSION)?;
+        if let Some(monitor) = &self.monitor {
+            writeln!(f, "monitor={}", monitor)?;
+        }
+        if let Some((x, y)) = self.position {
+            writeln!(f, "position={},{}", x, y)?;
+        }
+        writeln!(f, "size={},{}", self.size.0, self.size.1)?;
+        writeln!(f, "maximized={}", self.maximized)?;
+        writeln!(f, "fullscreen={}", self.fullscreen)
+    }
+}
+
+/// Parse a `first,second` pair
+fn pair<T: FromStr>(value: &str) -> Option<(T, T)> {
+    let (first, second) = value.split_once(',')?;
+    Some((first.trim().parse().ok()?, second.trim().parse().ok()?))
+}
+
+impl FromStr for WindowPlacement {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let mut placement = WindowPlacement {
+            monitor: None,
+            position: None,
+            size: (0, 0),
+            maximized: false,
+            fullscreen: false,
+        };
+        let mut version = None;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid =
+                || Error::invalid_parameter(format!("Line {}: invalid `{}`", index + 1, line));
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            match key.trim() {
+                "version" => version = Some(value.trim().parse::<u32>().map_err(|_| invalid())?),
+                "monitor" => placement.monitor = Some(value.to_string()),
+                "position" => placement.position = Some(pair(value).ok_or_else(invalid)?),
+                "size" => placement.size = pair(value).ok_or_else(invalid)?,
+                "maximized" => placement.maximized = value.trim().parse().map_err(|_| invalid())?,
+                "fullscreen" => {
+                    placement.fullscreen = value.trim().parse().map_err(|_| invalid())?
+                }
+                // Added by a later release, the rest of the placement is still usable
+                _ => {}
+            }
+        }
+
+        match version {
+            Some(FORMAT_VERSION) => {}
+            Some(version) => {
+                return Err(Error::invalid_parameter(format!(
+                    "Unsupported window placement version {}",
+                    version
+                )))
+            }
+            None => return Err(Error::invalid_parameter("Window placement has no version")),
+        }
+        if placement.size.0 == 0 || placement.size.1 == 0 {
+            return Err(Error::invalid_parameter("Window placement has no size"));
+        }
+        Ok(placement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, position: (i32, i32), size: (u32, u32), primary: bool) -> Monitor {
+        Monitor {
+            handle: 0,
+            name: name.to_string(),
+            position,
+            size,
+            // A 40 pixel taskbar at the bottom
+            work_area: (position.0, position.1, size.0, size.1 - 40),
+            refresh_rate: None,
+            scale_factor: 1.0,
+            primary,
+        }
+    }
+
+    fn placement() -> WindowPlacement {
+        WindowPlacement {
+            monitor: Some("DP-2".to_string()),
+            position: Some((2100, 100)),
+            size: (800, 600),
+            maximized: true,
+            fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let text = placement().to_string();
+        assert_eq!(
+            text,
+            "version=1\nmonitor=DP-2\nposition=2100,100\nsize=800,600\nmaximized=true\n\
+             fullscreen=false\n"
+        );
+        assert_eq!(text.parse::<WindowPlacement>().unwrap(), placement());
+
+        let wayland = WindowPlacement {
+            monitor: None,
+            position: None,
+            ..placement()
+        };
+        assert_eq!(
+            wayland.to_string().parse::<WindowPlacement>().unwrap(),
+            wayland
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_missing_sizes() {
+        let err = "version=2\nsize=800,600"
+            .parse::<WindowPlacement>()
+            .unwrap_err();
+        assert_eq!(err.message, "Unsupported window placement version 2");
+        let err = "size=800,600".parse::<WindowPlacement>().unwrap_err();
+        assert_eq!(err.message, "Window placement has no version");
+        let err = "version=1\nmaximized=true"
+            .parse::<WindowPlacement>()
+            .unwrap_err();
+        assert_eq!(err.message, "Window placement has no size");
+        let err = "version=1\nsize=800"
+            .parse::<WindowPlacement>()
+            .unwrap_err();
+        assert_eq!(err.message, "Line 2: invalid `size=800`");
+    }
+
+    #[test]
+    fn skips_unknown_keys() {
+        let text = "version=1\nsize=800,600\nworkspace=3\n\nmaximized=true";
+        let parsed = text.parse::<WindowPlacement>().unwrap();
+        assert_eq!(parsed.size, (800, 600));
+        assert!(parsed.maximized);
+    }
+
+    #[test]
+    fn clamps_to_the_connected_monitors() {
+        let laptop = monitor("eDP-1", (0, 0), (1920, 1080), true);
+        let external = monitor("DP-2", (1920, 0), (2560, 1440), false);
+        let monitors = [laptop, external];
+        let frame = (16, 39);
+
+        // Nothing to move while the monitor is still there
+        let (clamped, on) = placement().clamp(&monitors, frame);
+        assert_eq!(clamped, placement());
+        assert_eq!(on.unwrap().name, "DP-2");
+
+        // The external monitor is gone, the window moves onto the primary one
+        let (clamped, on) = placement().clamp(&monitors[..1], frame);
+        assert_eq!(on.unwrap().name, "eDP-1");
+        assert_eq!(clamped.monitor.as_deref(), Some("eDP-1"));
+        assert_eq!(clamped.position, Some((1920 - 816, 100)));
+        assert_eq!(clamped.size, (800, 600));
+
+        // Too large for the work area, the frame still fits
+        let large = WindowPlacement {
+            size: (4000, 3000),
+            ..placement()
+        };
+        let (clamped, _) = large.clamp(&monitors[..1], frame);
+        assert_eq!(clamped.position, Some((0, 0)));
+        assert_eq!(clamped.size, (1920 - 16, 1040 - 39));
+
+        // Parsed sizes can be anything
+        let huge = "version=1\nsize=4294967295,1"
+            .parse::<WindowPlacement>()
+            .unwrap();
+        let (clamped, _) = huge.clamp(&monitors[..1], frame);
+        assert_eq!(clamped.size, (1920 - 16, 1));
+    }
+}
@@ -1 +1 @@
-pub use crate::window::{WindowContext, WindowBuilder};
\ No newline at end of file
+pub use crate::window::{WindowBuilder, WindowContext};
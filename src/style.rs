@@ -1,4 +1,4 @@
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Theme {
     Light,
     Dark,
@@ -68,6 +68,17 @@ impl Default for Background {
     }
 }
 
+/// Turn a `0xAARRGGBB` color into a premultiplied pixel for a window with an alpha channel. The
+/// alpha byte is only used by transparent windows, other colors are opaque before `opacity`
+/// scales them.
+pub(crate) fn premultiply(color: u32, transparent: bool, opacity: f32) -> u32 {
+    let [alpha, red, green, blue] = color.to_be_bytes();
+    let alpha = if transparent { alpha } else { 0xFF };
+    let alpha = (alpha as f32 * opacity.clamp(0.0, 1.0)).round() as u32;
+    let scale = |channel: u8| (channel as u32 * alpha / 255) as u8;
+    u32::from_be_bytes([alpha as u8, scale(red), scale(green), scale(blue)])
+}
+
 impl From<u32> for Background {
     fn from(v: u32) -> Self {
         Self { light: v, dark: v }
@@ -0,0 +1,122 @@
+//! CPU framebuffer for software rendering.
+//!
+//! A [`Surface`] owns a buffer of `0xAARRGGBB` pixels that is drawn to the client area of a
+//! window with [`Surface::present`]. It is blitted with `StretchDIBits` on Windows, `PutImage`
+//! on X11 and a shared memory buffer on Wayland, so simple 2D tools need no GPU.
+//!
+//! ```no_run
+//! use storytree_native::event::{App, Event};
+//! use storytree_native::prelude::*;
+//! use storytree_native::surface::Surface;
+//! use storytree_native::Window;
+//!
+//! let id = Window::builder().title("Paint").show().unwrap();
+//! let surface = Surface::new(id).unwrap();
+//! App::run_with(surface, |_, event, state| {
+//!     if let Event::Repaint = event {
+//!         let mut surface = state.as_mut();
+//!         surface.fit().unwrap();
+//!         surface.fill(0xFF20_2020);
+//!         surface.present().unwrap();
+//!     }
+//! });
+//! ```
+
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut};
+
+use crate::error::Error;
+use crate::window::WindowId;
+
+/// Pixel buffer presented to a window
+#[derive(Debug, Clone)]
+pub struct Surface {
+    id: WindowId,
+    width: u32,
+    height: u32,
+    pixels: Vec<u32>,
+}
+
+impl Surface {
+    /// Create a buffer the size of the window's client area
+    pub fn new(id: WindowId) -> Result<Self, Error> {
+        let (width, height) = id.client_size()?;
+        let mut surface = Self {
+            id,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+        };
+        surface.resize(width, height);
+        Ok(surface)
+    }
+
+    /// Get the window the surface is presented to
+    pub fn window(&self) -> WindowId {
+        self.id
+    }
+
+    /// Get the width and height of the buffer in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Change the buffer size, the pixels are cleared to transparent black when it changes
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.pixels.clear();
+            self.pixels.resize(width as usize * height as usize, 0);
+        }
+    }
+
+    /// Resize the buffer to the client area of the window, returns `true` when the size changed
+    /// and the pixels have to be drawn again
+    pub fn fit(&mut self) -> Result<bool, Error> {
+        let size = self.id.client_size()?;
+        let changed = size != self.size();
+        self.resize(size.0, size.1);
+        Ok(changed)
+    }
+
+    /// Set every pixel to one color
+    pub fn fill(&mut self, pixel: u32) {
+        self.pixels.fill(pixel);
+    }
+
+    /// Draw the buffer to the window, stretched if it doesn't match the client size
+    pub fn present(&self) -> Result<(), Error> {
+        self.id.present(&self.pixels, self.width, self.height)
+    }
+}
+
+impl Deref for Surface {
+    type Target = [u32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.pixels
+    }
+}
+
+impl DerefMut for Surface {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pixels
+    }
+}
+
+/// Nearest neighbor scale a buffer for backends that can't stretch when drawing. Buffers that
+/// already have the target size are borrowed.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn stretch(pixels: &[u32], from: (u32, u32), to: (u32, u32)) -> Cow<'_, [u32]> {
+    if from == to {
+        return Cow::Borrowed(&pixels[..from.0 as usize * from.1 as usize]);
+    }
+    let (width, height) = (to.0 as usize, to.1 as usize);
+    let mut scaled = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = (y * from.1 as usize / height) * from.0 as usize;
+        scaled.extend((0..width).map(|x| pixels[row + x * from.0 as usize / width]));
+    }
+    Cow::Owned(scaled)
+}
@@ -1,36 +1,818 @@
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
+    RawWindowHandle, WindowHandle,
+};
+
+use crate::cursor::{Cursor, CursorGrab};
+use crate::data;
+use crate::dpi::{LogicalSize, PhysicalSize, Size};
 use crate::error::Error;
+use crate::event::{EventHandler, WindowHandler};
+use crate::icon::WindowIcon;
+use crate::monitor::{Monitor, VideoMode};
+use crate::placement::WindowPlacement;
 use crate::style::{Background, Theme};
 
+#[cfg(target_os = "linux")]
+pub use crate::linux::window::Window;
 #[cfg(target_os = "windows")]
 pub use crate::windows::window::Window;
 
+/// Position and client size of a window, the position is `None` when the backend can't tell
+pub(crate) type Bounds = (Option<(i32, i32)>, (u32, u32));
+
+/// Size used when no size is provided and the backend has no equivalent of `CW_USEDEFAULT`
+pub(crate) const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+/// Opaque handle to a window created by a [`WindowBuilder`].
+///
+/// Wraps the native handle of the active backend: an `HWND` on Win32, the window id on X11, the
+/// surface id on Wayland or a counter on the headless backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(pub(crate) isize);
+
+impl WindowId {
+    /// Get the native handle value of the window
+    pub fn as_raw(&self) -> isize {
+        self.0
+    }
+
+    /// Show the window
+    pub fn show(self) {
+        Window::show(self)
+    }
+
+    /// Hide the window
+    pub fn hide(self) {
+        Window::hide(self)
+    }
+
+    /// Minimize the window
+    pub fn minimize(self) {
+        Window::minimize(self)
+    }
+
+    /// Restore the window
+    pub fn restore(self) {
+        Window::restore(self)
+    }
+
+    /// Maximize the window
+    pub fn maximize(self) {
+        Window::maximize(self)
+    }
+
+    /// Toggle fullscreen mode
+    pub fn toggle_fullscreen(self) {
+        crate::toggle_fullscreen(self)
+    }
+
+    /// Make the window fullscreen, `None` puts it back to how it was before. Leaving exclusive
+    /// fullscreen restores the monitor's mode.
+    pub fn set_fullscreen(self, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        Window::set_fullscreen(self, fullscreen)
+    }
+
+    /// Get how the window is fullscreen, `None` when it isn't
+    pub fn fullscreen(self) -> Option<Fullscreen> {
+        Window::fullscreen(self)
+    }
+
+    /// Get the size of the area inside the window frame in physical pixels
+    pub fn client_size(self) -> Result<(u32, u32), Error> {
+        Window::client_size(self)
+    }
+
+    /// Get the size of the window including its frame in physical pixels
+    pub fn outer_size(self) -> Result<(u32, u32), Error> {
+        Window::outer_size(self)
+    }
+
+    /// Get the screen position of the top left corner of the window frame
+    pub fn position(self) -> Result<(i32, i32), Error> {
+        Window::position(self)
+    }
+
+    /// Get the window title
+    pub fn title(self) -> Result<String, Error> {
+        Window::title(self)
+    }
+
+    /// Check if the window is shown and not minimized
+    pub fn is_visible(self) -> bool {
+        Window::is_visible(self)
+    }
+
+    /// Check if the window is minimized
+    pub fn is_minimized(self) -> bool {
+        Window::is_minimized(self)
+    }
+
+    /// Check if the window is maximized
+    pub fn is_maximized(self) -> bool {
+        Window::is_maximized(self)
+    }
+
+    /// Check if the window is fullscreen
+    pub fn is_fullscreen(self) -> bool {
+        Window::is_fullscreen(self)
+    }
+
+    /// Change the window title
+    pub fn set_title(self, title: impl Into<String>) -> Result<(), Error> {
+        Window::set_title(self, title.into())
+    }
+
+    /// Switch the window between the light and dark theme or follow the system
+    pub fn set_theme(self, theme: Theme) -> Result<(), Error> {
+        Window::set_theme(self, theme)
+    }
+
+    /// Resize the area inside the window frame, in physical pixels
+    pub fn set_size(self, width: u32, height: u32) -> Result<(), Error> {
+        Window::set_size(self, width, height)
+    }
+
+    /// Move the top left corner of the window frame to a screen position
+    pub fn set_position(self, x: i32, y: i32) -> Result<(), Error> {
+        Window::set_position(self, x, y)
+    }
+
+    /// Move the window to the center of the work area of the monitor it is on
+    pub fn center_on_monitor(self) -> Result<(), Error> {
+        Window::center_on_monitor(self)
+    }
+
+    /// Get the ratio of physical to logical pixels for the monitor the window is on
+    pub fn scale_factor(self) -> f64 {
+        Window::scale_factor(self)
+    }
+
+    /// Replace the large icon, `None` removes it
+    pub fn set_icon(self, icon: Option<WindowIcon>) -> Result<(), Error> {
+        Window::set_icon(self, icon)
+    }
+
+    /// Replace the small icon, `None` falls back to the large icon
+    pub fn set_small_icon(self, icon: Option<WindowIcon>) -> Result<(), Error> {
+        Window::set_small_icon(self, icon)
+    }
+
+    /// Change the cursor shown while the mouse is over the client area
+    pub fn set_cursor(self, cursor: impl Into<Cursor>) -> Result<(), Error> {
+        Window::set_cursor(self, cursor.into())
+    }
+
+    /// Hide or show the cursor while the mouse is over the client area
+    pub fn set_cursor_visible(self, visible: bool) -> Result<(), Error> {
+        Window::set_cursor_visible(self, visible)
+    }
+
+    /// Confine or lock the pointer while the window has focus, the grab is released when the
+    /// window loses focus and taken again when it gets it back
+    pub fn set_cursor_grab(self, grab: CursorGrab) -> Result<(), Error> {
+        Window::set_cursor_grab(self, grab)
+    }
+
+    /// Send [`Event::MouseMotion`](crate::event::Event::MouseMotion) while the window has focus,
+    /// a cursor grab sends it either way
+    pub fn set_raw_mouse_motion(self, enabled: bool) -> Result<(), Error> {
+        Window::set_raw_mouse_motion(self, enabled)
+    }
+
+    /// Move the pointer to a point of the client area in physical pixels
+    pub fn set_cursor_position(self, x: i32, y: i32) -> Result<(), Error> {
+        Window::set_cursor_position(self, x, y)
+    }
+
+    /// Change how opaque the whole window is, from `0.0` to `1.0`
+    pub fn set_opacity(self, opacity: f32) -> Result<(), Error> {
+        Window::set_opacity(self, opacity.clamp(0.0, 1.0))
+    }
+
+    /// Use the alpha of the background color, see [`WindowBuilder::transparent`]
+    pub fn set_transparent(self, transparent: bool) -> Result<(), Error> {
+        Window::set_transparent(self, transparent)
+    }
+
+    /// Let mouse input pass through the window to whatever is below it
+    pub fn set_click_through(self, click_through: bool) -> Result<(), Error> {
+        Window::set_click_through(self, click_through)
+    }
+
+    /// Keep the window above or below normal windows
+    pub fn set_level(self, level: WindowLevel) -> Result<(), Error> {
+        Window::set_level(self, level)
+    }
+
+    /// Switch to a tool window, see [`WindowBuilder::tool_window`]
+    pub fn set_tool_window(self, tool_window: bool) -> Result<(), Error> {
+        Window::set_tool_window(self, tool_window)
+    }
+
+    /// Hide or show the taskbar entry of the window
+    pub fn set_skip_taskbar(self, skip_taskbar: bool) -> Result<(), Error> {
+        Window::set_skip_taskbar(self, skip_taskbar)
+    }
+
+    /// Hide or show the window in pagers and workspace switchers
+    pub fn set_skip_pager(self, skip_pager: bool) -> Result<(), Error> {
+        Window::set_skip_pager(self, skip_pager)
+    }
+
+    /// Get the monitor most of the window is on
+    pub fn monitor(self) -> Result<Monitor, Error> {
+        Monitor::from_window(self)
+    }
+
+    /// Get the placement to save for restoring the window with [`WindowBuilder::placement`]
+    pub fn placement(self) -> Result<WindowPlacement, Error> {
+        WindowPlacement::of(self)
+    }
+
+    /// Move the window to a saved placement, clamped to the work areas of the monitors that are
+    /// connected now. A maximized or fullscreen placement shows the window.
+    pub fn set_placement(self, placement: &WindowPlacement) -> Result<(), Error> {
+        placement.apply(self)
+    }
+
+    /// Attach a value to the window, one per type. It is dropped when the window is destroyed,
+    /// right after the app gets [`Event::Destroyed`](crate::event::Event::Destroyed) for it.
+    /// Returns the value it replaces, closed windows take no data.
+    ///
+    /// Windows belong to the thread that created them and so does their data, wrap the value in
+    /// a `Cell` or `RefCell` to change it from the event callback.
+    pub fn insert_data<T: 'static>(self, value: T) -> Result<Option<Rc<T>>, Error> {
+        if !Window::exists(self) {
+            return Err(Error::invalid_window(self));
+        }
+        Ok(data::insert(self, value))
+    }
+
+    /// Get the value of type `T` attached with [`WindowId::insert_data`]
+    pub fn data<T: 'static>(self) -> Option<Rc<T>> {
+        data::get(self)
+    }
+
+    /// Detach the value of type `T` from the window
+    pub fn remove_data<T: 'static>(self) -> Option<Rc<T>> {
+        data::remove(self)
+    }
+
+    /// Get the limits the user can resize the window within
+    pub fn size_constraints(self) -> Result<SizeConstraints, Error> {
+        Window::size_constraints(self)
+    }
+
+    /// Replace the size limits, the window is resized if it doesn't fit the new ones
+    pub fn set_size_constraints(self, constraints: SizeConstraints) -> Result<(), Error> {
+        Window::set_size_constraints(self, constraints)
+    }
+
+    /// Change the smallest client size the user can resize the window to
+    pub fn set_min_size(self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        let constraints = Window::size_constraints(self)?;
+        Window::set_size_constraints(
+            self,
+            SizeConstraints {
+                min_size: size,
+                ..constraints
+            },
+        )
+    }
+
+    /// Change the largest client size the user can resize the window to
+    pub fn set_max_size(self, size: Option<(u32, u32)>) -> Result<(), Error> {
+        let constraints = Window::size_constraints(self)?;
+        Window::set_size_constraints(
+            self,
+            SizeConstraints {
+                max_size: size,
+                ..constraints
+            },
+        )
+    }
+
+    /// Change the width to height ratio the client area keeps while resizing
+    pub fn set_aspect_ratio(self, ratio: Option<(u32, u32)>) -> Result<(), Error> {
+        let constraints = Window::size_constraints(self)?;
+        Window::set_size_constraints(
+            self,
+            SizeConstraints {
+                aspect_ratio: ratio,
+                ..constraints
+            },
+        )
+    }
+
+    /// Change the steps the client size grows and shrinks by while resizing
+    pub fn set_resize_increments(self, increments: Option<(u32, u32)>) -> Result<(), Error> {
+        let constraints = Window::size_constraints(self)?;
+        Window::set_size_constraints(
+            self,
+            SizeConstraints {
+                resize_increments: increments,
+                ..constraints
+            },
+        )
+    }
+
+    /// Draw a `width` by `height` buffer of `0xAARRGGBB` pixels, stretched over the client area.
+    /// The alpha byte is only used by transparent windows and has to be premultiplied. Call it
+    /// from `Event::Repaint` or keep a [`Surface`](crate::surface::Surface) that follows the
+    /// window size.
+    pub fn present(self, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        if (pixels.len() as u64) < width as u64 * height as u64 {
+            return Err(Error::invalid_buffer(pixels.len(), width, height));
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        Window::present(self, pixels, width, height)
+    }
+
+    /// Ask the window to close, the app callback gets `Event::Close` first
+    pub fn request_close(self) {
+        crate::event::close(self)
+    }
+
+    /// Destroy the window without sending `Event::Close`
+    pub fn close(self) -> Result<(), Error> {
+        Window::close(self)
+    }
+}
+
+/// Lets rendering libraries like wgpu, softbuffer and glutin create surfaces for the window. The
+/// handles are only valid until the window is closed, the headless backend has none.
+impl HasWindowHandle for WindowId {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        let raw = Window::raw_window_handle(*self)?;
+        // Safety: the native window lives as long as the window isn't closed
+        Ok(unsafe { WindowHandle::borrow_raw(raw) })
+    }
+}
+
+impl HasDisplayHandle for WindowId {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        let raw = Window::raw_display_handle(*self)?;
+        // Safety: connections are kept open for the rest of the thread
+        Ok(unsafe { DisplayHandle::borrow_raw(raw) })
+    }
+}
+
+/// Windowing system used to create windows and run the event loop.
+///
+/// Linux picks Wayland when `WAYLAND_DISPLAY` is set and X11 otherwise. This can be overridden
+/// with the `STORYTREE_BACKEND` environment variable (`x11`, `wayland` or `headless`) or
+/// [`WindowBuilder::backend`]. Building with the `headless` feature makes [`Backend::Headless`]
+/// the default everywhere. Only one backend can be active per thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Win32,
+    X11,
+    Wayland,
+    /// In memory windows without a display, see [`crate::headless`]
+    Headless,
+}
+
+impl Backend {
+    /// Environment variable used to pick the backend at runtime
+    pub const ENV: &'static str = "STORYTREE_BACKEND";
+
+    /// Get the backend requested through the `STORYTREE_BACKEND` environment variable
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(Self::ENV).ok()?.to_lowercase().as_str() {
+            "win32" | "windows" => Some(Backend::Win32),
+            "x11" => Some(Backend::X11),
+            "wayland" => Some(Backend::Wayland),
+            "headless" => Some(Backend::Headless),
+            _ => None,
+        }
+    }
+
+    /// Check if the backend can be used on the current platform
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Backend::Win32 => cfg!(target_os = "windows"),
+            Backend::X11 | Backend::Wayland => cfg!(target_os = "linux"),
+            Backend::Headless => true,
+        }
+    }
+
+    /// Backend used when neither the builder nor the environment ask for one
+    fn platform_default() -> Self {
+        if cfg!(feature = "headless") {
+            return Backend::Headless;
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Backend::Win32
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                Backend::Wayland
+            } else {
+                Backend::X11
+            }
+        }
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        {
+            Backend::Headless
+        }
+    }
+}
+
+thread_local! {
+    static BACKEND: Cell<Option<Backend>> = const { Cell::new(None) };
+}
+
+/// Pick the backend for this thread if one hasn't been picked yet.
+///
+/// `preferred` comes from the window builder and wins over the `STORYTREE_BACKEND` environment
+/// variable. Once a window is created the backend can't change since the event loop can only
+/// run one of them.
+pub(crate) fn select_backend(preferred: Option<Backend>) -> Result<Backend, Error> {
+    let backend = match (BACKEND.get(), preferred) {
+        (Some(active), Some(preferred)) if active != preferred => {
+            return Err(Error {
+                code: 0,
+                message: format!(
+                    "Can not create a {:?} window while the {:?} backend is active",
+                    preferred, active
+                ),
+            })
+        }
+        (Some(active), _) => active,
+        (None, Some(preferred)) => preferred,
+        (None, None) => Backend::from_env().unwrap_or_else(Backend::platform_default),
+    };
+
+    if !backend.is_supported() {
+        return Err(Error::unsupported(&format!("{:?} backend", backend)));
+    }
+    BACKEND.set(Some(backend));
+    Ok(backend)
+}
+
+/// Get the active backend, picking one if no window was created yet
+pub(crate) fn backend() -> Backend {
+    select_backend(None).unwrap_or_else(|_| Backend::platform_default())
+}
+
+/// Limits applied to the client size while the user resizes a window.
+///
+/// All sizes are physical pixels of the area inside the window frame. Increments are counted from
+/// `min_size`, or from zero when there is no minimum. A fixed size window ignores these.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SizeConstraints {
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    /// Width to height ratio, e.g. `(16, 9)`
+    pub aspect_ratio: Option<(u32, u32)>,
+    pub resize_increments: Option<(u32, u32)>,
+}
+
+impl SizeConstraints {
+    /// Check if any limit is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Get the closest size to `size` that fits the constraints.
+    ///
+    /// The aspect ratio is kept by changing the height, or the width when `height_driven` is set
+    /// because the user is dragging the top or bottom edge.
+    pub fn constrain(&self, size: (u32, u32), height_driven: bool) -> (u32, u32) {
+        let (mut width, mut height) = size;
+        let base = self.min_size.unwrap_or((0, 0));
+
+        if let Some((step_x, step_y)) = self.resize_increments {
+            if step_x > 1 && width > base.0 {
+                width = base.0 + (width - base.0) / step_x * step_x;
+            }
+            if step_y > 1 && height > base.1 {
+                height = base.1 + (height - base.1) / step_y * step_y;
+            }
+        }
+
+        if let Some((ratio_x, ratio_y)) = self.aspect_ratio.filter(|(x, y)| *x > 0 && *y > 0) {
+            let scale = |length: u32, to: u32, from: u32| {
+                (length as u64 * to as u64 / from as u64).min(u32::MAX as u64) as u32
+            };
+            if height_driven {
+                width = scale(height, ratio_x, ratio_y);
+            } else {
+                height = scale(width, ratio_y, ratio_x);
+            }
+            // Shrink both sides rather than cutting the ratio off at the maximum
+            let (max_width, max_height) = self.max_size.unwrap_or((u32::MAX, u32::MAX));
+            if width > max_width {
+                width = max_width;
+                height = scale(width, ratio_y, ratio_x);
+            }
+            if height > max_height {
+                height = max_height;
+                width = scale(height, ratio_x, ratio_y);
+            }
+        }
+
+        if let Some((max_width, max_height)) = self.max_size {
+            width = width.min(max_width);
+            height = height.min(max_height);
+        }
+        if let Some((min_width, min_height)) = self.min_size {
+            width = width.max(min_width);
+            height = height.max(min_height);
+        }
+        (width, height)
+    }
+}
+
+/// Stacking of a window relative to normal windows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowLevel {
+    /// Stays below normal windows, like a desktop widget
+    AlwaysOnBottom,
+    #[default]
+    Normal,
+    /// Stays above normal windows
+    AlwaysOnTop,
+}
+
+/// How a window covers a monitor
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fullscreen {
+    /// Cover a monitor without changing its mode, `None` is the monitor the window is on
+    Borderless(Option<Monitor>),
+    /// Switch the monitor of the mode to it and cover that monitor
+    Exclusive(VideoMode),
+}
+
+/// Edge or corner of a window that resizes it when dragged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// What a point of a window without decorations acts as, see [`HitTestRegions`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitTest {
+    /// Window content, mouse events go to the app callback
+    #[default]
+    Client,
+    /// Title bar, dragging it moves the window and double clicking it toggles maximize
+    Caption,
+    Resize(ResizeEdge),
+    Minimize,
+    Maximize,
+    Close,
+}
+
+/// Areas of a window without decorations that act like the native frame.
+///
+/// Rectangles are `(x, y, width, height)` in physical pixels of the client area. A negative `x`
+/// or `y` is counted from the right or bottom edge so buttons stay in the corner while the window
+/// is resized. Later regions win over earlier ones and the resize border wins over every region.
+///
+/// ```no_run
+/// use storytree_native::{HitTest, HitTestRegions};
+///
+/// let regions = HitTestRegions::new()
+///     .resize_border(6)
+///     .region((0, 0, u32::MAX, 32), HitTest::Caption)
+///     .region((-46, 0, 46, 32), HitTest::Close);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HitTestRegions {
+    /// Width of the band along the window edges that resizes the window, zero for none
+    pub resize_border: u32,
+    pub regions: Vec<((i32, i32, u32, u32), HitTest)>,
+}
+
+impl HitTestRegions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resize_border(mut self, width: u32) -> Self {
+        self.resize_border = width;
+        self
+    }
+
+    pub fn region(mut self, rect: (i32, i32, u32, u32), hit: HitTest) -> Self {
+        self.regions.push((rect, hit));
+        self
+    }
+
+    /// Get what a point of a client area of `size` acts as
+    pub fn hit_test(&self, x: i32, y: i32, size: (u32, u32)) -> HitTest {
+        let (width, height) = (size.0 as i64, size.1 as i64);
+        let (x, y) = (x as i64, y as i64);
+
+        let border = self.resize_border as i64;
+        let left = x < border;
+        let right = x >= width - border;
+        let top = y < border;
+        let bottom = y >= height - border;
+        let edge = match (top, bottom, left, right) {
+            (true, _, true, _) => Some(ResizeEdge::TopLeft),
+            (true, _, _, true) => Some(ResizeEdge::TopRight),
+            (_, true, true, _) => Some(ResizeEdge::BottomLeft),
+            (_, true, _, true) => Some(ResizeEdge::BottomRight),
+            (true, ..) => Some(ResizeEdge::Top),
+            (_, true, ..) => Some(ResizeEdge::Bottom),
+            (_, _, true, _) => Some(ResizeEdge::Left),
+            (.., true) => Some(ResizeEdge::Right),
+            _ => None,
+        };
+        if let Some(edge) = edge.filter(|_| border > 0) {
+            return HitTest::Resize(edge);
+        }
+
+        let contains = |&(rx, ry, rw, rh): &(i32, i32, u32, u32)| {
+            let rx = if rx < 0 { width + rx as i64 } else { rx as i64 };
+            let ry = if ry < 0 {
+                height + ry as i64
+            } else {
+                ry as i64
+            };
+            x >= rx && y >= ry && x - rx < rw as i64 && y - ry < rh as i64
+        };
+        self.regions
+            .iter()
+            .rev()
+            .find(|(rect, _)| contains(rect))
+            .map_or(HitTest::Client, |(_, hit)| *hit)
+    }
+}
+
+/// Hit-test function of a window without decorations, called with a point of the client area and
+/// the client size in physical pixels
+#[derive(Clone)]
+pub struct HitTester(Arc<HitTestFn>);
+
+type HitTestFn = dyn Fn(i32, i32, (u32, u32)) -> HitTest + Send + Sync;
+
+impl HitTester {
+    pub fn new(f: impl Fn(i32, i32, (u32, u32)) -> HitTest + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub fn hit_test(&self, x: i32, y: i32, size: (u32, u32)) -> HitTest {
+        (self.0)(x, y, size)
+    }
+}
+
+impl From<HitTestRegions> for HitTester {
+    fn from(regions: HitTestRegions) -> Self {
+        Self::new(move |x, y, size| regions.hit_test(x, y, size))
+    }
+}
+
+impl fmt::Debug for HitTester {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HitTester")
+    }
+}
+
 #[derive(Debug)]
 pub struct WindowOptions {
-    pub title: &'static str,
-    pub icon: Option<&'static str>,
+    pub title: String,
+    /// Icon for the taskbar and task switcher, also used for the title bar without `small_icon`
+    pub icon: Option<WindowIcon>,
+    /// Icon for the title bar
+    pub small_icon: Option<WindowIcon>,
 
     pub theme: Theme,
     pub background: Background,
     pub fixed_size: bool,
-    pub size: Option<(u32, u32)>,
+    /// Size of the area inside the window frame
+    pub size: Option<Size>,
+    pub constraints: SizeConstraints,
+    /// Draw the native title bar and border
+    pub decorations: bool,
+    /// Frame behaviour of a window without decorations, all of it is client area when unset
+    pub hit_test: Option<HitTester>,
+    /// Opacity of the whole window from `0.0` to `1.0`
+    pub opacity: f32,
+    /// Use the alpha byte of the background color
+    pub transparent: bool,
+    /// Mouse input goes to the windows below
+    pub click_through: bool,
+    pub level: WindowLevel,
+    /// Small caption and no taskbar entry
+    pub tool_window: bool,
+    pub skip_taskbar: bool,
+    pub skip_pager: bool,
+    /// Window this one stays above and minimizes with
+    pub owner: Option<WindowId>,
+    /// Block input to the owner while this window is shown
+    pub modal: bool,
+    /// Cursor shown over the client area
+    pub cursor: Cursor,
+    pub cursor_visible: bool,
+    pub cursor_grab: CursorGrab,
+    /// Report raw mouse movement while the window has focus, also done while the cursor is grabbed
+    pub raw_mouse_motion: bool,
+    /// Saved placement the window is moved to before it is shown
+    pub placement: Option<WindowPlacement>,
+    /// Gets the events of this window instead of the app callback
+    pub handler: Option<EventHandler>,
 
     pub show: bool,
+    pub backend: Option<Backend>,
 }
 
 impl Default for WindowOptions {
     fn default() -> Self {
         Self {
-            title: "",
+            title: String::new(),
             icon: None,
+            small_icon: None,
 
             theme: Theme::Auto,
             background: Background::default(),
             fixed_size: false,
             size: None,
+            constraints: SizeConstraints::default(),
+            decorations: true,
+            hit_test: None,
+            opacity: 1.0,
+            transparent: false,
+            click_through: false,
+            level: WindowLevel::Normal,
+            tool_window: false,
+            skip_taskbar: false,
+            skip_pager: false,
+            owner: None,
+            modal: false,
+            cursor: Cursor::default(),
+            cursor_visible: true,
+            cursor_grab: CursorGrab::None,
+            raw_mouse_motion: false,
+            placement: None,
+            handler: None,
 
             show: false,
+            backend: None,
+        }
+    }
+}
+
+impl WindowOptions {
+    /// Get what a point of the client area acts as, decorated windows are client area only
+    pub(crate) fn hit_test(&self, x: i32, y: i32, size: (u32, u32)) -> HitTest {
+        match &self.hit_test {
+            Some(tester) if !self.decorations => tester.hit_test(x, y, size),
+            _ => HitTest::Client,
         }
     }
+
+    /// Check if raw mouse movement is reported while the window has focus
+    pub(crate) fn raw_mouse_motion(&self) -> bool {
+        self.raw_mouse_motion || self.cursor_grab != CursorGrab::None
+    }
+}
+
+/// Create a window with `create` and give it what all backends handle the same way. The
+/// placement is applied before the window is shown. Windows created hidden only get the placement
+/// bounds, maximizing would show them. A placement that can't be applied leaves the window where
+/// the backend put it rather than failing after the window exists.
+pub(crate) fn build(
+    mut options: WindowOptions,
+    create: impl FnOnce(WindowOptions) -> Result<WindowId, Error>,
+) -> Result<WindowId, Error> {
+    let Some(placement) = options.placement.take() else {
+        return create(options);
+    };
+    let show = std::mem::replace(&mut options.show, false);
+    let id = create(options)?;
+    let monitor = placement.apply_bounds(id).unwrap_or_else(|err| {
+        #[cfg(debug_assertions)]
+        eprintln!("{}", err);
+        None
+    });
+    if show {
+        Window::show(id);
+        if let Err(err) = placement.apply_state(id, monitor) {
+            #[cfg(debug_assertions)]
+            eprintln!("{}", err);
+        }
+    }
+    Ok(id)
 }
 
 pub trait WindowContext
@@ -39,33 +821,455 @@ where
 {
     type Builder: WindowBuilder;
 
-    fn create(options: WindowOptions) -> Result<isize, Error>
+    fn create(options: WindowOptions) -> Result<WindowId, Error>
     where
         Self: Sized;
     fn builder() -> Box<Self::Builder>
     where
         Self: Sized;
-    fn set_theme(&mut self, theme: Theme) -> Result<(), Error>;
-    fn show(id: isize);
-    fn hide(id: isize);
-    fn minimize(id: isize);
-    fn restore(id: isize);
-    fn maximize(id: isize);
-    fn close(id: isize) -> Result<(), Error>;
+    fn show(id: WindowId);
+    fn hide(id: WindowId);
+    fn minimize(id: WindowId);
+    fn restore(id: WindowId);
+    fn maximize(id: WindowId);
+    fn close(id: WindowId) -> Result<(), Error>;
+
+    fn client_size(id: WindowId) -> Result<(u32, u32), Error>;
+    fn outer_size(id: WindowId) -> Result<(u32, u32), Error>;
+    fn position(id: WindowId) -> Result<(i32, i32), Error>;
+    fn title(id: WindowId) -> Result<String, Error>;
+    /// Check if the window is open, closed windows leave the backend's registry
+    fn exists(id: WindowId) -> bool;
+    fn is_visible(id: WindowId) -> bool;
+    fn is_minimized(id: WindowId) -> bool;
+    fn is_maximized(id: WindowId) -> bool;
+    fn is_fullscreen(id: WindowId) -> bool;
+    fn fullscreen(id: WindowId) -> Option<Fullscreen>;
+    fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error>;
+    fn scale_factor(id: WindowId) -> f64;
+    /// Get the position and client size the window has when it is neither maximized nor
+    /// fullscreen, the position is `None` when the backend can't tell
+    fn normal_bounds(id: WindowId) -> Result<Bounds, Error>;
+
+    fn set_title(id: WindowId, title: String) -> Result<(), Error>;
+    fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error>;
+    fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error>;
+    fn set_position(id: WindowId, x: i32, y: i32) -> Result<(), Error>;
+    fn center_on_monitor(id: WindowId) -> Result<(), Error>;
+
+    fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error>;
+    fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error>;
+
+    fn set_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error>;
+    fn set_small_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error>;
+
+    fn set_cursor(id: WindowId, cursor: Cursor) -> Result<(), Error>;
+    fn set_cursor_visible(id: WindowId, visible: bool) -> Result<(), Error>;
+    fn set_cursor_grab(id: WindowId, grab: CursorGrab) -> Result<(), Error>;
+    fn set_raw_mouse_motion(id: WindowId, enabled: bool) -> Result<(), Error>;
+    fn set_cursor_position(id: WindowId, x: i32, y: i32) -> Result<(), Error>;
+
+    fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error>;
+    fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error>;
+    fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error>;
+
+    fn set_level(id: WindowId, level: WindowLevel) -> Result<(), Error>;
+    fn set_tool_window(id: WindowId, tool_window: bool) -> Result<(), Error>;
+    fn set_skip_taskbar(id: WindowId, skip_taskbar: bool) -> Result<(), Error>;
+    fn set_skip_pager(id: WindowId, skip_pager: bool) -> Result<(), Error>;
+
+    fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error>;
+
+    fn raw_window_handle(id: WindowId) -> Result<RawWindowHandle, HandleError>;
+    fn raw_display_handle(id: WindowId) -> Result<RawDisplayHandle, HandleError>;
 }
 
 pub trait WindowBuilder {
     fn new() -> Self;
-    fn title(self, title: &'static str) -> Self;
+    fn title(self, title: impl Into<String>) -> Self;
     fn theme(self, theme: Theme) -> Self;
     fn background(self, background: Background) -> Self;
-    fn icon(self, icon: &'static str) -> Self;
+    /// Icon for the taskbar and task switcher, also used for the title bar without a small icon
+    fn icon(self, icon: WindowIcon) -> Self;
+    /// Icon for the title bar, the large icon is scaled down when there is none
+    fn small_icon(self, icon: WindowIcon) -> Self;
     fn fixed(self) -> Self;
+    /// Size of the area inside the window frame in logical pixels
     fn size(self, width: u32, height: u32) -> Self;
-    fn create(self) -> Result<isize, Error>
+    /// Size of the area inside the window frame in physical pixels, not scaled for the monitor
+    fn physical_size(self, width: u32, height: u32) -> Self;
+    /// Smallest client size the user can resize the window to
+    fn min_size(self, width: u32, height: u32) -> Self;
+    /// Largest client size the user can resize the window to
+    fn max_size(self, width: u32, height: u32) -> Self;
+    /// Width to height ratio the client area keeps while resizing
+    fn aspect_ratio(self, width: u32, height: u32) -> Self;
+    /// Steps the client size grows and shrinks by while resizing
+    fn resize_increments(self, width: u32, height: u32) -> Self;
+    /// Show or hide the native title bar and border
+    fn decorations(self, decorations: bool) -> Self;
+    /// Areas of a window without decorations that move, resize, minimize, maximize and close it
+    fn hit_test_regions(self, regions: HitTestRegions) -> Self;
+    /// Decide what each point of a window without decorations acts as
+    fn hit_test(
+        self,
+        hit_test: impl Fn(i32, i32, (u32, u32)) -> HitTest + Send + Sync + 'static,
+    ) -> Self;
+    /// Opacity of the whole window from `0.0` to `1.0`
+    fn opacity(self, opacity: f32) -> Self;
+    /// Use the `AA` byte of the background color as its alpha so the window can be see-through
+    /// per pixel. Colors without it are fully transparent. X11 windows need this when they are
+    /// created to get a visual with an alpha channel.
+    fn transparent(self, transparent: bool) -> Self;
+    /// Let mouse input pass through the window to whatever is below it
+    fn click_through(self, click_through: bool) -> Self;
+    /// Keep the window above or below normal windows
+    fn level(self, level: WindowLevel) -> Self;
+    /// Utility window with a small caption and no taskbar entry, for palettes and toolboxes
+    fn tool_window(self, tool_window: bool) -> Self;
+    /// Leave the window out of the taskbar
+    fn skip_taskbar(self, skip_taskbar: bool) -> Self;
+    /// Leave the window out of pagers and workspace switchers. Only X11 has these, other
+    /// platforms ignore it.
+    fn skip_pager(self, skip_pager: bool) -> Self;
+    /// Keep the window above `owner` and minimize it along with it, for inspectors and
+    /// settings panes
+    fn owner(self, owner: WindowId) -> Self;
+    /// Block input to the owner until the window is hidden or closed, needs
+    /// [`WindowBuilder::owner`]
+    fn modal(self, modal: bool) -> Self;
+    /// Cursor shown while the mouse is over the client area
+    fn cursor(self, cursor: impl Into<Cursor>) -> Self;
+    /// Send [`Event::MouseMotion`](crate::event::Event::MouseMotion) while the window has focus
+    fn raw_mouse_motion(self, enabled: bool) -> Self;
+    /// Restore a placement saved with [`WindowId::placement`], the options size is replaced by it
+    fn placement(self, placement: WindowPlacement) -> Self;
+    /// Send the events of this window to `handler` instead of the callback passed to
+    /// [`App::run`](crate::event::App::run)
+    fn handler(self, handler: impl WindowHandler + 'static) -> Self;
+    /// Force the windowing system used for this window instead of detecting it
+    fn backend(self, backend: Backend) -> Self;
+    fn create(self) -> Result<WindowId, Error>
     where
         Self: Sized;
-    fn show(self) -> Result<isize, Error>
+    fn show(self) -> Result<WindowId, Error>
     where
         Self: Sized;
 }
+
+/// Builds a [`WindowOptions`] and hands it to the backend
+#[derive(Default)]
+pub struct Builder {
+    options: WindowOptions,
+}
+
+impl WindowBuilder for Builder {
+    fn new() -> Self {
+        Builder {
+            options: WindowOptions::default(),
+        }
+    }
+
+    fn title(mut self, title: impl Into<String>) -> Self {
+        self.options.title = title.into();
+        self
+    }
+
+    fn theme(mut self, theme: Theme) -> Self {
+        self.options.theme = theme;
+        self
+    }
+
+    fn background(mut self, background: Background) -> Self {
+        self.options.background = background;
+        self
+    }
+
+    fn icon(mut self, icon: WindowIcon) -> Self {
+        self.options.icon = Some(icon);
+        self
+    }
+
+    fn small_icon(mut self, icon: WindowIcon) -> Self {
+        self.options.small_icon = Some(icon);
+        self
+    }
+
+    fn fixed(mut self) -> Self {
+        self.options.fixed_size = true;
+        self
+    }
+
+    fn size(mut self, width: u32, height: u32) -> Self {
+        self.options.size = Some(Size::Logical(LogicalSize::new(width as f64, height as f64)));
+        self
+    }
+
+    fn physical_size(mut self, width: u32, height: u32) -> Self {
+        self.options.size = Some(Size::Physical(PhysicalSize::new(width, height)));
+        self
+    }
+
+    fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.options.constraints.min_size = Some((width, height));
+        self
+    }
+
+    fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.options.constraints.max_size = Some((width, height));
+        self
+    }
+
+    fn aspect_ratio(mut self, width: u32, height: u32) -> Self {
+        self.options.constraints.aspect_ratio = Some((width, height));
+        self
+    }
+
+    fn resize_increments(mut self, width: u32, height: u32) -> Self {
+        self.options.constraints.resize_increments = Some((width, height));
+        self
+    }
+
+    fn decorations(mut self, decorations: bool) -> Self {
+        self.options.decorations = decorations;
+        self
+    }
+
+    fn hit_test_regions(mut self, regions: HitTestRegions) -> Self {
+        self.options.hit_test = Some(regions.into());
+        self
+    }
+
+    fn hit_test(
+        mut self,
+        hit_test: impl Fn(i32, i32, (u32, u32)) -> HitTest + Send + Sync + 'static,
+    ) -> Self {
+        self.options.hit_test = Some(HitTester::new(hit_test));
+        self
+    }
+
+    fn opacity(mut self, opacity: f32) -> Self {
+        self.options.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    fn transparent(mut self, transparent: bool) -> Self {
+        self.options.transparent = transparent;
+        self
+    }
+
+    fn click_through(mut self, click_through: bool) -> Self {
+        self.options.click_through = click_through;
+        self
+    }
+
+    fn level(mut self, level: WindowLevel) -> Self {
+        self.options.level = level;
+        self
+    }
+
+    fn tool_window(mut self, tool_window: bool) -> Self {
+        self.options.tool_window = tool_window;
+        self
+    }
+
+    fn skip_taskbar(mut self, skip_taskbar: bool) -> Self {
+        self.options.skip_taskbar = skip_taskbar;
+        self
+    }
+
+    fn skip_pager(mut self, skip_pager: bool) -> Self {
+        self.options.skip_pager = skip_pager;
+        self
+    }
+
+    fn owner(mut self, owner: WindowId) -> Self {
+        self.options.owner = Some(owner);
+        self
+    }
+
+    fn modal(mut self, modal: bool) -> Self {
+        self.options.modal = modal;
+        self
+    }
+
+    fn cursor(mut self, cursor: impl Into<Cursor>) -> Self {
+        self.options.cursor = cursor.into();
+        self
+    }
+
+    fn raw_mouse_motion(mut self, enabled: bool) -> Self {
+        self.options.raw_mouse_motion = enabled;
+        self
+    }
+
+    fn placement(mut self, placement: WindowPlacement) -> Self {
+        self.options.placement = Some(placement);
+        self
+    }
+
+    fn handler(mut self, handler: impl WindowHandler + 'static) -> Self {
+        self.options.handler = Some(EventHandler::new(handler));
+        self
+    }
+
+    fn backend(mut self, backend: Backend) -> Self {
+        self.options.backend = Some(backend);
+        self
+    }
+
+    fn create(self) -> Result<WindowId, Error> {
+        Window::create(self.options)
+    }
+
+    fn show(mut self) -> Result<WindowId, Error> {
+        self.options.show = true;
+        self.create()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constrain_keeps_the_aspect_ratio_within_the_max_size() {
+        let constraints = SizeConstraints {
+            max_size: Some((800, 600)),
+            aspect_ratio: Some((16, 9)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.constrain((640, 100), false), (640, 360));
+        assert_eq!(constraints.constrain((100, 270), true), (480, 270));
+        assert_eq!(constraints.constrain((1000, 1000), false), (800, 450));
+        // The height limits first with a tall ratio
+        let constraints = SizeConstraints {
+            aspect_ratio: Some((1, 2)),
+            ..constraints
+        };
+        assert_eq!(constraints.constrain((500, 100), false), (300, 600));
+    }
+
+    #[test]
+    fn constrain_snaps_to_increments_from_the_min_size() {
+        let constraints = SizeConstraints {
+            min_size: Some((100, 100)),
+            resize_increments: Some((10, 20)),
+            ..Default::default()
+        };
+        assert_eq!(constraints.constrain((137, 155), false), (130, 140));
+        assert_eq!(constraints.constrain((50, 50), false), (100, 100));
+        let from_zero = SizeConstraints {
+            resize_increments: Some((8, 16)),
+            ..Default::default()
+        };
+        assert_eq!(from_zero.constrain((100, 100), false), (96, 96));
+        assert_eq!(SizeConstraints::default().constrain((7, 9), false), (7, 9));
+    }
+
+    #[test]
+    fn fixed_size_windows_ignore_constraints() {
+        let fixed = Window::builder()
+            .backend(Backend::Headless)
+            .fixed()
+            .physical_size(300, 200)
+            .max_size(100, 100)
+            .create()
+            .unwrap();
+        let resizable = Window::builder()
+            .backend(Backend::Headless)
+            .physical_size(300, 200)
+            .max_size(100, 100)
+            .create()
+            .unwrap();
+        assert_eq!(fixed.client_size().unwrap(), (300, 200));
+        assert_eq!(resizable.client_size().unwrap(), (100, 100));
+
+        let constraints = SizeConstraints {
+            min_size: Some((400, 400)),
+            ..Default::default()
+        };
+        fixed.set_size_constraints(constraints).unwrap();
+        resizable.set_size_constraints(constraints).unwrap();
+        assert_eq!(fixed.client_size().unwrap(), (300, 200));
+        assert_eq!(resizable.client_size().unwrap(), (400, 400));
+    }
+
+    fn title_bar() -> HitTestRegions {
+        HitTestRegions::new()
+            .resize_border(6)
+            .region((0, 0, u32::MAX, 32), HitTest::Caption)
+            .region((-46, 0, 46, 32), HitTest::Close)
+    }
+
+    #[test]
+    fn hit_test_regions_resize_from_the_border() {
+        let regions = title_bar();
+        let size = (800, 600);
+        let resize = |x, y| match regions.hit_test(x, y, size) {
+            HitTest::Resize(edge) => Some(edge),
+            _ => None,
+        };
+        assert_eq!(resize(0, 0), Some(ResizeEdge::TopLeft));
+        assert_eq!(resize(799, 0), Some(ResizeEdge::TopRight));
+        assert_eq!(resize(0, 599), Some(ResizeEdge::BottomLeft));
+        assert_eq!(resize(799, 599), Some(ResizeEdge::BottomRight));
+        assert_eq!(resize(400, 5), Some(ResizeEdge::Top));
+        assert_eq!(resize(400, 594), Some(ResizeEdge::Bottom));
+        assert_eq!(resize(5, 300), Some(ResizeEdge::Left));
+        assert_eq!(resize(794, 300), Some(ResizeEdge::Right));
+        assert_eq!(resize(6, 6), None);
+        assert_eq!(
+            regions.clone().resize_border(0).hit_test(0, 0, size),
+            HitTest::Caption
+        );
+    }
+
+    #[test]
+    fn hit_test_regions_fall_through_to_the_client() {
+        let regions = title_bar();
+        let size = (800, 600);
+        assert_eq!(regions.hit_test(400, 16, size), HitTest::Caption);
+        // Counted from the right edge and over the caption it was added after
+        assert_eq!(regions.hit_test(770, 16, size), HitTest::Close);
+        assert_eq!(regions.hit_test(753, 16, size), HitTest::Caption);
+        assert_eq!(regions.hit_test(1170, 16, (1200, 600)), HitTest::Close);
+        assert_eq!(regions.hit_test(400, 32, size), HitTest::Client);
+        assert_eq!(regions.hit_test(400, 300, size), HitTest::Client);
+        assert_eq!(HitTestRegions::new().hit_test(0, 0, size), HitTest::Client);
+    }
+
+    #[test]
+    fn hit_tester_only_applies_without_decorations() {
+        let tester = HitTester::from(title_bar());
+        assert_eq!(tester.hit_test(400, 16, (800, 600)), HitTest::Caption);
+        assert_eq!(tester.hit_test(400, 300, (800, 600)), HitTest::Client);
+
+        let mut options = WindowOptions {
+            hit_test: Some(HitTester::new(|_, _, _| HitTest::Maximize)),
+            ..Default::default()
+        };
+        assert_eq!(options.hit_test(10, 10, (800, 600)), HitTest::Client);
+        options.decorations = false;
+        assert_eq!(options.hit_test(10, 10, (800, 600)), HitTest::Maximize);
+        options.hit_test = None;
+        assert_eq!(options.hit_test(10, 10, (800, 600)), HitTest::Client);
+    }
+
+    #[test]
+    fn raw_mouse_motion_follows_the_option_or_a_grab() {
+        let id = Window::builder()
+            .backend(Backend::Headless)
+            .raw_mouse_motion(true)
+            .create()
+            .unwrap();
+        assert!(crate::headless::window(id).unwrap().raw_mouse_motion);
+        id.set_raw_mouse_motion(false).unwrap();
+        assert!(!crate::headless::window(id).unwrap().raw_mouse_motion);
+
+        let mut options = WindowOptions::default();
+        assert!(!options.raw_mouse_motion());
+        options.cursor_grab = CursorGrab::Confined;
+        assert!(options.raw_mouse_motion());
+    }
+}
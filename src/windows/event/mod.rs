@@ -2,25 +2,87 @@ mod keyboard;
 mod mouse;
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::mem::transmute;
 use std::sync::Arc;
 
-use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
-use windows::Win32::Graphics::Gdi::{CreateSolidBrush, FillRect, HDC};
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BitBlt, CreateCompatibleDC, CreateDIBSection, CreateSolidBrush, DeleteDC, DeleteObject,
+    FillRect, ScreenToClient, SelectObject, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, HDC,
+    SRCCOPY,
+};
 use windows::Win32::System::Console::{FreeConsole, GetConsoleWindow};
+use windows::Win32::UI::HiDpi::{
+    AdjustWindowRectExForDpi, GetDpiForWindow, GetSystemMetricsForDpi,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{EnableWindow, GetFocus};
 use windows::Win32::UI::WindowsAndMessaging::{
     DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect, GetMessageW, GetWindowLongPtrW,
-    PostQuitMessage, SetWindowLongPtrW, ShowWindow, CREATESTRUCTW, GWLP_USERDATA, MSG, SW_HIDE,
-    WM_CLOSE, WM_CREATE, WM_DESTROY, WM_ERASEBKGND, WM_KEYDOWN, WM_KEYUP, WM_PAINT, WM_SYSKEYDOWN,
-    WM_SYSKEYUP,
+    GetWindowLongW, GetWindowRect, IsIconic, IsZoomed, PostMessageW, PostQuitMessage, SetCursor,
+    SetWindowLongPtrW, SetWindowPos, ShowWindow, CREATESTRUCTW, GWLP_USERDATA, GWL_EXSTYLE,
+    GWL_STYLE, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTCLIENT, HTCLOSE, HTLEFT,
+    HTMAXBUTTON, HTMINBUTTON, HTRIGHT, HTTOP, HTTOPLEFT, HTTOPRIGHT, HWND_BOTTOM, MINMAXINFO, MSG,
+    SC_CLOSE, SC_MAXIMIZE, SC_MINIMIZE, SC_RESTORE, SIZE_MAXIMIZED, SIZE_MINIMIZED, SM_CXFRAME,
+    SM_CXPADDEDBORDER, SM_CYFRAME, SWP_NOACTIVATE, SWP_NOZORDER, SW_HIDE, WINDOWPOS,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WMSZ_BOTTOM, WMSZ_BOTTOMLEFT, WMSZ_LEFT, WMSZ_TOP, WMSZ_TOPLEFT,
+    WMSZ_TOPRIGHT, WM_CLOSE, WM_CREATE, WM_DESTROY, WM_DPICHANGED, WM_ERASEBKGND, WM_GETMINMAXINFO,
+    WM_INPUT, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS, WM_MOVE, WM_NCCALCSIZE, WM_NCHITTEST,
+    WM_NCLBUTTONDOWN, WM_NCLBUTTONUP, WM_PAINT, WM_SETCURSOR, WM_SETFOCUS, WM_SHOWWINDOW, WM_SIZE,
+    WM_SIZING, WM_SYSCOMMAND, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_WINDOWPOSCHANGING,
 };
 
+use crate::cursor::CursorGrab;
 use crate::event::keyboard::{KeyCode, KeyEvent};
 use crate::event::mouse::MouseEvent;
-use crate::event::{keyboard as kbd, mouse as mse, Event, IntoEventResult, State};
-use crate::style::{Background, Theme};
-use crate::window::WindowOptions;
+use crate::event::{
+    exit_after_close, keyboard as kbd, mouse as mse, state_change, Event, IntoEventResult, State,
+};
+use crate::style::{premultiply, Background, Theme};
+use crate::window::{HitTest, ResizeEdge, WindowId, WindowLevel, WindowOptions};
+use crate::windows::window::{
+    attach, client_cursor, created, forget, write_cursor_clip, write_icons,
+};
 use crate::windows::{is_dark_mode, swap_rb};
+use mouse::raw_motion;
+
+/// Fill a rect with a color keeping its alpha. GDI brushes ignore alpha so this draws a
+/// premultiplied 32 bit bitmap instead, DWM blends it when blur behind is enabled.
+fn fill_alpha(hdc: HDC, rect: &RECT, color: u32) {
+    let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+    if width <= 0 || height <= 0 {
+        return;
+    }
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative for a top down bitmap
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    unsafe {
+        let memory = CreateCompatibleDC(hdc);
+        let mut bits = std::ptr::null_mut();
+        let Ok(bitmap) = CreateDIBSection(memory, &info, DIB_RGB_COLORS, &mut bits, None, 0) else {
+            DeleteDC(memory);
+            return;
+        };
+        std::slice::from_raw_parts_mut(bits as *mut u32, (width * height) as usize)
+            .fill(premultiply(color, true, 1.0));
+        let previous = SelectObject(memory, bitmap);
+        let _ = BitBlt(
+            hdc, rect.left, rect.top, width, height, memory, 0, 0, SRCCOPY,
+        );
+        SelectObject(memory, previous);
+        DeleteObject(bitmap);
+        DeleteDC(memory);
+    }
+}
 
 #[derive(Default)]
 struct Handler {
@@ -44,6 +106,8 @@ impl Handler {
 
 thread_local! {
     static HANDLER: RefCell<Handler> = RefCell::new(Handler::default());
+    /// Last `(minimized, maximized)` state seen in `WM_SIZE` for each window
+    static SHOW_STATE: RefCell<HashMap<WindowId, (bool, bool)>> = RefCell::new(HashMap::new());
 }
 
 impl From<(u32, WPARAM, LPARAM)> for KeyEvent {
@@ -80,9 +144,13 @@ fn input_message(message: u32) -> bool {
 pub fn run<R, F, T>(state: State<T>, callback: F)
 where
     R: IntoEventResult,
-    F: (Fn(isize, Event, State<T>) -> R) + 'static + Sync + Send,
+    F: (Fn(WindowId, Event, State<T>) -> R) + 'static + Sync + Send,
     T: Send + Sync + Clone + 'static,
 {
+    if crate::headless::is_active() {
+        return crate::headless::event::run(state, callback);
+    }
+
     #[cfg(feature = "hide-console")]
     {
         // Free the console
@@ -99,24 +167,97 @@ where
     HANDLER.with(move |handler| {
         handler.borrow_mut().set_handler(
             move |hwnd: HWND, message: u32, wparam: WPARAM, lparam: LPARAM| match message {
+                // Windows still being created, or destroyed because creating them failed
+                _ if !created(hwnd) => {}
                 _ if input_message(message) => {
                     unsafe { DefWindowProcW(hwnd, message, wparam, lparam) };
                     callback(
-                        hwnd.0,
+                        hwnd.into(),
                         Event::from((message, wparam, lparam)),
                         state.clone(),
                     );
                 }
                 WM_CLOSE => {
                     let result =
-                        { callback(hwnd.0, Event::Close, state.clone()).into_event_result() };
+                        { callback(hwnd.into(), Event::Close, state.clone()).into_event_result() };
                     if result {
+                        // The owner has to be enabled before the modal window goes away or
+                        // Windows activates some other app
+                        release_owner(hwnd);
                         let _ = unsafe { DestroyWindow(hwnd) };
                     }
                 }
+                // Raw input goes to the focused window, which may not have asked for it
+                WM_INPUT if options(hwnd).is_some_and(|o| o.raw_mouse_motion()) => {
+                    if let Some((dx, dy)) = raw_motion(lparam) {
+                        let (dx, dy) = (dx as f64, dy as f64);
+                        callback(hwnd.into(), Event::MouseMotion { dx, dy }, state.clone());
+                    }
+                }
                 WM_PAINT => {
                     unsafe { DefWindowProcW(hwnd, message, wparam, lparam) };
-                    callback(hwnd.0, Event::Repaint, state.clone());
+                    callback(hwnd.into(), Event::Repaint, state.clone());
+                }
+                WM_SIZE => {
+                    let id = WindowId::from(hwnd);
+                    let minimized = wparam.0 as u32 == SIZE_MINIMIZED;
+                    let maximized = wparam.0 as u32 == SIZE_MAXIMIZED;
+                    let was = SHOW_STATE
+                        .with(|states| states.borrow_mut().insert(id, (minimized, maximized)))
+                        .unwrap_or_default();
+                    if let Some(event) = state_change(was, minimized, maximized) {
+                        callback(id, event, state.clone());
+                    }
+                    // Minimized windows report a zero size
+                    if !minimized {
+                        let width = (lparam.0 & 0xffff) as u32;
+                        let height = ((lparam.0 >> 16) & 0xffff) as u32;
+                        callback(id, Event::Resized { width, height }, state.clone());
+                    }
+                }
+                WM_MOVE => {
+                    // The message has the client area position, events use the frame like
+                    // `position` does. Minimized windows are moved off screen.
+                    let mut rect = RECT::default();
+                    if !bool::from(unsafe { IsIconic(hwnd) })
+                        && unsafe { GetWindowRect(hwnd, &mut rect) }.is_ok()
+                    {
+                        callback(
+                            hwnd.into(),
+                            Event::Moved {
+                                x: rect.left,
+                                y: rect.top,
+                            },
+                            state.clone(),
+                        );
+                    }
+                }
+                WM_DPICHANGED => {
+                    // Sent before the window is moved to the rectangle Windows suggests, the
+                    // client size is what is left of it without the frame at the new DPI
+                    let dpi = (wparam.0 & 0xffff) as u32;
+                    let suggested = unsafe { *(lparam.0 as *const RECT) };
+                    let (frame_width, frame_height) = dpi_frame_size(hwnd, dpi);
+                    callback(
+                        hwnd.into(),
+                        Event::ScaleFactorChanged {
+                            scale_factor: dpi as f64 / 96.0,
+                            width: (suggested.right - suggested.left - frame_width).max(0) as u32,
+                            height: (suggested.bottom - suggested.top - frame_height).max(0) as u32,
+                        },
+                        state.clone(),
+                    );
+                }
+                WM_SETFOCUS => {
+                    callback(hwnd.into(), Event::Focused(true), state.clone());
+                }
+                WM_KILLFOCUS => {
+                    callback(hwnd.into(), Event::Focused(false), state.clone());
+                }
+                WM_DESTROY => {
+                    let id = WindowId::from(hwnd);
+                    SHOW_STATE.with(|states| states.borrow_mut().remove(&id));
+                    callback(id, Event::Destroyed, state.clone());
                 }
                 _ => {}
             },
@@ -128,6 +269,82 @@ where
     }
 }
 
+/// Get the options stored by `WM_CREATE`, `None` for messages sent before it
+pub(crate) fn options<'a>(window: HWND) -> Option<&'a WindowOptions> {
+    let user_data = unsafe { GetWindowLongPtrW(window, GWLP_USERDATA) };
+    std::ptr::NonNull::<WindowOptions>::new(user_data as _).map(|s| unsafe { s.as_ref() })
+}
+
+/// Get how much wider and taller the window is than its client area
+fn frame_size(window: HWND) -> (i32, i32) {
+    let (mut outer, mut client) = (RECT::default(), RECT::default());
+    unsafe {
+        let _ = GetWindowRect(window, &mut outer);
+        let _ = GetClientRect(window, &mut client);
+    }
+    (
+        (outer.right - outer.left) - (client.right - client.left),
+        (outer.bottom - outer.top) - (client.bottom - client.top),
+    )
+}
+
+/// Get the frame size of a window at another DPI than it currently has
+fn dpi_frame_size(window: HWND, dpi: u32) -> (i32, i32) {
+    if options(window).is_some_and(|options| !options.decorations) {
+        return (0, 0);
+    }
+    let mut rect = RECT::default();
+    unsafe {
+        let style = WINDOW_STYLE(GetWindowLongW(window, GWL_STYLE) as u32);
+        let ex_style = WINDOW_EX_STYLE(GetWindowLongW(window, GWL_EXSTYLE) as u32);
+        let _ = AdjustWindowRectExForDpi(&mut rect, style, false, ex_style, dpi);
+    }
+    (rect.right - rect.left, rect.bottom - rect.top)
+}
+
+/// Get the `WM_NCHITTEST` result for a region of a window without decorations
+fn hit_test_code(hit: HitTest) -> u32 {
+    match hit {
+        HitTest::Client => HTCLIENT,
+        HitTest::Caption => HTCAPTION,
+        HitTest::Resize(ResizeEdge::Top) => HTTOP,
+        HitTest::Resize(ResizeEdge::Bottom) => HTBOTTOM,
+        HitTest::Resize(ResizeEdge::Left) => HTLEFT,
+        HitTest::Resize(ResizeEdge::Right) => HTRIGHT,
+        HitTest::Resize(ResizeEdge::TopLeft) => HTTOPLEFT,
+        HitTest::Resize(ResizeEdge::TopRight) => HTTOPRIGHT,
+        HitTest::Resize(ResizeEdge::BottomLeft) => HTBOTTOMLEFT,
+        HitTest::Resize(ResizeEdge::BottomRight) => HTBOTTOMRIGHT,
+        HitTest::Minimize => HTMINBUTTON,
+        HitTest::Maximize => HTMAXBUTTON,
+        HitTest::Close => HTCLOSE,
+    }
+}
+
+/// Get the owner of a modal window
+fn modal_owner(window: HWND) -> Option<HWND> {
+    options(window)
+        .filter(|options| options.modal)
+        .and_then(|options| options.owner)
+        .map(HWND::from)
+}
+
+/// Give input back to the owner of a modal window
+fn release_owner(window: HWND) {
+    if let Some(owner) = modal_owner(window) {
+        unsafe { EnableWindow(owner, true) };
+    }
+}
+
+/// Check if a window was created without decorations
+fn frameless(window: HWND) -> bool {
+    options(window).is_some_and(|options| !options.decorations)
+}
+
+fn grabs_cursor(window: HWND) -> bool {
+    options(window).is_some_and(|options| options.cursor_grab != CursorGrab::None)
+}
+
 pub extern "system" fn wnd_proc(
     window: HWND,
     message: u32,
@@ -144,22 +361,175 @@ pub extern "system" fn wnd_proc(
                 unsafe {
                     SetWindowLongPtrW(window, GWLP_USERDATA, create_struct.lpCreateParams as _)
                 };
+                attach(window, create_struct.lpCreateParams as _);
                 LRESULT(0)
             }
             WM_DESTROY => {
-                unsafe { PostQuitMessage(0) };
+                release_owner(window);
+                let _ = write_icons(window, None, None);
+                let id = WindowId::from(window);
+                // Windows that failed to be created were never counted as open
+                if forget(id).is_some_and(|remaining| exit_after_close(id, remaining)) {
+                    unsafe { PostQuitMessage(0) };
+                }
+                LRESULT(0)
+            }
+            WM_SHOWWINDOW => {
+                // Modal windows disable their owner while they are shown
+                if let Some(owner) = modal_owner(window) {
+                    unsafe { EnableWindow(owner, wparam.0 == 0) };
+                }
+                unsafe { DefWindowProcW(window, message, wparam, lparam) }
+            }
+            WM_GETMINMAXINFO => {
+                let Some(options) = options(window).filter(|o| !o.fixed_size) else {
+                    return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+                };
+                // The limits are for the client area, Windows wants them for the whole window
+                let (frame_width, frame_height) = frame_size(window);
+                let outer = |(width, height): (u32, u32)| POINT {
+                    x: width as i32 + frame_width,
+                    y: height as i32 + frame_height,
+                };
+                let info = unsafe { &mut *(lparam.0 as *mut MINMAXINFO) };
+                if let Some(size) = options.constraints.min_size {
+                    info.ptMinTrackSize = outer(size);
+                }
+                if let Some(size) = options.constraints.max_size {
+                    info.ptMaxTrackSize = outer(size);
+                }
+                LRESULT(0)
+            }
+            WM_SIZING => {
+                let Some(options) = options(window).filter(|o| !o.fixed_size) else {
+                    return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+                };
+                let edge = wparam.0 as u32;
+                let (frame_width, frame_height) = frame_size(window);
+                let rect = unsafe { &mut *(lparam.0 as *mut RECT) };
+                let client = (
+                    (rect.right - rect.left - frame_width).max(0) as u32,
+                    (rect.bottom - rect.top - frame_height).max(0) as u32,
+                );
+
+                // Dragging only the top or bottom edge changes the height, the width follows it
+                let height_driven = edge == WMSZ_TOP || edge == WMSZ_BOTTOM;
+                let (width, height) = options.constraints.constrain(client, height_driven);
+                let (width, height) = (width as i32 + frame_width, height as i32 + frame_height);
+
+                // Keep the edges the user isn't dragging in place
+                if edge == WMSZ_LEFT || edge == WMSZ_TOPLEFT || edge == WMSZ_BOTTOMLEFT {
+                    rect.left = rect.right - width;
+                } else {
+                    rect.right = rect.left + width;
+                }
+                if edge == WMSZ_TOP || edge == WMSZ_TOPLEFT || edge == WMSZ_TOPRIGHT {
+                    rect.top = rect.bottom - height;
+                } else {
+                    rect.bottom = rect.top + height;
+                }
+                LRESULT(1)
+            }
+            WM_WINDOWPOSCHANGING
+                if options(window)
+                    .is_some_and(|options| options.level == WindowLevel::AlwaysOnBottom) =>
+            {
+                // Send the window back down whenever it would be raised
+                let pos = unsafe { &mut *(lparam.0 as *mut WINDOWPOS) };
+                pos.hwndInsertAfter = HWND_BOTTOM;
+                unsafe { DefWindowProcW(window, message, wparam, lparam) }
+            }
+            WM_DPICHANGED => {
+                // Moving to the suggested rectangle keeps the window the same logical size
+                let rect = unsafe { *(lparam.0 as *const RECT) };
+                let _ = unsafe {
+                    SetWindowPos(
+                        window,
+                        None,
+                        rect.left,
+                        rect.top,
+                        rect.right - rect.left,
+                        rect.bottom - rect.top,
+                        SWP_NOZORDER | SWP_NOACTIVATE,
+                    )
+                };
+                LRESULT(0)
+            }
+            WM_NCCALCSIZE if frameless(window) => {
+                // Returning the window rectangle unchanged makes all of it client area. The frame
+                // styles stay so the window still snaps, animates and has a shadow.
+                if wparam.0 != 0 && bool::from(unsafe { IsZoomed(window) }) {
+                    // Maximized windows hang over the monitor edges by the resize border
+                    let rect = unsafe { &mut *(lparam.0 as *mut RECT) };
+                    let dpi = unsafe { GetDpiForWindow(window) };
+                    let padding = unsafe { GetSystemMetricsForDpi(SM_CXPADDEDBORDER, dpi) };
+                    let x = unsafe { GetSystemMetricsForDpi(SM_CXFRAME, dpi) } + padding;
+                    let y = unsafe { GetSystemMetricsForDpi(SM_CYFRAME, dpi) } + padding;
+                    rect.left += x;
+                    rect.right -= x;
+                    rect.top += y;
+                    rect.bottom -= y;
+                }
+                LRESULT(0)
+            }
+            // The pointer clip follows the client area and only holds while the window has focus
+            WM_SETFOCUS | WM_KILLFOCUS | WM_SIZE | WM_MOVE if grabs_cursor(window) => {
+                let focused = match message {
+                    WM_SETFOCUS => true,
+                    WM_KILLFOCUS => false,
+                    _ => window == unsafe { GetFocus() },
+                };
+                let _ = write_cursor_clip(window, focused);
+                unsafe { DefWindowProcW(window, message, wparam, lparam) }
+            }
+            // The client area shows the window's own cursor instead of the class cursor
+            WM_SETCURSOR if (lparam.0 & 0xffff) as u32 == HTCLIENT => match client_cursor(window) {
+                Some(cursor) => {
+                    unsafe { SetCursor(cursor) };
+                    LRESULT(1)
+                }
+                None => unsafe { DefWindowProcW(window, message, wparam, lparam) },
+            },
+            WM_NCHITTEST if frameless(window) => {
+                let options = options(window).unwrap();
+                let mut point = POINT {
+                    x: (lparam.0 & 0xffff) as i16 as i32,
+                    y: ((lparam.0 >> 16) & 0xffff) as i16 as i32,
+                };
+                let mut client = RECT::default();
+                unsafe {
+                    ScreenToClient(window, &mut point);
+                    let _ = GetClientRect(window, &mut client);
+                }
+                let size = (client.right as u32, client.bottom as u32);
+                LRESULT(hit_test_code(options.hit_test(point.x, point.y, size)) as isize)
+            }
+            // The default handling draws the old style buttons over the window, they are
+            // pressed on release instead
+            WM_NCLBUTTONDOWN
+                if frameless(window)
+                    && [HTMINBUTTON, HTMAXBUTTON, HTCLOSE].contains(&(wparam.0 as u32)) =>
+            {
+                LRESULT(0)
+            }
+            WM_NCLBUTTONUP if frameless(window) => {
+                let command = match wparam.0 as u32 {
+                    HTMINBUTTON => SC_MINIMIZE,
+                    HTMAXBUTTON if bool::from(unsafe { IsZoomed(window) }) => SC_RESTORE,
+                    HTMAXBUTTON => SC_MAXIMIZE,
+                    HTCLOSE => SC_CLOSE,
+                    _ => return unsafe { DefWindowProcW(window, message, wparam, lparam) },
+                };
+                let _ = unsafe {
+                    PostMessageW(window, WM_SYSCOMMAND, WPARAM(command as usize), LPARAM(0))
+                };
                 LRESULT(0)
             }
             WM_ERASEBKGND => {
                 // Auto fill background with window theme color
-                let user_data = unsafe { GetWindowLongPtrW(window, GWLP_USERDATA) };
-                let sample = std::ptr::NonNull::<WindowOptions>::new(user_data as _);
-                let (theme, background) =
-                    sample.map_or((Theme::default(), Background::default()), |s| {
-                        (
-                            unsafe { s.as_ref() }.theme.clone(),
-                            unsafe { s.as_ref() }.background.clone(),
-                        )
+                let (theme, background) = options(window)
+                    .map_or((Theme::default(), Background::default()), |options| {
+                        (options.theme.clone(), options.background.clone())
                     });
 
                 let mut rect = RECT::default();
@@ -171,8 +541,16 @@ pub extern "system" fn wnd_proc(
                     Theme::Auto => background.color(is_dark_mode().into()),
                 };
 
-                let brush = unsafe { CreateSolidBrush(COLORREF(swap_rb(color))) };
-                unsafe { FillRect(HDC(wparam.0 as isize), &rect, brush) };
+                let hdc = HDC(wparam.0 as isize);
+                if options(window).is_some_and(|options| options.transparent) {
+                    fill_alpha(hdc, &rect, color);
+                } else {
+                    let brush = unsafe { CreateSolidBrush(COLORREF(swap_rb(color))) };
+                    unsafe {
+                        FillRect(hdc, &rect, brush);
+                        DeleteObject(brush);
+                    }
+                }
                 LRESULT(0)
             }
             _ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
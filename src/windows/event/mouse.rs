@@ -1,13 +1,45 @@
+use std::ffi::c_void;
+use std::mem::size_of;
+
 use crate::event::mouse::{MouseButton, MouseEvent, MouseEventType};
 use crate::windows::{get_wheel_delta_wparam, hiword, loword};
+use windows::Win32::Devices::HumanInterfaceDevice::MOUSE_MOVE_ABSOLUTE;
 use windows::Win32::Foundation::{LPARAM, WPARAM};
 use windows::Win32::UI::Controls::{WM_MOUSEHOVER, WM_MOUSELEAVE};
+use windows::Win32::UI::Input::{
+    GetRawInputData, HRAWINPUT, RAWINPUT, RAWINPUTHEADER, RID_INPUT, RIM_TYPEMOUSE,
+};
 use windows::Win32::UI::WindowsAndMessaging::{
     WM_CAPTURECHANGED, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDBLCLK,
     WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDBLCLK,
     WM_RBUTTONDOWN, WM_RBUTTONUP, WM_XBUTTONDBLCLK, WM_XBUTTONDOWN, WM_XBUTTONUP,
 };
 
+/// Get the relative movement in a `WM_INPUT` message, `None` for other devices, absolute
+/// positions and button or wheel input without movement
+pub fn raw_motion(lparam: LPARAM) -> Option<(i32, i32)> {
+    let mut input = RAWINPUT::default();
+    let mut size = size_of::<RAWINPUT>() as u32;
+    let read = unsafe {
+        GetRawInputData(
+            HRAWINPUT(lparam.0),
+            RID_INPUT,
+            Some(&mut input as *mut RAWINPUT as *mut c_void),
+            &mut size,
+            size_of::<RAWINPUTHEADER>() as u32,
+        )
+    };
+    if read == u32::MAX || input.header.dwType != RIM_TYPEMOUSE.0 {
+        return None;
+    }
+    let mouse = unsafe { input.data.mouse };
+    // Tablets and remote desktop sessions send positions instead of movement
+    if mouse.usFlags as u32 & MOUSE_MOVE_ABSOLUTE != 0 || (mouse.lLastX, mouse.lLastY) == (0, 0) {
+        return None;
+    }
+    Some((mouse.lLastX, mouse.lLastY))
+}
+
 impl MouseEvent {
     pub fn message(m: u32) -> bool {
         match m {
@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::window::WindowId;
 use windows::core::{HSTRING, PCSTR, PCWSTR};
 use windows::Win32::Foundation::{BOOL, HWND};
 use windows::Win32::UI::Controls::Dialogs::COMMON_DLG_ERRORS;
@@ -7,12 +8,12 @@ use windows::Win32::UI::Controls::Dialogs::{
     CDERR_LOADSTRFAILURE, CDERR_LOCKRESFAILURE, CDERR_MEMALLOCFAILURE, CDERR_MEMLOCKFAILURE,
     CDERR_NOHINSTANCE, CDERR_NOHOOK, CDERR_NOTEMPLATE, CDERR_REGISTERMSGFAIL, CDERR_STRUCTSIZE,
 };
-use windows::Win32::UI::WindowsAndMessaging::IsZoomed;
 use windows::UI::ViewManagement::{UIColorType, UISettings};
 use windows::Win32::System::Com::{COINIT_APARTMENTTHREADED, CoInitializeEx, CoUninitialize};
 
 pub mod event;
 pub mod modal;
+pub mod monitor;
 pub mod window;
 
 thread_local! {
@@ -54,6 +55,18 @@ impl From<COMMON_DLG_ERRORS> for Error {
     }
 }
 
+impl From<WindowId> for HWND {
+    fn from(id: WindowId) -> Self {
+        HWND(id.0)
+    }
+}
+
+impl From<HWND> for WindowId {
+    fn from(hwnd: HWND) -> Self {
+        WindowId(hwnd.0)
+    }
+}
+
 pub trait IntoPCSTR {
     fn as_pcstr(&self) -> PCSTR;
 }
@@ -107,11 +120,6 @@ pub(crate) fn swap_rb(value: u32) -> u32 {
     u32::from_be_bytes([values[0], values[3], values[2], values[1]])
 }
 
-/// Check if a window is maxamized
-pub fn is_maxamized(id: isize) -> bool {
-    unsafe { IsZoomed(HWND(id)) }.into()
-}
-
 pub(crate) fn co_initialize() {
     unsafe {
         CoInitializeEx(None, COINIT_APARTMENTTHREADED);
@@ -0,0 +1,169 @@
+use std::mem::size_of;
+
+use windows::core::{HSTRING, PCWSTR};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, POINT, RECT};
+use windows::Win32::Graphics::Gdi::{
+    ChangeDisplaySettingsExW, EnumDisplayMonitors, EnumDisplaySettingsW, GetMonitorInfoW,
+    MonitorFromPoint, MonitorFromWindow, CDS_FULLSCREEN, CDS_TYPE, DEVMODEW,
+    DISP_CHANGE_SUCCESSFUL, DM_BITSPERPEL, DM_DISPLAYFREQUENCY, DM_PELSHEIGHT, DM_PELSWIDTH,
+    ENUM_CURRENT_SETTINGS, ENUM_DISPLAY_SETTINGS_MODE, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+    MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::{IsWindow, MONITORINFOF_PRIMARY};
+
+use crate::error::Error;
+use crate::headless;
+use crate::monitor::{Monitor, VideoMode};
+use crate::window::WindowId;
+
+/// Read everything about a monitor handle
+fn monitor(handle: HMONITOR) -> Result<Monitor, Error> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+    if !bool::from(unsafe { GetMonitorInfoW(handle, &mut info as *mut _ as *mut MONITORINFO) }) {
+        return Err(Error::from(windows::core::Error::from_win32()));
+    }
+    let bounds = info.monitorInfo.rcMonitor;
+    let work = info.monitorInfo.rcWork;
+    let device = &info.szDevice[..info.szDevice.iter().position(|c| *c == 0).unwrap_or(32)];
+
+    let mut mode = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    let refresh_rate = unsafe {
+        EnumDisplaySettingsW(
+            PCWSTR(info.szDevice.as_ptr()),
+            ENUM_CURRENT_SETTINGS,
+            &mut mode,
+        )
+    }
+    .as_bool()
+    // 0 and 1 mean the hardware default rate
+    .then_some(mode.dmDisplayFrequency)
+    .filter(|hz| *hz > 1)
+    .map(|hz| hz * 1000);
+
+    let (mut dpi_x, mut dpi_y) = (96, 96);
+    let _ = unsafe { GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) };
+
+    Ok(Monitor {
+        handle: handle.0,
+        name: String::from_utf16_lossy(device),
+        position: (bounds.left, bounds.top),
+        size: (
+            (bounds.right - bounds.left) as u32,
+            (bounds.bottom - bounds.top) as u32,
+        ),
+        work_area: (
+            work.left,
+            work.top,
+            (work.right - work.left) as u32,
+            (work.bottom - work.top) as u32,
+        ),
+        refresh_rate,
+        scale_factor: dpi_x as f64 / 96.0,
+        primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+    })
+}
+
+unsafe extern "system" fn collect(handle: HMONITOR, _: HDC, _: *mut RECT, handles: LPARAM) -> BOOL {
+    (*(handles.0 as *mut Vec<HMONITOR>)).push(handle);
+    BOOL(1)
+}
+
+pub fn all() -> Result<Vec<Monitor>, Error> {
+    if headless::is_active() {
+        return headless::monitor::all();
+    }
+    let mut handles: Vec<HMONITOR> = Vec::new();
+    unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(collect),
+            LPARAM(&mut handles as *mut _ as isize),
+        );
+    }
+    handles.into_iter().map(monitor).collect()
+}
+
+pub fn primary() -> Result<Monitor, Error> {
+    if headless::is_active() {
+        return headless::monitor::primary();
+    }
+    // The primary monitor always has the origin of the virtual screen in its top left corner
+    monitor(unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) })
+}
+
+pub fn from_window(id: WindowId) -> Result<Monitor, Error> {
+    if headless::is_active() {
+        return headless::monitor::from_window(id);
+    }
+    let hwnd = HWND::from(id);
+    if !bool::from(unsafe { IsWindow(hwnd) }) {
+        return Err(Error::invalid_window(id));
+    }
+    monitor(unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) })
+}
+
+/// List every mode the display driver reports for the monitor's device
+pub fn video_modes(monitor: &Monitor) -> Result<Vec<VideoMode>, Error> {
+    if headless::is_active() {
+        return headless::monitor::video_modes(monitor);
+    }
+    let device = HSTRING::from(monitor.name.as_str());
+    let mut mode = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    let mut modes = Vec::new();
+    let mut index = 0;
+    while unsafe { EnumDisplaySettingsW(&device, ENUM_DISPLAY_SETTINGS_MODE(index), &mut mode) }
+        .as_bool()
+    {
+        modes.push(VideoMode {
+            size: (mode.dmPelsWidth, mode.dmPelsHeight),
+            bit_depth: mode.dmBitsPerPel as u16,
+            refresh_rate: Some(mode.dmDisplayFrequency)
+                .filter(|hz| *hz > 1)
+                .map(|hz| hz * 1000),
+            monitor: monitor.clone(),
+        });
+        index += 1;
+    }
+    Ok(modes)
+}
+
+/// Switch the monitor of a mode to it until [`reset_video_mode`], returns the monitor's device
+pub(crate) fn set_video_mode(mode: &VideoMode) -> Result<HSTRING, Error> {
+    let device = HSTRING::from(mode.monitor().name.as_str());
+    let mut settings = DEVMODEW {
+        dmSize: size_of::<DEVMODEW>() as u16,
+        dmFields: DM_PELSWIDTH | DM_PELSHEIGHT | DM_BITSPERPEL,
+        dmPelsWidth: mode.size.0,
+        dmPelsHeight: mode.size.1,
+        dmBitsPerPel: mode.bit_depth as u32,
+        ..Default::default()
+    };
+    if let Some(refresh_rate) = mode.refresh_rate {
+        settings.dmFields |= DM_DISPLAYFREQUENCY;
+        settings.dmDisplayFrequency = refresh_rate / 1000;
+    }
+    // Fullscreen changes are temporary and never written to the registry
+    let result =
+        unsafe { ChangeDisplaySettingsExW(&device, Some(&settings), None, CDS_FULLSCREEN, None) };
+    if result != DISP_CHANGE_SUCCESSFUL {
+        return Err(Error::invalid_parameter(format!(
+            "Changing the video mode failed with {}",
+            result.0
+        )));
+    }
+    Ok(device)
+}
+
+/// Put a device back to the mode stored in the registry
+pub(crate) fn reset_video_mode(device: &HSTRING) {
+    let _ = unsafe { ChangeDisplaySettingsExW(device, None, None, CDS_TYPE(0), None) };
+}
@@ -1,48 +1,122 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::ffi::c_void;
 use std::mem::size_of;
-use std::ops::DerefMut;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::num::NonZeroIsize;
+use std::sync::{Mutex, Once};
 
-use windows::core::HSTRING;
+use raw_window_handle::{
+    HandleError, RawDisplayHandle, RawWindowHandle, Win32WindowHandle, WindowsDisplayHandle,
+};
+use windows::core::{HSTRING, PCWSTR};
 use windows::Foundation::{EventRegistrationToken, TypedEventHandler};
-use windows::Win32::Foundation::{BOOL, HANDLE, HMODULE, HWND, LPARAM, WPARAM};
-use windows::Win32::Graphics::Dwm::{DwmSetWindowAttribute, DWMWINDOWATTRIBUTE};
+use windows::Win32::Foundation::{BOOL, COLORREF, HWND, LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::Graphics::Dwm::{
+    DwmEnableBlurBehindWindow, DwmSetWindowAttribute, DWMWINDOWATTRIBUTE, DWM_BB_BLURREGION,
+    DWM_BB_ENABLE, DWM_BLURBEHIND,
+};
 use windows::Win32::Graphics::Gdi::{
-    GetDC, GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTOPRIMARY,
+    ClientToScreen, CreateBitmap, CreateDIBSection, CreateRectRgn, DeleteObject, GetDC,
+    GetMonitorInfoW, InvalidateRect, MonitorFromWindow, ReleaseDC, SetStretchBltMode,
+    StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, COLORONCOLOR, DIB_RGB_COLORS, HDC, HMONITOR,
+    MONITORINFO, MONITOR_DEFAULTTONEAREST, SRCCOPY,
 };
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::HiDpi::{
+    AdjustWindowRectExForDpi, GetDpiForSystem, GetDpiForWindow, GetSystemMetricsForDpi,
+    SetProcessDpiAwareness, SetProcessDpiAwarenessContext,
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, PROCESS_PER_MONITOR_DPI_AWARE,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::GetFocus;
+use windows::Win32::UI::Input::{RegisterRawInputDevices, RAWINPUTDEVICE, RIDEV_REMOVE};
+use windows::Win32::UI::Shell::{ITaskbarList, TaskbarList};
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallWindowProcW, CloseWindow, CreateWindowExW, GetWindowLongW, GetWindowPlacement, LoadCursorW,
-    LoadImageW, RegisterClassW, SetWindowLongW, SetWindowPlacement, SetWindowPos, ShowWindow,
-    CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, GWL_STYLE, HICON, HWND_TOP, IDC_ARROW, IMAGE_ICON,
-    LR_DEFAULTSIZE, LR_LOADFROMFILE, LR_LOADTRANSPARENT, LR_SHARED, SWP_FRAMECHANGED, SWP_NOMOVE,
-    SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER, SW_HIDE, SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE,
-    SW_SHOWNORMAL, WINDOWPLACEMENT, WINDOW_EX_STYLE, WINDOW_STYLE, WM_ERASEBKGND, WM_PAINT,
-    WNDCLASSW, WS_CAPTION, WS_MINIMIZEBOX, WS_OVERLAPPED, WS_OVERLAPPEDWINDOW, WS_SYSMENU,
+    CallWindowProcW, ClipCursor, CreateIconIndirect, CreateWindowExW, DestroyCursor, DestroyIcon,
+    DestroyWindow, GetClientRect, GetCursorPos, GetWindowLongPtrW, GetWindowLongW,
+    GetWindowPlacement, GetWindowRect, GetWindowTextLengthW, GetWindowTextW, IsIconic, IsWindow,
+    IsWindowVisible, IsZoomed, LoadCursorW, RegisterClassW, SendMessageW, SetCursorPos,
+    SetLayeredWindowAttributes, SetWindowLongPtrW, SetWindowLongW, SetWindowPlacement,
+    SetWindowPos, SetWindowTextW, ShowWindow, WindowFromPoint, CS_HREDRAW, CS_VREDRAW,
+    CW_USEDEFAULT, GWLP_HINSTANCE, GWLP_USERDATA, GWL_EXSTYLE, GWL_STYLE, HCURSOR, HICON,
+    HWND_BOTTOM, HWND_NOTOPMOST, HWND_TOP, HWND_TOPMOST, ICONINFO, ICON_BIG, ICON_SMALL,
+    IDC_APPSTARTING, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP, IDC_IBEAM, IDC_NO, IDC_SIZEALL,
+    IDC_SIZENESW, IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE, IDC_WAIT, LWA_ALPHA, SM_CXICON,
+    SM_CXSMICON, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOOWNERZORDER, SWP_NOSIZE,
+    SWP_NOZORDER, SW_HIDE, SW_MAXIMIZE, SW_MINIMIZE, SW_RESTORE, SW_SHOWNA, SW_SHOWNORMAL,
+    WINDOWPLACEMENT, WINDOW_EX_STYLE, WINDOW_STYLE, WM_ERASEBKGND, WM_MOUSEMOVE, WM_NCHITTEST,
+    WM_PAINT, WM_SETCURSOR, WM_SETICON, WNDCLASSW, WS_CAPTION, WS_EX_LAYERED, WS_EX_TOOLWINDOW,
+    WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_MINIMIZEBOX, WS_OVERLAPPED, WS_OVERLAPPEDWINDOW,
+    WS_SYSMENU,
 };
 use windows::UI::ViewManagement::UISettings;
 
+use crate::cursor::{Cursor, CursorGrab, CursorIcon};
 use crate::e;
 use crate::error::Error;
-use crate::style::{Background, Theme};
-use crate::window::{WindowBuilder, WindowContext, WindowOptions};
+use crate::event::{self, set_handler};
+use crate::headless;
+use crate::icon::{IconImage, WindowIcon};
+use crate::style::Theme;
+use crate::window::{
+    build, select_backend, Backend, Bounds, Builder, Fullscreen, SizeConstraints, WindowBuilder,
+    WindowContext, WindowId, WindowLevel, WindowOptions,
+};
 
-use super::{event::wnd_proc, is_dark_mode, IntoPCWSTR, UI_SETTINGS};
+use super::event::{options, wnd_proc};
+use super::monitor::{reset_video_mode, set_video_mode};
+use super::{co_initialize, co_uninitialize, is_dark_mode, IntoPCWSTR, UI_SETTINGS};
 
 thread_local! {
-    static WINDOWS: Mutex<Vec<Window>> = Mutex::new(Vec::new())
+    static WINDOWS: Mutex<Vec<Window>> = Mutex::new(Vec::new());
+    /// Cursor of each window's client area and whether the window created it. Kept out of
+    /// `WINDOWS` since `WM_SETCURSOR` can arrive while that is locked.
+    static CURSORS: RefCell<HashMap<WindowId, (HCURSOR, bool)>> = RefCell::new(HashMap::new());
+    /// Raw mouse input is registered, see [`update_raw_input`]
+    static RAW_INPUT: Cell<bool> = const { Cell::new(false) };
 }
 
-/// Set the fullscreen state of a window
-pub fn toggle_fullscreen(id: isize) {
-    WINDOWS.with(|windows| {
-        let mut windows = windows.lock().unwrap();
-        let windows = windows.deref_mut();
-        if let Some(window) = windows.iter_mut().find(|w| w.handle.0 == id) {
-            window.fullscreen();
+/// HID usage of mice, raw input is registered for it
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_MOUSE: u16 = 0x02;
+
+static DPI_AWARENESS: Once = Once::new();
+
+/// Opt in to per monitor DPI so Windows doesn't stretch the windows bitmap on high DPI
+/// monitors. The awareness context needs Windows 10 1703, older versions get the Windows 8.1
+/// per monitor mode. Fails when the manifest already set one, which is fine.
+fn enable_dpi_awareness() {
+    unsafe {
+        if SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).is_err() {
+            let _ = SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE);
         }
-    })
+    }
+}
+
+/// Drop a destroyed window from the registry and destroy its custom cursor, returns how many
+/// windows are left or `None` when the window wasn't registered
+pub(crate) fn forget(id: WindowId) -> Option<usize> {
+    if let Some((cursor, true)) = CURSORS.with(|cursors| cursors.borrow_mut().remove(&id)) {
+        let _ = unsafe { DestroyCursor(cursor) };
+    }
+    let (window, remaining) = WINDOWS.with(|windows| {
+        let mut windows = windows.lock().unwrap();
+        let window = windows
+            .iter()
+            .position(|window| WindowId::from(window.handle) == id)
+            .map(|index| windows.remove(index));
+        (window, windows.len())
+    });
+    let window = window?;
+    if let Some(device) = &window.display_mode {
+        reset_video_mode(device);
+    }
+    if window.options.raw_mouse_motion() {
+        update_raw_input();
+    }
+    // `WM_DESTROY` reached the app callback before this
+    event::forget(id);
+    Some(remaining)
 }
 
 macro_rules! boxed_unwrap {
@@ -54,79 +128,577 @@ macro_rules! boxed_unwrap {
     };
 }
 
-#[derive(Default)]
-pub struct Builder {
-    options: WindowOptions,
+trait ToStyle {
+    fn to_style(&self) -> WINDOW_STYLE;
+    fn to_ex_style(&self) -> WINDOW_EX_STYLE;
 }
 
-impl WindowBuilder for Builder {
-    fn new() -> Self {
-        Builder {
-            options: WindowOptions::default(),
+impl ToStyle for WindowOptions {
+    fn to_style(&self) -> WINDOW_STYLE {
+        if self.fixed_size {
+            WS_OVERLAPPED | WS_MINIMIZEBOX | WS_SYSMENU | WS_CAPTION
+        } else {
+            WS_OVERLAPPEDWINDOW
         }
     }
 
-    fn title(mut self, title: &'static str) -> Self {
-        self.options.title = title;
-        self
+    fn to_ex_style(&self) -> WINDOW_EX_STYLE {
+        let mut style = layered_style(self.opacity, self.click_through);
+        if self.level == WindowLevel::AlwaysOnTop {
+            style |= WS_EX_TOPMOST;
+        }
+        if self.tool_window {
+            style |= WS_EX_TOOLWINDOW;
+        }
+        style
     }
+}
 
-    fn theme(mut self, theme: Theme) -> Self {
-        self.options.theme = theme;
-        self
+/// Add or remove the taskbar button of a window. The taskbar adds buttons when windows are
+/// shown, so this has to run again after every show.
+fn set_taskbar_tab(handle: HWND, visible: bool) -> Result<(), Error> {
+    co_initialize();
+    let result = unsafe {
+        CoCreateInstance::<_, ITaskbarList>(&TaskbarList, None, CLSCTX_INPROC_SERVER).and_then(
+            |taskbar| {
+                taskbar.HrInit()?;
+                match visible {
+                    true => taskbar.AddTab(handle),
+                    false => taskbar.DeleteTab(handle),
+                }
+            },
+        )
+    };
+    co_uninitialize();
+    Ok(result?)
+}
+
+/// Remove the taskbar button of a shown window that skips the taskbar. Tool windows never get
+/// one.
+fn update_taskbar_tab(handle: HWND) -> Result<(), Error> {
+    match options(handle) {
+        Some(options) if options.skip_taskbar && !options.tool_window => {
+            set_taskbar_tab(handle, false)
+        }
+        _ => Ok(()),
     }
+}
 
-    fn background(mut self, background: Background) -> Self {
-        self.options.background = background;
-        self
+/// Get the window a `SetWindowPos` call puts the window behind for a level
+fn insert_after(level: WindowLevel) -> HWND {
+    match level {
+        WindowLevel::AlwaysOnTop => HWND_TOPMOST,
+        WindowLevel::Normal => HWND_NOTOPMOST,
+        WindowLevel::AlwaysOnBottom => HWND_BOTTOM,
     }
+}
+
+/// Change the registry entry of a window, the registry lock is released before the result is
+/// used since changing the window sends messages to the window procedure
+fn update_window<R>(id: WindowId, change: impl FnOnce(&mut Window) -> R) -> Result<R, Error> {
+    WINDOWS.with(|windows| {
+        windows
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|window| WindowId::from(window.handle) == id)
+            .map(change)
+            .ok_or_else(|| Error::invalid_window(id))
+    })
+}
 
-    fn icon(mut self, icon: &'static str) -> Self {
-        self.options.icon = Some(icon);
-        self
+/// Change the stored options of a window, see [`update_window`]
+fn update_options<R>(
+    id: WindowId,
+    change: impl FnOnce(&mut WindowOptions) -> R,
+) -> Result<R, Error> {
+    update_window(id, |window| change(&mut window.options))
+}
+
+/// Follow the theme on the title bar, `Theme::Auto` registers for system color changes
+fn apply_theme(
+    handle: HWND,
+    theme: Theme,
+    theme_cookie: &mut Option<EventRegistrationToken>,
+) -> Result<(), Error> {
+    // Stop following the system before switching, `Theme::Auto` registers again
+    if let Some(cookie) = theme_cookie.take() {
+        boxed_unwrap!(UI_SETTINGS.with(|ui_settings| ui_settings.RemoveColorValuesChanged(cookie)));
     }
+    let state = match theme {
+        Theme::Light => BOOL(0),
+        Theme::Dark => BOOL(1),
+        Theme::Auto => {
+            *theme_cookie = Some(UI_SETTINGS.with(|ui_settings| {
+                ui_settings.ColorValuesChanged(&TypedEventHandler::new(
+                    move |settings: &Option<UISettings>, _| {
+                        if settings.is_some() {
+                            unsafe {
+                                DwmSetWindowAttribute(
+                                    handle,
+                                    DWMWINDOWATTRIBUTE(20),
+                                    &is_dark_mode() as *const _ as *const _,
+                                    4,
+                                )
+                                .unwrap();
+                                CallWindowProcW(
+                                    Some(wnd_proc),
+                                    handle,
+                                    WM_ERASEBKGND,
+                                    WPARAM(GetDC(handle).0 as usize),
+                                    LPARAM(0),
+                                );
+                                CallWindowProcW(
+                                    Some(wnd_proc),
+                                    handle,
+                                    WM_PAINT,
+                                    WPARAM(0),
+                                    LPARAM(0),
+                                );
+                            }
+                        }
+                        Ok(())
+                    },
+                ))
+            })?);
+
+            is_dark_mode()
+        }
+    };
 
-    fn fixed(mut self) -> Self {
-        self.options.fixed_size = true;
-        self
+    unsafe {
+        DwmSetWindowAttribute(
+            handle,
+            DWMWINDOWATTRIBUTE(20),
+            &state as *const _ as *const c_void,
+            4,
+        )?;
     }
 
-    fn size(mut self, width: u32, height: u32) -> Self {
-        self.options.size = Some((width, height));
-        self
+    Ok(())
+}
+
+/// Give the registry entry holding `options` its handle and set the window's event handler,
+/// called from `WM_CREATE` so the window can be found while it is still being created
+pub(crate) fn attach(handle: HWND, options: *const WindowOptions) {
+    let handler = WINDOWS.with(|windows| {
+        let mut windows = windows.lock().unwrap();
+        let window = windows
+            .iter_mut()
+            .find(|window| std::ptr::eq(&*window.options, options))?;
+        window.handle = handle;
+        window.options.handler.clone()
+    });
+    set_handler(WindowId::from(handle), handler.as_ref());
+}
+
+/// Register raw mouse input while a window reports raw mouse motion and remove it once none
+/// does, every mouse report is a `WM_INPUT` message while it is registered. Without a target
+/// window raw input goes to the window with keyboard focus.
+fn update_raw_input() {
+    let wanted = WINDOWS.with(|windows| {
+        let windows = windows.lock().unwrap();
+        windows
+            .iter()
+            .any(|window| window.options.raw_mouse_motion())
+    });
+    if RAW_INPUT.get() == wanted {
+        return;
     }
+    let mouse = RAWINPUTDEVICE {
+        usUsagePage: HID_USAGE_PAGE_GENERIC,
+        usUsage: HID_USAGE_GENERIC_MOUSE,
+        dwFlags: if wanted {
+            Default::default()
+        } else {
+            RIDEV_REMOVE
+        },
+        hwndTarget: HWND(0),
+    };
+    match unsafe { RegisterRawInputDevices(&[mouse], size_of::<RAWINPUTDEVICE>() as u32) } {
+        Ok(()) => RAW_INPUT.set(wanted),
+        Err(err) => {
+            #[cfg(debug_assertions)]
+            eprintln!("{:?}", err);
+        }
+    }
+}
 
-    fn create(self) -> Result<isize, Error> {
-        Window::create(self.options)
+/// Get the area a monitor covers on the virtual screen
+fn monitor_rect(monitor: HMONITOR) -> Result<RECT, Error> {
+    let mut info = MONITORINFO {
+        cbSize: size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if !bool::from(unsafe { GetMonitorInfoW(monitor, &mut info) }) {
+        return Err(Error::invalid_parameter("Monitor is no longer connected"));
     }
+    Ok(info.rcMonitor)
+}
 
-    fn show(mut self) -> Result<isize, Error> {
-        self.options.show = true;
-        self.create()
+/// Cover `monitor` with the window, or give it back its frame and placement for `None`. Video
+/// modes are changed by the caller.
+fn apply_fullscreen(
+    id: WindowId,
+    monitor: Option<HMONITOR>,
+    was_fullscreen: bool,
+    frame: i32,
+) -> Result<(), Error> {
+    let hwnd = HWND::from(id);
+    let style = unsafe { GetWindowLongW(hwnd, GWL_STYLE) };
+    match monitor {
+        Some(monitor) => {
+            // Read after the mode change, the monitor has its new size
+            let rect = monitor_rect(monitor)?;
+            if !was_fullscreen {
+                let mut placement = WINDOWPLACEMENT {
+                    length: size_of::<WINDOWPLACEMENT>() as u32,
+                    ..Default::default()
+                };
+                e!(unsafe { GetWindowPlacement(hwnd, &mut placement) })?;
+                update_window(id, |window| window.prev_style = Some(placement))?;
+                unsafe { SetWindowLongW(hwnd, GWL_STYLE, style & !frame) };
+            }
+            e!(unsafe {
+                SetWindowPos(
+                    hwnd,
+                    HWND_TOP,
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                )
+            })?;
+        }
+        None => {
+            if let Some(placement) = update_window(id, |window| window.prev_style.take())? {
+                unsafe {
+                    SetWindowLongW(hwnd, GWL_STYLE, style | frame);
+                    let _ = SetWindowPlacement(hwnd, &placement);
+                    let _ = SetWindowPos(
+                        hwnd,
+                        None,
+                        0,
+                        0,
+                        0,
+                        0,
+                        SWP_NOMOVE
+                            | SWP_NOSIZE
+                            | SWP_NOZORDER
+                            | SWP_NOOWNERZORDER
+                            | SWP_FRAMECHANGED,
+                    );
+                }
+            }
+        }
     }
+    Ok(())
 }
 
-trait ToStyle {
-    fn to_style(&self) -> WINDOW_STYLE;
+/// Check if a window is done being created, its events reach the app only then
+pub(crate) fn created(handle: HWND) -> bool {
+    update_window(WindowId::from(handle), |window| window.created).unwrap_or(false)
 }
 
-impl ToStyle for WindowOptions {
-    fn to_style(&self) -> WINDOW_STYLE {
-        if self.fixed_size {
-            WS_OVERLAPPED | WS_MINIMIZEBOX | WS_SYSMENU | WS_CAPTION
-        } else {
-            WS_OVERLAPPEDWINDOW
+/// Drop the registry entry of a window that failed to be created, the window procedure stops
+/// reading the options before they are freed
+fn discard(options: *const WindowOptions) {
+    let window = WINDOWS.with(|windows| {
+        let mut windows = windows.lock().unwrap();
+        let index = windows
+            .iter()
+            .position(|window| std::ptr::eq(&*window.options, options))?;
+        Some(windows.remove(index))
+    });
+    if let Some(window) = window.filter(|window| window.handle.0 != 0) {
+        unsafe { SetWindowLongPtrW(window.handle, GWLP_USERDATA, 0) };
+        event::forget(window.handle.into());
+    }
+}
+
+/// Create an icon from RGBA pixels, or a cursor when there is a hotspot. The color bitmap has an
+/// alpha channel so the mask is never used, it only has to exist.
+fn create_icon(image: &IconImage, hotspot: Option<(u32, u32)>) -> Result<HICON, Error> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative for a top down bitmap
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    unsafe {
+        let mut bits = std::ptr::null_mut();
+        let color = CreateDIBSection(HDC(0), &info, DIB_RGB_COLORS, &mut bits, None, 0)?;
+        // DIBs store pixels as BGRA
+        let pixels = std::slice::from_raw_parts_mut(bits as *mut u8, image.rgba().len());
+        for (to, from) in pixels.chunks_exact_mut(4).zip(image.rgba().chunks_exact(4)) {
+            to.copy_from_slice(&[from[2], from[1], from[0], from[3]]);
+        }
+        let mask = CreateBitmap(width, height, 1, 1, None);
+        let (x, y) = hotspot.unwrap_or_default();
+        let icon = CreateIconIndirect(&ICONINFO {
+            fIcon: BOOL::from(hotspot.is_none()),
+            xHotspot: x,
+            yHotspot: y,
+            hbmMask: mask,
+            hbmColor: color,
+        });
+        DeleteObject(color);
+        DeleteObject(mask);
+        Ok(icon?)
+    }
+}
+
+/// Replace the large and small icon of a window with the image closest to the system icon sizes,
+/// the icons they replace are destroyed. Each falls back to the other so the title bar and the
+/// taskbar match.
+pub(crate) fn write_icons(
+    handle: HWND,
+    large: Option<&WindowIcon>,
+    small: Option<&WindowIcon>,
+) -> Result<(), Error> {
+    let dpi = unsafe { GetDpiForWindow(handle) };
+    for (kind, icon, metric) in [
+        (ICON_BIG, large.or(small), SM_CXICON),
+        (ICON_SMALL, small.or(large), SM_CXSMICON),
+    ] {
+        let size = unsafe { GetSystemMetricsForDpi(metric, dpi) } as u32;
+        let icon = match icon {
+            Some(icon) => create_icon(icon.best(size), None)?,
+            None => HICON(0),
+        };
+        let previous =
+            unsafe { SendMessageW(handle, WM_SETICON, WPARAM(kind as usize), LPARAM(icon.0)) };
+        if previous.0 != 0 {
+            let _ = unsafe { DestroyIcon(HICON(previous.0)) };
+        }
+    }
+    Ok(())
+}
+
+/// Get the system cursor for a shape, Windows has no zoom, copy or alias cursors
+fn system_cursor(icon: CursorIcon) -> PCWSTR {
+    match icon {
+        CursorIcon::Default
+        | CursorIcon::ContextMenu
+        | CursorIcon::Alias
+        | CursorIcon::Copy
+        | CursorIcon::ZoomIn
+        | CursorIcon::ZoomOut => IDC_ARROW,
+        CursorIcon::Help => IDC_HELP,
+        CursorIcon::Pointer | CursorIcon::Grab => IDC_HAND,
+        CursorIcon::Progress => IDC_APPSTARTING,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::Cell | CursorIcon::Crosshair => IDC_CROSS,
+        CursorIcon::Text | CursorIcon::VerticalText => IDC_IBEAM,
+        CursorIcon::Move | CursorIcon::Grabbing | CursorIcon::AllScroll => IDC_SIZEALL,
+        CursorIcon::NoDrop | CursorIcon::NotAllowed => IDC_NO,
+        CursorIcon::EResize
+        | CursorIcon::WResize
+        | CursorIcon::EwResize
+        | CursorIcon::ColResize => IDC_SIZEWE,
+        CursorIcon::NResize
+        | CursorIcon::SResize
+        | CursorIcon::NsResize
+        | CursorIcon::RowResize => IDC_SIZENS,
+        CursorIcon::NeResize | CursorIcon::SwResize | CursorIcon::NeswResize => IDC_SIZENESW,
+        CursorIcon::NwResize | CursorIcon::SeResize | CursorIcon::NwseResize => IDC_SIZENWSE,
+    }
+}
+
+/// Get the cursor for the client area of a window, a null cursor hides it
+pub(crate) fn client_cursor(handle: HWND) -> Option<HCURSOR> {
+    CURSORS.with(|cursors| {
+        cursors
+            .borrow()
+            .get(&WindowId::from(handle))
+            .map(|(cursor, _)| *cursor)
+    })
+}
+
+/// Replace the cursor of a window and show it right away if the mouse is over the window. A
+/// custom cursor it replaces is destroyed.
+fn write_cursor(handle: HWND, cursor: &Cursor, visible: bool) -> Result<(), Error> {
+    let cursor = match (cursor, visible) {
+        (_, false) => (HCURSOR(0), false),
+        (Cursor::Icon(icon), true) => (unsafe { LoadCursorW(None, system_cursor(*icon))? }, false),
+        (Cursor::Custom(custom), true) => {
+            let icon = create_icon(custom.image(), Some(custom.hotspot()))?;
+            (HCURSOR(icon.0), true)
+        }
+    };
+    let previous =
+        CURSORS.with(|cursors| cursors.borrow_mut().insert(WindowId::from(handle), cursor));
+    unsafe {
+        let mut point = POINT::default();
+        if GetCursorPos(&mut point).is_ok() && WindowFromPoint(point) == handle {
+            // Ask the window which part is under the mouse like a mouse move would
+            let position = ((point.y as u16 as isize) << 16) | point.x as u16 as isize;
+            let hit = SendMessageW(handle, WM_NCHITTEST, WPARAM(0), LPARAM(position));
+            SendMessageW(
+                handle,
+                WM_SETCURSOR,
+                WPARAM(handle.0 as usize),
+                LPARAM(((WM_MOUSEMOVE as isize) << 16) | (hit.0 & 0xffff)),
+            );
+        }
+        if let Some((previous, true)) = previous {
+            let _ = DestroyCursor(previous);
+        }
+    }
+    Ok(())
+}
+
+/// Change the cursor options and set the cursor again
+fn update_cursor(id: WindowId, change: impl FnOnce(&mut WindowOptions)) -> Result<(), Error> {
+    let (cursor, visible) = update_options(id, |options| {
+        change(options);
+        (options.cursor.clone(), options.cursor_visible)
+    })?;
+    write_cursor(HWND::from(id), &cursor, visible)
+}
+
+/// Keep the pointer inside the client area while the window has focus, a locked pointer is held
+/// on the middle pixel. Windows also drops the clip when another window is activated.
+pub(crate) fn write_cursor_clip(handle: HWND, focused: bool) -> Result<(), Error> {
+    let grab = options(handle).map_or(CursorGrab::None, |options| options.cursor_grab);
+    if !focused || grab == CursorGrab::None {
+        if grab != CursorGrab::None || focused {
+            unsafe { ClipCursor(None)? };
         }
+        return Ok(());
+    }
+    let mut rect = RECT::default();
+    let mut corners = [POINT::default(); 2];
+    unsafe {
+        GetClientRect(handle, &mut rect)?;
+        corners[0] = POINT {
+            x: rect.left,
+            y: rect.top,
+        };
+        corners[1] = POINT {
+            x: rect.right,
+            y: rect.bottom,
+        };
+        for corner in &mut corners {
+            ClientToScreen(handle, corner);
+        }
+    }
+    let [top_left, bottom_right] = corners;
+    let clip = match grab {
+        CursorGrab::Locked => {
+            let x = (top_left.x + bottom_right.x) / 2;
+            let y = (top_left.y + bottom_right.y) / 2;
+            unsafe { SetCursorPos(x, y)? };
+            RECT {
+                left: x,
+                top: y,
+                right: x + 1,
+                bottom: y + 1,
+            }
+        }
+        _ => RECT {
+            left: top_left.x,
+            top: top_left.y,
+            right: bottom_right.x,
+            bottom: bottom_right.y,
+        },
+    };
+    unsafe { ClipCursor(Some(&clip))? };
+    Ok(())
+}
+
+/// Change an icon and write both of them again
+fn update_icon(id: WindowId, change: impl FnOnce(&mut WindowOptions)) -> Result<(), Error> {
+    let (large, small) = update_options(id, |options| {
+        change(options);
+        (options.icon.clone(), options.small_icon.clone())
+    })?;
+    write_icons(HWND::from(id), large.as_ref(), small.as_ref())
+}
+
+/// Opacity needs a layered window, click-through additionally needs `WS_EX_TRANSPARENT` so hit
+/// testing skips the window
+fn layered_style(opacity: f32, click_through: bool) -> WINDOW_EX_STYLE {
+    let mut style = WINDOW_EX_STYLE::default();
+    if opacity < 1.0 || click_through {
+        style |= WS_EX_LAYERED;
+    }
+    if click_through {
+        style |= WS_EX_TRANSPARENT;
+    }
+    style
+}
+
+/// Apply the opacity, transparency and click-through settings of a window.
+///
+/// Per pixel alpha comes from DWM blur behind with an empty blur region, DWM then blends the
+/// client area using the alpha `WM_ERASEBKGND` fills it with.
+fn apply_transparency(
+    handle: HWND,
+    opacity: f32,
+    transparent: bool,
+    click_through: bool,
+) -> Result<(), Error> {
+    let layered = layered_style(opacity, click_through);
+    unsafe {
+        let ex_style = GetWindowLongW(handle, GWL_EXSTYLE) as u32;
+        let ex_style = ex_style & !(WS_EX_LAYERED.0 | WS_EX_TRANSPARENT.0) | layered.0;
+        SetWindowLongW(handle, GWL_EXSTYLE, ex_style as i32);
+        // Layered windows stay invisible until their attributes are set
+        if layered.contains(WS_EX_LAYERED) {
+            SetLayeredWindowAttributes(
+                handle,
+                COLORREF(0),
+                (opacity * 255.0).round() as u8,
+                LWA_ALPHA,
+            )?;
+        }
+
+        let region = CreateRectRgn(0, 0, -1, -1);
+        let blur = DWM_BLURBEHIND {
+            dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+            fEnable: transparent.into(),
+            hRgnBlur: region,
+            fTransitionOnMaximized: false.into(),
+        };
+        let result = DwmEnableBlurBehindWindow(handle, &blur);
+        DeleteObject(region);
+        result?;
+
+        InvalidateRect(handle, None, true);
     }
+    Ok(())
+}
+
+/// Change the transparency options of a window and apply them again
+fn set_transparency(id: WindowId, change: impl FnOnce(&mut WindowOptions)) -> Result<(), Error> {
+    let (opacity, transparent, click_through) = update_options(id, |options| {
+        change(options);
+        (options.opacity, options.transparent, options.click_through)
+    })?;
+    apply_transparency(HWND::from(id), opacity, transparent, click_through)
 }
 
 pub struct Window {
     handle: HWND,
-    class: HSTRING,
-    instance: HMODULE,
-    options: WindowOptions,
+    /// Set once `create` returns, the app doesn't get events of a window it doesn't know yet
+    created: bool,
+    /// Boxed so the pointer handed to the window procedure survives the registry growing
+    options: Box<WindowOptions>,
 
+    /// Placement to go back to, only kept while the window is fullscreen
     prev_style: Option<WINDOWPLACEMENT>,
+    fullscreen: Option<Fullscreen>,
+    /// Device switched to another mode for exclusive fullscreen
+    display_mode: Option<HSTRING>,
 
     theme_cookie: Option<EventRegistrationToken>,
 }
@@ -140,275 +712,824 @@ impl Window {
         self.handle = handle;
     }
 
-    pub fn fullscreen(&mut self) {
-        let style = unsafe { GetWindowLongW(self.handle, GWL_STYLE) };
-        if self.prev_style.is_none() {
-            self.prev_style = Some(WINDOWPLACEMENT::default());
-            let mut mi = MONITORINFO {
-                cbSize: size_of::<MONITORINFO>() as u32,
-                ..Default::default()
-            };
-            if unsafe { GetWindowPlacement(self.handle, self.prev_style.as_mut().unwrap()) }.is_ok()
-                && bool::from(unsafe {
-                    GetMonitorInfoW(
-                        MonitorFromWindow(self.handle, MONITOR_DEFAULTTOPRIMARY),
-                        &mut mi,
-                    )
-                })
-            {
-                unsafe {
-                    SetWindowLongW(
-                        self.handle,
-                        GWL_STYLE,
-                        style & !(self.options.to_style().0 as i32),
-                    );
-                    if let Err(err) = SetWindowPos(
-                        self.handle,
-                        HWND_TOP,
-                        mi.rcMonitor.left,
-                        mi.rcMonitor.top,
-                        mi.rcMonitor.right - mi.rcMonitor.left,
-                        mi.rcMonitor.bottom - mi.rcMonitor.top,
-                        SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
-                    ) {
-                        #[cfg(debug_assertions)]
-                        eprintln!("{:?}", err);
-                    }
-                }
+    /// Create the Win32 window, [`WindowContext::create`] adds what every backend shares
+    fn create_native(options: WindowOptions) -> Result<WindowId, Error> {
+        DPI_AWARENESS.call_once(enable_dpi_awareness);
+
+        // Windows with a parent handle are owned windows, they stay above the owner and
+        // minimize with it
+        let owner = match options.owner {
+            Some(owner) if !bool::from(unsafe { IsWindow(HWND::from(owner)) }) => {
+                return Err(Error::invalid_window(owner));
             }
-        } else {
-            unsafe {
-                SetWindowLongW(
-                    self.handle,
-                    GWL_STYLE,
-                    style | self.options.to_style().0 as i32,
-                );
-                let _ = SetWindowPlacement(self.handle, self.prev_style.as_ref().unwrap());
-                let _ = SetWindowPos(
-                    self.handle,
+            owner => owner.map_or(HWND(0), HWND::from),
+        };
+
+        let instance = e!(unsafe { GetModuleHandleW(None) })?;
+        debug_assert!(instance.0 != 0);
+        let class = HSTRING::from(format!("Window-StoryTree-{}", uuid::Uuid::new_v4()));
+
+        let wc = WNDCLASSW {
+            hCursor: e!(unsafe { LoadCursorW(None, IDC_ARROW) })?,
+            hInstance: instance.into(),
+            lpszClassName: class.as_pcwstr(),
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(wnd_proc),
+            ..Default::default()
+        };
+
+        let atom = unsafe { RegisterClassW(&wc) };
+        debug_assert!(atom != 0);
+
+        // `WM_GETMINMAXINFO` is sent before the options reach the window procedure. The
+        // monitor isn't known yet so logical sizes use the system DPI, `WM_DPICHANGED`
+        // rescales the window if it opens on another monitor.
+        let scale_factor = unsafe { GetDpiForSystem() } as f64 / 96.0;
+        let size = options
+            .size
+            .map(|size| size.to_physical(scale_factor).into())
+            .map(|size| match options.fixed_size {
+                true => size,
+                false => options.constraints.constrain(size, false),
+            })
+            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |(w, h)| {
+                (w as i32, h as i32)
+            });
+        let title = HSTRING::from(options.title.as_str());
+        let (style, ex_style) = (options.to_style(), options.to_ex_style());
+        let decorations = options.decorations;
+        let show = options.show;
+
+        // The entry is in the registry before the window exists so `WM_CREATE` can give it its
+        // handle, and the lock is released since creating the window sends it messages that
+        // reach the app
+        let options = Box::new(options);
+        let create_params: *const WindowOptions = &*options;
+        WINDOWS.with(|windows| {
+            windows.lock().unwrap().push(Window {
+                handle: HWND(0),
+                created: false,
+                prev_style: None,
+                fullscreen: None,
+                display_mode: None,
+                options,
+                theme_cookie: None,
+            })
+        });
+
+        let handle = unsafe {
+            CreateWindowExW(
+                ex_style,
+                class.as_pcwstr(),
+                title.as_pcwstr(),
+                style,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                size.0,
+                size.1,
+                owner,
+                None,
+                instance,
+                Some(create_params as *const _),
+            )
+        };
+        if handle.0 == 0 {
+            let err = Error::from(windows::core::Error::from_win32());
+            discard(create_params);
+            return Err(err);
+        }
+
+        let id = WindowId::from(handle);
+        Window::finish_create(id, decorations).inspect_err(|_| {
+            // Taken out of the registry first so `WM_DESTROY` doesn't count it as a closed window
+            discard(create_params);
+            let _ = unsafe { DestroyWindow(handle) };
+        })?;
+        let _ = update_window(id, |window| window.created = true);
+        update_raw_input();
+        if show {
+            Window::show(id);
+        }
+        Ok(id)
+    }
+
+    /// Apply the options `CreateWindowExW` doesn't take to a new window
+    fn finish_create(id: WindowId, decorations: bool) -> Result<(), Error> {
+        let handle = HWND::from(id);
+        if !decorations {
+            // The frame was measured before the options reached the window procedure
+            let _ = unsafe {
+                SetWindowPos(
+                    handle,
                     None,
                     0,
                     0,
                     0,
                     0,
-                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
-                );
-            }
-            self.prev_style = None;
+                    SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+                )
+            };
         }
+        // Copied out of the registry, the calls below send messages to the window procedure
+        let (
+            (opacity, transparent, click_through),
+            (icon, small_icon),
+            (cursor, cursor_visible),
+            (level, theme),
+        ) = update_options(id, |options| {
+            (
+                (options.opacity, options.transparent, options.click_through),
+                (options.icon.clone(), options.small_icon.clone()),
+                (options.cursor.clone(), options.cursor_visible),
+                (options.level, options.theme),
+            )
+        })?;
+        if opacity < 1.0 || transparent || click_through {
+            apply_transparency(handle, opacity, transparent, click_through)?;
+        }
+        write_icons(handle, icon.as_ref(), small_icon.as_ref())?;
+        write_cursor(handle, &cursor, cursor_visible)?;
+        if level == WindowLevel::AlwaysOnBottom {
+            let _ = unsafe {
+                SetWindowPos(
+                    handle,
+                    HWND_BOTTOM,
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                )
+            };
+        }
+        let mut theme_cookie = None;
+        apply_theme(handle, theme, &mut theme_cookie)?;
+        update_window(id, |window| window.theme_cookie = theme_cookie)
     }
 }
 
 impl WindowContext for Window {
     type Builder = Builder;
 
-    fn create(options: WindowOptions) -> Result<isize, Error> {
-        // Either this or thread local. Doing it this way gives the user the power to decide how to
-        // handle multi-threading.
-        WINDOWS.with(move |windows| {
-            let mut windows = windows.lock().unwrap();
-            windows.push(Window {
-                handle: HWND(0),
-                instance: HMODULE(0),
-                class: HSTRING::from(format!("Window-StoryTree-{}", uuid::Uuid::new_v4())),
-                prev_style: None,
-                options,
-                theme_cookie: None,
-            });
-            let window = windows.last_mut().unwrap();
-
-            window.instance = e!(unsafe { GetModuleHandleW(None) })?;
-            debug_assert!(window.instance.0 != 0);
-
-            let wc = WNDCLASSW {
-                hCursor: e!(unsafe { LoadCursorW(None, IDC_ARROW) })?,
-                hInstance: window.instance.into(),
-                lpszClassName: window.class.as_pcwstr(),
-                hIcon: icon(window.options.icon.map(|i| {
-                    let mut path = PathBuf::from(i);
-                    if path.extension().is_none() {
-                        path = path.with_extension("ico");
-                    }
-                    HSTRING::from(path.display().to_string())
-                })),
-                style: CS_HREDRAW | CS_VREDRAW,
-                lpfnWndProc: Some(wnd_proc),
-                ..Default::default()
-            };
-
-            let atom = unsafe { RegisterClassW(&wc) };
-            debug_assert!(atom != 0);
-
-            let size = window
-                .options
-                .size
-                .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |(w, h)| {
-                    (w as i32, h as i32)
-                });
-            unsafe {
-                window.set_handle(CreateWindowExW(
-                    WINDOW_EX_STYLE::default(),
-                    window.class.as_pcwstr(),
-                    HSTRING::from(window.options.title).as_pcwstr(),
-                    window.options.to_style(),
-                    CW_USEDEFAULT,
-                    CW_USEDEFAULT,
-                    size.0,
-                    size.1,
-                    None,
-                    None,
-                    window.instance.clone(),
-                    Some(&window.options as *const _ as *const _),
-                ));
-            }
-
-            if window.handle.0 == 0 {
-                return Err(Error::from(windows::core::Error::from_win32()));
+    fn create(options: WindowOptions) -> Result<WindowId, Error> {
+        build(options, |options| {
+            if select_backend(options.backend)? == Backend::Headless {
+                return headless::Window::create(options);
             }
-            window.set_theme(window.options.theme)?;
-            if window.options.show {
-                Window::show(window.handle.0);
-            }
-            Ok(window.handle.0)
+            Window::create_native(options)
         })
     }
 
-    fn set_theme(&mut self, theme: Theme) -> Result<(), Error> {
-        let state = match theme {
-            Theme::Light => {
-                if let Some(cookie) = self.theme_cookie {
-                    boxed_unwrap!(UI_SETTINGS
-                        .with(|ui_settings| { ui_settings.RemoveColorValuesChanged(cookie) }));
-                }
-                BOOL(0)
-            }
-            Theme::Dark => {
-                if let Some(cookie) = self.theme_cookie {
-                    boxed_unwrap!(UI_SETTINGS
-                        .with(|ui_settings| { ui_settings.RemoveColorValuesChanged(cookie) }));
-                }
-                BOOL(1)
-            }
-            Theme::Auto => {
-                let handle = self.handle;
-                self.theme_cookie = Some(UI_SETTINGS.with(|ui_settings| {
-                    ui_settings.ColorValuesChanged(&TypedEventHandler::new(
-                        move |settings: &Option<UISettings>, _| {
-                            if settings.is_some() {
-                                unsafe {
-                                    DwmSetWindowAttribute(
-                                        handle,
-                                        DWMWINDOWATTRIBUTE(20),
-                                        &is_dark_mode() as *const _ as *const _,
-                                        4,
-                                    )
-                                    .unwrap();
-                                    CallWindowProcW(
-                                        Some(wnd_proc),
-                                        handle,
-                                        WM_ERASEBKGND,
-                                        WPARAM(GetDC(handle).0 as usize),
-                                        LPARAM(0),
-                                    );
-                                    CallWindowProcW(
-                                        Some(wnd_proc),
-                                        handle,
-                                        WM_PAINT,
-                                        WPARAM(0),
-                                        LPARAM(0),
-                                    );
-                                }
-                            }
-                            Ok(())
-                        },
-                    ))
-                })?);
-
-                is_dark_mode()
-            }
-        };
-
-        unsafe {
-            DwmSetWindowAttribute(
-                self.handle,
-                DWMWINDOWATTRIBUTE(20),
-                &state as *const _ as *const c_void,
-                4,
-            )?;
-        }
-
-        Ok(())
-    }
-
     fn builder() -> Box<Self::Builder> {
         Box::new(Builder::new())
     }
 
     /// Show the window
-    fn show(id: isize) {
+    fn show(id: WindowId) {
+        if headless::is_active() {
+            return headless::Window::show(id);
+        }
         unsafe {
-            ShowWindow(HWND(id), SW_SHOWNORMAL);
+            ShowWindow(HWND::from(id), SW_SHOWNORMAL);
+        }
+        if let Err(err) = update_taskbar_tab(HWND::from(id)) {
+            #[cfg(debug_assertions)]
+            eprintln!("{}", err);
         }
     }
 
     /// Hide the window
-    fn hide(id: isize) {
+    fn hide(id: WindowId) {
+        if headless::is_active() {
+            return headless::Window::hide(id);
+        }
         unsafe {
-            ShowWindow(HWND(id), SW_HIDE);
+            ShowWindow(HWND::from(id), SW_HIDE);
         }
     }
 
     /// Minimize the window
-    fn minimize(id: isize) {
+    fn minimize(id: WindowId) {
+        if headless::is_active() {
+            return headless::Window::minimize(id);
+        }
         unsafe {
-            ShowWindow(HWND(id), SW_MINIMIZE);
+            ShowWindow(HWND::from(id), SW_MINIMIZE);
         }
     }
 
     /// Restore the window
-    fn restore(id: isize) {
+    fn restore(id: WindowId) {
+        if headless::is_active() {
+            return headless::Window::restore(id);
+        }
         unsafe {
-            ShowWindow(HWND(id), SW_RESTORE);
+            ShowWindow(HWND::from(id), SW_RESTORE);
         }
     }
 
     /// Maximize the window
-    fn maximize(id: isize) {
+    fn maximize(id: WindowId) {
+        if headless::is_active() {
+            return headless::Window::maximize(id);
+        }
         unsafe {
-            ShowWindow(HWND(id), SW_MAXIMIZE);
+            ShowWindow(HWND::from(id), SW_MAXIMIZE);
+        }
+    }
+
+    fn close(id: WindowId) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::close(id);
+        }
+        // `WM_DESTROY` takes the window out of the registry
+        Ok(e!(unsafe { DestroyWindow(HWND::from(id)) })?)
+    }
+
+    fn client_size(id: WindowId) -> Result<(u32, u32), Error> {
+        if headless::is_active() {
+            return headless::Window::client_size(id);
+        }
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(HWND::from(id), &mut rect)? };
+        Ok((
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        ))
+    }
+
+    fn outer_size(id: WindowId) -> Result<(u32, u32), Error> {
+        if headless::is_active() {
+            return headless::Window::outer_size(id);
+        }
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(HWND::from(id), &mut rect)? };
+        Ok((
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        ))
+    }
+
+    fn position(id: WindowId) -> Result<(i32, i32), Error> {
+        if headless::is_active() {
+            return headless::Window::position(id);
+        }
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(HWND::from(id), &mut rect)? };
+        Ok((rect.left, rect.top))
+    }
+
+    fn title(id: WindowId) -> Result<String, Error> {
+        if headless::is_active() {
+            return headless::Window::title(id);
+        }
+        let hwnd = HWND::from(id);
+        if !bool::from(unsafe { IsWindow(hwnd) }) {
+            return Err(Error::invalid_window(id));
         }
+        let mut title = vec![0u16; unsafe { GetWindowTextLengthW(hwnd) } as usize + 1];
+        let len = unsafe { GetWindowTextW(hwnd, &mut title) };
+        Ok(String::from_utf16_lossy(&title[..len as usize]))
     }
 
-    fn close(id: isize) -> Result<(), Error> {
+    fn exists(id: WindowId) -> bool {
+        if headless::is_active() {
+            return headless::Window::exists(id);
+        }
+        update_window(id, |_| ()).is_ok()
+    }
+
+    fn is_visible(id: WindowId) -> bool {
+        if headless::is_active() {
+            return headless::Window::is_visible(id);
+        }
+        let hwnd = HWND::from(id);
+        unsafe { IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool() }
+    }
+
+    fn is_minimized(id: WindowId) -> bool {
+        if headless::is_active() {
+            return headless::Window::is_minimized(id);
+        }
+        unsafe { IsIconic(HWND::from(id)) }.into()
+    }
+
+    fn is_maximized(id: WindowId) -> bool {
+        if headless::is_active() {
+            return headless::Window::is_maximized(id);
+        }
+        unsafe { IsZoomed(HWND::from(id)) }.into()
+    }
+
+    /// Read the restored bounds from the `WINDOWPLACEMENT`, the one saved when the window went
+    /// fullscreen while it is
+    fn normal_bounds(id: WindowId) -> Result<Bounds, Error> {
+        if headless::is_active() {
+            return headless::Window::normal_bounds(id);
+        }
+        let hwnd = HWND::from(id);
+        let (saved, decorations, tool_window, style, ex_style) = update_window(id, |window| {
+            let options = &window.options;
+            (
+                window.prev_style,
+                options.decorations,
+                options.tool_window,
+                options.to_style(),
+                options.to_ex_style(),
+            )
+        })?;
+        let placement = match saved {
+            Some(placement) => placement,
+            None => {
+                let mut placement = WINDOWPLACEMENT {
+                    length: size_of::<WINDOWPLACEMENT>() as u32,
+                    ..Default::default()
+                };
+                e!(unsafe { GetWindowPlacement(hwnd, &mut placement) })?;
+                placement
+            }
+        };
+
+        // The normal position is relative to the work area unless the window is a tool window
+        let mut rect = placement.rcNormalPosition;
+        let mut info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        if !tool_window && bool::from(unsafe { GetMonitorInfoW(monitor, &mut info) }) {
+            let (x, y) = (
+                info.rcWork.left - info.rcMonitor.left,
+                info.rcWork.top - info.rcMonitor.top,
+            );
+            rect.left += x;
+            rect.right += x;
+            rect.top += y;
+            rect.bottom += y;
+        }
+
+        // Fullscreen removed the frame styles, measure the frame the options give instead
+        let mut frame = RECT::default();
+        if decorations {
+            let dpi = unsafe { GetDpiForWindow(hwnd) };
+            let _ = unsafe { AdjustWindowRectExForDpi(&mut frame, style, false, ex_style, dpi) };
+        }
+        Ok((
+            Some((rect.left, rect.top)),
+            (
+                ((rect.right - rect.left) - (frame.right - frame.left)).max(1) as u32,
+                ((rect.bottom - rect.top) - (frame.bottom - frame.top)).max(1) as u32,
+            ),
+        ))
+    }
+
+    fn is_fullscreen(id: WindowId) -> bool {
+        if headless::is_active() {
+            return headless::Window::is_fullscreen(id);
+        }
+        // Fullscreen is emulated by resizing the window over the monitor, the previous placement
+        // is only kept while it is fullscreen
+        WINDOWS.with(|windows| {
+            windows
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|window| WindowId::from(window.handle) == id && window.prev_style.is_some())
+        })
+    }
+
+    fn fullscreen(id: WindowId) -> Option<Fullscreen> {
+        if headless::is_active() {
+            return headless::Window::fullscreen(id);
+        }
+        update_window(id, |window| window.fullscreen.clone())
+            .ok()
+            .flatten()
+    }
+
+    /// Fullscreen removes the frame and covers the monitor, exclusive fullscreen switches the
+    /// monitor's mode first. The mode of a monitor no longer covered is put back.
+    fn set_fullscreen(id: WindowId, fullscreen: Option<Fullscreen>) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_fullscreen(id, fullscreen);
+        }
+        let (was_fullscreen, frame, current_mode) = update_window(id, |window| {
+            (
+                window.prev_style.is_some(),
+                window.options.to_style().0 as i32,
+                window.display_mode.clone(),
+            )
+        })?;
+        let monitor = fullscreen.as_ref().map(|mode| match mode {
+            Fullscreen::Borderless(Some(monitor)) => HMONITOR(monitor.handle),
+            Fullscreen::Exclusive(mode) => HMONITOR(mode.monitor().handle),
+            Fullscreen::Borderless(None) => unsafe {
+                MonitorFromWindow(HWND::from(id), MONITOR_DEFAULTTONEAREST)
+            },
+        });
+        // Checked before the mode changes so a disconnected monitor leaves the display alone
+        if let Some(monitor) = monitor {
+            monitor_rect(monitor)?;
+        }
+        let device = match &fullscreen {
+            Some(Fullscreen::Exclusive(mode)) => Some(set_video_mode(mode)?),
+            _ => None,
+        };
+        let result = apply_fullscreen(id, monitor, was_fullscreen, frame).and_then(|()| {
+            update_window(id, |window| {
+                window.fullscreen = fullscreen;
+                std::mem::replace(&mut window.display_mode, device.clone())
+            })
+        });
+        let previous = match result {
+            Ok(previous) => previous,
+            Err(err) => {
+                // A device the window already switched stays switched until it leaves fullscreen
+                if let Some(device) = device.as_ref().filter(|_| device != current_mode) {
+                    reset_video_mode(device);
+                }
+                return Err(err);
+            }
+        };
+        if let Some(previous) = previous.filter(|previous| Some(previous) != device.as_ref()) {
+            reset_video_mode(&previous);
+        }
+        Ok(())
+    }
+
+    fn scale_factor(id: WindowId) -> f64 {
+        if headless::is_active() {
+            return headless::Window::scale_factor(id);
+        }
+        // Zero for invalid windows
+        match unsafe { GetDpiForWindow(HWND::from(id)) } {
+            0 => 1.0,
+            dpi => dpi as f64 / 96.0,
+        }
+    }
+
+    fn set_title(id: WindowId, title: String) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_title(id, title);
+        }
+        unsafe { SetWindowTextW(HWND::from(id), &HSTRING::from(title.as_str()))? };
         WINDOWS.with(|windows| {
             let mut windows = windows.lock().unwrap();
-            if let Some(index) = windows.iter().position(|window| window.handle.0 == id) {
-                windows.remove(index);
+            if let Some(window) = windows
+                .iter_mut()
+                .find(|window| WindowId::from(window.handle) == id)
+            {
+                window.options.title = title;
             }
         });
-        Ok(e!(unsafe { CloseWindow(HWND(id)) })?)
+        Ok(())
+    }
+
+    fn set_theme(id: WindowId, theme: Theme) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_theme(id, theme);
+        }
+        // The registration is taken out so no lock is held while the title bar changes
+        let mut theme_cookie = update_window(id, |window| {
+            window.options.theme = theme;
+            window.theme_cookie.take()
+        })?;
+        let result = apply_theme(HWND::from(id), theme, &mut theme_cookie);
+        update_window(id, |window| window.theme_cookie = theme_cookie)?;
+        result
     }
-}
 
-/// TODO: Automatic loading of other file formats?
-pub fn icon(path: Option<HSTRING>) -> HICON {
-    let result = HICON(path.map_or(0, |icon| {
+    fn set_size(id: WindowId, width: u32, height: u32) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_size(id, width, height);
+        }
+        let hwnd = HWND::from(id);
+        // Grow the requested client size by whatever the frame currently takes up
+        let (mut outer, mut client) = (RECT::default(), RECT::default());
         unsafe {
-            LoadImageW(
+            GetWindowRect(hwnd, &mut outer)?;
+            GetClientRect(hwnd, &mut client)?;
+        }
+        let frame_width = (outer.right - outer.left) - (client.right - client.left);
+        let frame_height = (outer.bottom - outer.top) - (client.bottom - client.top);
+        unsafe {
+            SetWindowPos(
+                hwnd,
                 None,
-                icon.as_pcwstr(),
-                IMAGE_ICON,
                 0,
                 0,
-                LR_DEFAULTSIZE | LR_LOADFROMFILE | LR_SHARED | LR_LOADTRANSPARENT,
-            )
+                width as i32 + frame_width,
+                height as i32 + frame_height,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?;
         }
-        .unwrap_or_else(|err| {
-            #[cfg(debug_assertions)]
-            eprintln!("{}", Error::from(err));
-            HANDLE(0)
+        Ok(())
+    }
+
+    fn set_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_position(id, x, y);
+        }
+        unsafe {
+            SetWindowPos(
+                HWND::from(id),
+                None,
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn center_on_monitor(id: WindowId) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::center_on_monitor(id);
+        }
+        let hwnd = HWND::from(id);
+        let mut mi = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !bool::from(unsafe {
+            GetMonitorInfoW(MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST), &mut mi)
+        }) {
+            return Err(Error::from(windows::core::Error::from_win32()));
+        }
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(hwnd, &mut rect)? };
+
+        let work = mi.rcWork;
+        Window::set_position(
+            id,
+            work.left + ((work.right - work.left) - (rect.right - rect.left)) / 2,
+            work.top + ((work.bottom - work.top) - (rect.bottom - rect.top)) / 2,
+        )
+    }
+
+    fn size_constraints(id: WindowId) -> Result<SizeConstraints, Error> {
+        if headless::is_active() {
+            return headless::Window::size_constraints(id);
+        }
+        WINDOWS.with(|windows| {
+            windows
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|window| WindowId::from(window.handle) == id)
+                .map(|window| window.options.constraints)
+                .ok_or_else(|| Error::invalid_window(id))
         })
-        .0
-    }));
-    result
+    }
+
+    /// The window procedure reads the constraints from the options on every resize, changing
+    /// them here is enough for `WM_GETMINMAXINFO` and `WM_SIZING` to pick them up
+    fn set_size_constraints(id: WindowId, constraints: SizeConstraints) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_size_constraints(id, constraints);
+        }
+        let fixed_size = WINDOWS.with(|windows| {
+            windows
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .find(|window| WindowId::from(window.handle) == id)
+                .map(|window| {
+                    window.options.constraints = constraints;
+                    window.options.fixed_size
+                })
+                .ok_or_else(|| Error::invalid_window(id))
+        })?;
+
+        let size = Window::client_size(id)?;
+        let fitted = constraints.constrain(size, false);
+        if !fixed_size && fitted != size {
+            Window::set_size(id, fitted.0, fitted.1)?;
+        }
+        Ok(())
+    }
+
+    fn set_cursor(id: WindowId, cursor: Cursor) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_cursor(id, cursor);
+        }
+        update_cursor(id, |options| options.cursor = cursor)
+    }
+
+    fn set_cursor_visible(id: WindowId, visible: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_cursor_visible(id, visible);
+        }
+        update_cursor(id, |options| options.cursor_visible = visible)
+    }
+
+    fn set_cursor_grab(id: WindowId, grab: CursorGrab) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_cursor_grab(id, grab);
+        }
+        update_options(id, |options| options.cursor_grab = grab)?;
+        update_raw_input();
+        let handle = HWND::from(id);
+        write_cursor_clip(handle, unsafe { GetFocus() } == handle)
+    }
+
+    fn set_raw_mouse_motion(id: WindowId, enabled: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_raw_mouse_motion(id, enabled);
+        }
+        update_options(id, |options| options.raw_mouse_motion = enabled)?;
+        update_raw_input();
+        Ok(())
+    }
+
+    fn set_cursor_position(id: WindowId, x: i32, y: i32) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_cursor_position(id, x, y);
+        }
+        let mut point = POINT { x, y };
+        if !bool::from(unsafe { ClientToScreen(HWND::from(id), &mut point) }) {
+            return Err(Error::invalid_window(id));
+        }
+        Ok(e!(unsafe { SetCursorPos(point.x, point.y) })?)
+    }
+
+    fn set_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_icon(id, icon);
+        }
+        update_icon(id, |options| options.icon = icon)
+    }
+
+    fn set_small_icon(id: WindowId, icon: Option<WindowIcon>) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_small_icon(id, icon);
+        }
+        update_icon(id, |options| options.small_icon = icon)
+    }
+
+    fn set_opacity(id: WindowId, opacity: f32) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_opacity(id, opacity);
+        }
+        set_transparency(id, |options| options.opacity = opacity)
+    }
+
+    fn set_transparent(id: WindowId, transparent: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_transparent(id, transparent);
+        }
+        set_transparency(id, |options| options.transparent = transparent)
+    }
+
+    fn set_click_through(id: WindowId, click_through: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_click_through(id, click_through);
+        }
+        set_transparency(id, |options| options.click_through = click_through)
+    }
+
+    /// Topmost is the `WS_EX_TOPMOST` style, always on bottom is kept up by the window procedure
+    /// moving the window back to the bottom
+    fn set_level(id: WindowId, level: WindowLevel) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_level(id, level);
+        }
+        update_options(id, |options| options.level = level)?;
+        unsafe {
+            SetWindowPos(
+                HWND::from(id),
+                insert_after(level),
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn set_tool_window(id: WindowId, tool_window: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_tool_window(id, tool_window);
+        }
+        update_options(id, |options| options.tool_window = tool_window)?;
+        let hwnd = HWND::from(id);
+        unsafe {
+            let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+            let ex_style = match tool_window {
+                true => ex_style | WS_EX_TOOLWINDOW.0,
+                false => ex_style & !WS_EX_TOOLWINDOW.0,
+            };
+            SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style as i32);
+            SetWindowPos(
+                hwnd,
+                None,
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?;
+            // The taskbar only checks the style when a window is shown
+            if IsWindowVisible(hwnd).as_bool() {
+                ShowWindow(hwnd, SW_HIDE);
+                ShowWindow(hwnd, SW_SHOWNA);
+            }
+        }
+        update_taskbar_tab(hwnd)
+    }
+
+    fn set_skip_taskbar(id: WindowId, skip_taskbar: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_skip_taskbar(id, skip_taskbar);
+        }
+        let tool_window = update_options(id, |options| {
+            options.skip_taskbar = skip_taskbar;
+            options.tool_window
+        })?;
+        match tool_window {
+            true => Ok(()),
+            false => set_taskbar_tab(HWND::from(id), !skip_taskbar),
+        }
+    }
+
+    fn raw_window_handle(id: WindowId) -> Result<RawWindowHandle, HandleError> {
+        if headless::is_active() {
+            return headless::Window::raw_window_handle(id);
+        }
+        let hwnd = HWND::from(id);
+        let hwnd_value = NonZeroIsize::new(hwnd.0)
+            .filter(|_| bool::from(unsafe { IsWindow(hwnd) }))
+            .ok_or(HandleError::Unavailable)?;
+        let mut handle = Win32WindowHandle::new(hwnd_value);
+        handle.hinstance = NonZeroIsize::new(unsafe { GetWindowLongPtrW(hwnd, GWLP_HINSTANCE) });
+        Ok(handle.into())
+    }
+
+    fn raw_display_handle(id: WindowId) -> Result<RawDisplayHandle, HandleError> {
+        if headless::is_active() {
+            return headless::Window::raw_display_handle(id);
+        }
+        Ok(WindowsDisplayHandle::new().into())
+    }
+
+    /// Stretch the pixels over the client area with GDI, a 32 bit DIB uses the same `0xAARRGGBB`
+    /// layout so no conversion is needed
+    fn present(id: WindowId, pixels: &[u32], width: u32, height: u32) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::present(id, pixels, width, height);
+        }
+        let hwnd = HWND::from(id);
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(hwnd, &mut rect)? };
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width as i32,
+                // Negative for a top down bitmap
+                biHeight: -(height as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe {
+            let hdc = GetDC(hwnd);
+            // Nearest neighbor like the other backends, the default mode blends rows away
+            SetStretchBltMode(hdc, COLORONCOLOR);
+            let lines = StretchDIBits(
+                hdc,
+                0,
+                0,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                Some(pixels.as_ptr() as *const c_void),
+                &info,
+                DIB_RGB_COLORS,
+                SRCCOPY,
+            );
+            let error = (lines == 0).then(windows::core::Error::from_win32);
+            ReleaseDC(hwnd, hdc);
+            if let Some(error) = error {
+                return Err(Error::from(error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Windows has no pagers, the option is only stored
+    fn set_skip_pager(id: WindowId, skip_pager: bool) -> Result<(), Error> {
+        if headless::is_active() {
+            return headless::Window::set_skip_pager(id, skip_pager);
+        }
+        update_options(id, |options| options.skip_pager = skip_pager)
+    }
 }